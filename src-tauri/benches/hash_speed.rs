@@ -0,0 +1,55 @@
+//! Per-image time of each perceptual hash algorithm, to help choose `hash_algorithm`.
+//! "decode + hash" is what ranking init pays per photo; "hash only" isolates the algorithm.
+//!
+//! Run with `cargo bench --bench hash_speed`.
+
+use image::{DynamicImage, RgbImage};
+use photo_tinder::hashing::{compute_hash, hash_image, HashAlgorithm};
+use std::time::{Duration, Instant};
+
+/// Synthetic 12 MP photo, roughly a camera JPEG
+const WIDTH: u32 = 4000;
+const HEIGHT: u32 = 3000;
+const ITERATIONS: u32 = 5;
+
+const ALGORITHMS: [(&str, HashAlgorithm); 3] = [
+    ("ahash", HashAlgorithm::Ahash),
+    ("dhash", HashAlgorithm::Dhash),
+    ("phash", HashAlgorithm::Phash),
+];
+
+fn synthetic_photo() -> DynamicImage {
+    let img = RgbImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        let (fx, fy) = (x as f64, y as f64);
+        let v = 128.0 + 60.0 * (fx / 97.0).sin() * (fy / 61.0).cos() + 40.0 * ((fx + fy) / 233.0).sin();
+        image::Rgb([v as u8, (v * 0.8) as u8, (255.0 - v) as u8])
+    });
+    DynamicImage::ImageRgb8(img)
+}
+
+fn average<F: FnMut()>(mut run: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let img = synthetic_photo();
+    let path = std::env::temp_dir().join(format!("photo-tinder-hash-bench-{}.jpg", std::process::id()));
+    img.save(&path).expect("write benchmark JPEG");
+
+    println!("{}x{} JPEG, average of {} runs", WIDTH, HEIGHT, ITERATIONS);
+    for (name, algorithm) in ALGORITHMS {
+        let hash_only = average(|| {
+            hash_image(&img, algorithm);
+        });
+        let with_decode = average(|| {
+            compute_hash(&path, algorithm).expect("hash benchmark JPEG");
+        });
+        println!("{:<6} hash only {:>8.1?}  decode + hash {:>8.1?}", name, hash_only, with_decode);
+    }
+
+    let _ = std::fs::remove_file(&path);
+}
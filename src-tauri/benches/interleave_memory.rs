@@ -0,0 +1,96 @@
+//! Peak memory of interleaving scanned records: cloning every record (the old
+//! approach) versus moving them out of the per-folder vectors.
+//!
+//! Run with `cargo bench --bench interleave_memory`.
+
+use photo_tinder::image_manager::interleave_records;
+use photo_tinder::state::ImageRecord;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// Synthetic tree: 10 source folders of 10k photos each
+const FOLDERS: usize = 10;
+const FILES_PER_FOLDER: usize = 10_000;
+
+/// System allocator that tracks live and peak heap bytes
+struct PeakAlloc;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(live, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: PeakAlloc = PeakAlloc;
+
+fn synthetic_tree() -> Vec<Vec<ImageRecord>> {
+    (0..FOLDERS)
+        .map(|folder| {
+            (0..FILES_PER_FOLDER)
+                .map(|i| ImageRecord {
+                    id: format!("{:012x}", folder * FILES_PER_FOLDER + i),
+                    source_folder: format!("/home/user/Pictures/shoots/shoot-{:02}", folder),
+                    relative_path: format!("2023/day-{:03}/IMG_{:05}.jpg", i / 100, i),
+                    timestamp: None,
+                    size: 0,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The interleave as it was before records were moved
+fn interleave_by_clone(folder_images: Vec<Vec<ImageRecord>>) -> Vec<ImageRecord> {
+    let mut interleaved = Vec::new();
+    let max_len = folder_images.iter().map(|v| v.len()).max().unwrap_or(0);
+
+    for i in 0..max_len {
+        for folder_imgs in &folder_images {
+            if i < folder_imgs.len() {
+                interleaved.push(folder_imgs[i].clone());
+            }
+        }
+    }
+
+    interleaved
+}
+
+/// Report the peak heap growth above the already-built tree while interleaving it
+fn measure(name: &str, interleave: fn(Vec<Vec<ImageRecord>>) -> Vec<ImageRecord>) {
+    let tree = synthetic_tree();
+    let baseline = LIVE.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+
+    let start = Instant::now();
+    let records = interleave(tree);
+    let elapsed = start.elapsed();
+
+    let peak = PEAK.load(Ordering::Relaxed) - baseline;
+    println!(
+        "{:<6} {} records  peak +{:.1} MiB  {:.1?}",
+        name,
+        records.len(),
+        peak as f64 / (1024.0 * 1024.0),
+        elapsed
+    );
+}
+
+fn main() {
+    measure("clone", interleave_by_clone);
+    measure("move", interleave_records);
+}
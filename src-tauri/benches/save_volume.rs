@@ -0,0 +1,104 @@
+//! Bytes written to state.json during a rapid culling session: saving on every
+//! swipe (the old behaviour) versus the debounced background saver.
+//!
+//! Both runs go through the app's own save paths (`PersistentState::save`, and
+//! `AppState::save_later`/`flush_state`) against a state.json in a temp HOME, and
+//! add up the size of every file actually written. Time is simulated: the saver's
+//! wake-ups are replayed between swipes instead of sleeping.
+//!
+//! Run with `cargo bench --bench save_volume`.
+
+use photo_tinder::config::Config;
+use photo_tinder::state::{AppState, PersistentState, PhotoRating, SAVE_DEBOUNCE};
+use std::fs;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// Collection size: every photo decided once and rated in the ranking session
+const PHOTOS: usize = 10_000;
+/// A session of quick swipes, one every SWIPE_INTERVAL
+const SWIPES: usize = 500;
+const SWIPE_INTERVAL: Duration = Duration::from_millis(150);
+
+fn synthetic_state() -> PersistentState {
+    let mut state = PersistentState::default();
+    for i in 0..PHOTOS {
+        let id = format!("{:012x}", i);
+        state.decisions.insert(id.clone(), "accepted".to_string());
+        state.moved_files.insert(id.clone(), format!("/home/user/Pictures/accepted/IMG_{:05}.jpg", i));
+        state.original_paths.insert(id.clone(), format!("/home/user/Pictures/shoots/IMG_{:05}.jpg", i));
+        state.ranking.ratings.insert(id, PhotoRating::default());
+    }
+    state
+}
+
+/// Size of state.json as just written
+fn written_bytes() -> u64 {
+    fs::metadata(Config::state_path()).map(|meta| meta.len()).unwrap_or(0)
+}
+
+/// A swipe's change to the state: one more photo decided
+fn swipe(state: &AppState, n: usize) {
+    let id = format!("new-{:05}", n);
+    state.persistent.lock().unwrap().record_decision(&id, "rejected", None, n as f64);
+}
+
+/// Save synchronously after every swipe. Returns (saves, bytes written).
+fn save_every_swipe(state: &AppState) -> (usize, u64) {
+    let mut bytes = 0;
+    for n in 0..SWIPES {
+        swipe(state, n);
+        state.persistent.lock().unwrap().save().unwrap();
+        bytes += written_bytes();
+    }
+    (SWIPES, bytes)
+}
+
+/// Mark the state dirty on every swipe; the saver flushes once per SAVE_DEBOUNCE,
+/// plus the final flush on exit. Returns (saves, bytes written).
+fn save_debounced(state: &AppState) -> (usize, u64) {
+    let (mut saves, mut bytes) = (0, 0);
+    let mut flush = |state: &AppState| {
+        let pending = state.state_dirty.load(Ordering::SeqCst);
+        state.flush_state().unwrap();
+        if pending {
+            saves += 1;
+            bytes += written_bytes();
+        }
+    };
+
+    let mut next_wake = SAVE_DEBOUNCE;
+    for n in 0..SWIPES {
+        let at = SWIPE_INTERVAL * n as u32;
+        while next_wake <= at {
+            flush(state);
+            next_wake += SAVE_DEBOUNCE;
+        }
+        swipe(state, n);
+        state.save_later();
+    }
+    flush(state);
+    (saves, bytes)
+}
+
+fn main() {
+    // Keep the real config and state out of it
+    let home = std::env::temp_dir().join(format!("photo-tinder-save-volume-{}", std::process::id()));
+    fs::create_dir_all(&home).unwrap();
+    std::env::set_var("HOME", &home);
+    std::env::remove_var("XDG_CONFIG_HOME");
+
+    let mib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+    println!("{} swipes every {:?} over {} decided photos:", SWIPES, SWIPE_INTERVAL, PHOTOS);
+
+    let state = AppState::new();
+    *state.persistent.lock().unwrap() = synthetic_state();
+    let (saves, bytes) = save_every_swipe(&state);
+    println!("  every swipe  {:>4} saves  {:>8.1} MiB written", saves, mib(bytes));
+
+    *state.persistent.lock().unwrap() = synthetic_state();
+    let (saves, bytes) = save_debounced(&state);
+    println!("  debounced    {:>4} saves  {:>8.1} MiB written", saves, mib(bytes));
+
+    fs::remove_dir_all(&home).unwrap();
+}
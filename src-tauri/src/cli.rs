@@ -0,0 +1,151 @@
+//! Headless command line mode for scripted culling without the GUI.
+//! Uses the same config and state.json as the app, so close the app while it runs.
+
+use crate::config::Config;
+use crate::image_manager::{
+    build_pending_indices, move_image, record_move, scan_source_folders, sync_record_ids,
+};
+use crate::sharpness::{sharpness, DEFAULT_BLUR_THRESHOLD};
+use crate::state::PersistentState;
+use clap::{Parser, Subcommand};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Save state.json after this many decisions instead of after each one
+const SAVE_INTERVAL: usize = 50;
+
+#[derive(Parser)]
+#[command(name = "photo-tinder", about = "Photo Tinder - Image Triage and Ranking")]
+pub struct Cli {
+    /// Run a command without starting the GUI
+    #[arg(long)]
+    pub headless: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Scan the source folders and print how many photos are decided and pending
+    Scan,
+    /// Decide pending photos automatically
+    TriageAuto {
+        /// Reject photos whose sharpness is below --threshold
+        #[arg(long)]
+        reject_blurry: bool,
+        /// Laplacian variance below which a photo counts as blurry
+        #[arg(long, default_value_t = DEFAULT_BLUR_THRESHOLD)]
+        threshold: f64,
+    },
+}
+
+/// Entry point for `--headless`; returns the process exit code
+pub fn run() -> i32 {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Some(Command::Scan) => scan(),
+        Some(Command::TriageAuto { reject_blurry, threshold }) => triage_auto(reject_blurry, threshold),
+        None => Err("--headless needs a command: scan | triage-auto".to_string()),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+fn load_valid_config() -> Result<Config, String> {
+    let config = Config::load();
+    if !config.is_valid() {
+        return Err(format!("Config at {} needs source, accepted and rejected folders",
+            Config::config_path().display()));
+    }
+    Ok(config)
+}
+
+fn scan() -> Result<(), String> {
+    let config = load_valid_config()?;
+    let mut persistent = PersistentState::load();
+
+    let records = scan_source_folders(&config);
+    if sync_record_ids(&mut persistent, &records, &config) {
+        persistent.save()?;
+    }
+    let pending = build_pending_indices(&records, &persistent.decisions);
+
+    println!("{} photos: {} decided, {} pending", records.len(), records.len() - pending.len(), pending.len());
+    Ok(())
+}
+
+fn triage_auto(reject_blurry: bool, threshold: f64) -> Result<(), String> {
+    if !reject_blurry {
+        return Err("Nothing to do: pass --reject-blurry".to_string());
+    }
+
+    let config = load_valid_config()?;
+    let mut persistent = PersistentState::load();
+
+    let records = scan_source_folders(&config);
+    if sync_record_ids(&mut persistent, &records, &config) {
+        persistent.save()?;
+    }
+    let pending = build_pending_indices(&records, &persistent.decisions);
+
+    let mut rejected = 0;
+    let mut kept = 0;
+    let mut failed = 0;
+
+    for &idx in &pending {
+        let record = &records[idx];
+        let path = record.full_path();
+
+        let score = match sharpness(&path) {
+            Ok(score) => score,
+            Err(e) => {
+                eprintln!("Warning: {}", e);
+                failed += 1;
+                continue;
+            }
+        };
+        if score >= threshold {
+            kept += 1;
+            continue;
+        }
+
+        // Same bookkeeping as a left swipe, so the GUI can undo these (or, in a dry
+        // run, carry them out with commit_dry_run)
+        match move_image(record, "rejected", &config) {
+            Ok(moved) => {
+                if let Some(moved) = moved {
+                    record_move(record, "rejected", moved, &config, &mut persistent);
+                }
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64();
+                persistent.record_decision(&record.id, "rejected", Some("blurry".to_string()), now);
+
+                // Moves are in moves.log meanwhile, so an interrupted run can be repaired
+                rejected += 1;
+                if rejected % SAVE_INTERVAL == 0 {
+                    persistent.save()?;
+                }
+                let verb = if config.dry_run { "Would reject" } else { "Rejected" };
+                println!("{} {} (sharpness {:.1})", verb, path.display(), score);
+            }
+            Err(e) => {
+                eprintln!("Warning: {}", e);
+                failed += 1;
+            }
+        }
+    }
+
+    if rejected > 0 {
+        persistent.save()?;
+    }
+
+    println!("{} pending photos: {} rejected as blurry, {} kept, {} failed",
+        pending.len(), rejected, kept, failed);
+    Ok(())
+}
@@ -1,17 +1,47 @@
 //! Tauri commands - Functions callable from JavaScript
 
-use crate::config::{Config, QuickAccessLocation};
-use crate::hashing::{compute_dhash, cluster_photos};
+use crate::config::{
+    active_profile, normalize_extension, set_active_profile, validate_profile_name, Config,
+    DestinationOverride, QuickAccessLocation, RatingConfig, MAX_PRELOAD_COUNT,
+};
+use crate::export::{
+    read_ratings_json, star_ratings, write_leaderboard_csv, write_leaderboard_html,
+    write_ratings_json, ExportRow, RatingsBackup,
+};
+use crate::hashing::{
+    compute_hash, cluster_photos, hamming_distance, matches_cluster, HashAlgorithm,
+    MAX_HAMMING_THRESHOLD,
+};
 use crate::image_manager::{
-    browse_directory, build_pending_indices, get_current_record, move_image,
-    scan_accepted_photos, scan_source_folders, undo_move,
+    available_space, browse_directory, build_pending_indices, folder_status, generate_image_id,
+    get_current_record, has_supported_extension, index_files_by_name, move_image, record_move,
+    remove_extra_copies, scan_accepted_photos, scan_probed_source_folders, source_folder_statuses,
+    sync_record_ids, undo_copy, undo_move, ExcludeFilter, FolderStatus, MovedImage,
+    TRASH_TOKEN_PREFIX,
+};
+use crate::metadata::{image_dimensions, read_exif_summary, write_exif_rating};
+use crate::ranking::{
+    advance_bracket, best_in_cluster, estimate_remaining_comparisons, fill_pair_queue,
+    finalize_cluster, fold_comparison, glicko_adjust_both, glicko_update,
+    get_conservative_score, initialize_ratings, invalidate_queued_pairs, pair_key,
+    record_recent_pair, seed_bracket, select_triple, tournament_pair, win_probability,
+    PairStrategy,
 };
-use crate::ranking::{glicko_update, select_pair, get_conservative_score, initialize_ratings};
-use crate::state::{AppState, Cluster, ComparisonRecord, save_photo_hashes};
+use crate::state::{
+    AppState, CachedDimensions, Cluster, ComparisonRecord, FolderScan, ImageRecord,
+    PersistentState, PhotoHash, PhotoRating, RankedRemoval, RankingState, RemovedFolder, StateRecovery,
+    MAX_COMPARISON_HISTORY, MAX_HISTORY, load_photo_hashes, load_triage_hashes, save_photo_hashes, save_triage_hashes,
+};
+use crate::thumbnails::cached_thumbnail;
+use crate::watcher::SourceWatcher;
+use rayon::prelude::*;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 // ============================================================================
 // Response types
@@ -27,18 +57,28 @@ pub struct ImageInfo {
     pub filename: Option<String>,
     pub source_folder: Option<String>,
     pub file_path: Option<String>,
+    pub file_exists: bool, // checked as this was returned, after the EXIF read; false with no photo
+    pub reason: Option<String>,
+    pub captured_at: Option<String>,
+    pub camera_model: Option<String>,
     pub stats: Stats,
     pub message: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct Stats {
     pub total: usize,
     pub pending: usize,
     pub accepted: usize,
     pub rejected: usize,
+    pub favorites: usize,
     pub skipped: usize,
+    pub deferred: usize, // skipped max_skips times, out of the queue
     pub processed: usize,
+    // Sizes of the scanned source photos; photos moved out before the last scan count as 0
+    pub total_bytes: u64,
+    pub accepted_bytes: u64, // includes favorites
+    pub rejected_bytes: u64,
 }
 
 #[derive(Serialize)]
@@ -54,6 +94,88 @@ pub struct UndoResult {
     pub image_id: Option<String>,
 }
 
+#[derive(Serialize)]
+pub struct SwipeBatchResult {
+    pub image_ids: Vec<String>,   // decided photos, in queue order
+    pub decision: Option<String>, // decision recorded (None if nothing was decided)
+    pub error: Option<String>,    // set if a move failed and the batch stopped early
+}
+
+#[derive(Serialize)]
+pub struct UndoManyResult {
+    pub undone: usize,
+    pub image_ids: Vec<String>, // most recent decision first
+    pub error: Option<String>,  // set if a file restore failed and the batch stopped early
+}
+
+#[derive(Serialize)]
+pub struct DestinationSpace {
+    pub folder: String,
+    pub available_bytes: Option<u64>, // None if the volume couldn't be queried
+}
+
+#[derive(Serialize)]
+pub struct SpaceReport {
+    pub destinations: Vec<DestinationSpace>,
+    pub pending_bytes: u64, // total size of the photos still to triage
+}
+
+#[derive(Serialize)]
+pub struct PlannedMove {
+    pub id: String,
+    pub decision: String,
+    pub source: String,
+    pub destination: String, // where the photo would have gone at decision time
+}
+
+#[derive(Serialize)]
+pub struct DryRunCommitResult {
+    pub moved: usize,
+    pub dropped: usize,      // plans whose photo is gone or whose decision no longer moves it
+    pub failed: Vec<String>, // source files that could not be moved; their plans are kept
+}
+
+#[derive(Serialize)]
+pub struct TriageResetResult {
+    pub cleared: usize,      // decisions forgotten
+    pub restored: usize,     // files moved back to their source folder
+    pub failed: Vec<String>, // files that could not be restored; their decisions are kept
+}
+
+#[derive(Serialize)]
+pub struct RepairAction {
+    pub image_id: String,
+    pub action: String, // "restored_original" | "restored_moved" | "dropped_moved" | "dropped_original"
+    pub path: String,
+}
+
+#[derive(Serialize)]
+pub struct RepairReport {
+    pub checked: usize,
+    pub actions: Vec<RepairAction>,
+}
+
+#[derive(Serialize)]
+pub struct MissingMovedFile {
+    pub image_id: String,
+    pub path: String,             // recorded location that no longer exists
+    pub relocated: Option<String>, // new location, if it was found by name and the record updated
+}
+
+#[derive(Serialize)]
+pub struct MovedFilesReport {
+    pub checked: usize,
+    pub missing: Vec<MissingMovedFile>,
+}
+
+#[derive(Serialize)]
+pub struct EtaEstimate {
+    pub pending: usize,
+    pub seconds_per_decision: Option<f64>,
+    pub seconds_remaining: Option<u64>,
+    pub human: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct PairInfo {
     pub done: bool,
@@ -64,6 +186,13 @@ pub struct PairInfo {
     pub stats: Option<RankingStats>,
 }
 
+#[derive(Serialize)]
+pub struct TripleInfo {
+    pub done: bool, // no triple available
+    pub photos: Vec<PhotoInfo>,
+    pub stats: RankingStats,
+}
+
 #[derive(Serialize)]
 pub struct PhotoInfo {
     pub id: String,
@@ -73,7 +202,7 @@ pub struct PhotoInfo {
     pub file_path: String,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct RankingStats {
     pub initialized: bool,
     pub total_photos: usize,
@@ -84,6 +213,9 @@ pub struct RankingStats {
     pub medium_uncertainty: usize,
     pub low_uncertainty: usize,
     pub avg_matches_per_photo: f64,
+    pub uncompared: usize, // photos that have never been in a comparison
+    pub winner_id: Option<String>, // tournament champion
+    pub estimated_remaining_comparisons: Option<usize>, // rough; None until there is history to go on
 }
 
 #[derive(Serialize)]
@@ -96,12 +228,135 @@ pub struct LeaderboardPhoto {
     pub file_path: String,
 }
 
+#[derive(Serialize)]
+pub struct PhotoComparison {
+    pub opponent_id: String,
+    pub outcome: String, // from this photo's side: "win" | "loss" | "tie" | "skip" | "both_good" | "both_bad"
+    pub mu_before: f64,
+    pub sigma_before: f64,
+    pub timestamp: f64,
+}
+
+#[derive(Serialize)]
+pub struct PhotoDetail {
+    pub id: String,
+    pub file_path: String,
+    pub mu: f64,
+    pub sigma: f64,
+    pub volatility: f64,
+    pub score: f64, // conservative score, as on the leaderboard
+    pub matches: usize,
+    pub cluster_id: Option<String>,
+    pub cluster_mates: Vec<ClusterMember>, // best score first, excluding this photo
+    // Record over all comparisons, including those trimmed from the history
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+    pub recent_comparisons: Vec<PhotoComparison>, // most recent first
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct HeadToHead {
+    pub a_wins: usize,
+    pub b_wins: usize,
+    pub ties: usize,
+}
+
+#[derive(Serialize)]
+pub struct RatingsImport {
+    pub imported: usize, // ratings merged into the current ranking
+    pub dropped: usize, // ratings for photos not in the ranked folder
+    pub comparisons_added: usize, // history records not already present
+}
+
+#[derive(Serialize)]
+pub struct UncomparedPhoto {
+    pub id: String,
+    pub file_path: String,
+}
+
+#[derive(Serialize)]
+pub struct DuplicatePhoto {
+    pub id: String,
+    pub file_path: String,
+}
+
+#[derive(Serialize)]
+pub struct DuplicateDistance {
+    pub a: String,
+    pub b: String,
+    pub distance: u32,
+}
+
+#[derive(Serialize)]
+pub struct DuplicateGroup {
+    pub photos: Vec<DuplicatePhoto>,
+    pub distances: Vec<DuplicateDistance>, // every pair within the group
+}
+
+#[derive(Serialize)]
+pub struct SimilarPhoto {
+    pub id: String,
+    pub file_path: String,
+    pub distance: u32,
+    pub queue_offset: usize, // how many photos after the current one it is in the queue
+}
+
+#[derive(Serialize)]
+pub struct ClusterMember {
+    pub id: String,
+    pub file_path: String,
+    pub score: f64,
+}
+
+#[derive(Serialize)]
+pub struct ClusterInfo {
+    pub id: String,
+    pub size: usize,
+    pub representative_id: Option<String>,
+    pub internal_ranking_complete: bool,
+    pub members: Vec<ClusterMember>, // best score first
+}
+
+#[derive(Serialize)]
+pub struct ClusterDecisionResult {
+    pub moved: usize,
+    pub kept: usize,   // the spared representative
+    pub failed: usize, // files that could not be moved (left in the cluster)
+}
+
+#[derive(Serialize)]
+pub struct ClusterDistances {
+    pub cluster_id: String,
+    pub representative_id: String,
+    pub threshold: u32,              // current cluster_threshold, for comparison
+    pub photo_ids: Vec<String>,      // row/column order of `matrix`
+    pub to_representative: Vec<u32>, // distance from each photo to the representative
+    pub matrix: Vec<Vec<u32>>,       // pairwise Hamming distances
+}
+
+#[derive(Serialize)]
+pub struct TopKConfidence {
+    pub k: usize,
+    pub above: LeaderboardPhoto, // photo at rank k
+    pub below: LeaderboardPhoto, // photo at rank k+1
+    pub confidence: f64,         // probability `above` truly outranks `below`
+    pub settled: bool,
+}
+
 #[derive(Serialize)]
 pub struct FolderInfo {
     pub path: String,
+    pub enabled: bool, // disabled folders aren't scanned, so their counts are 0
     pub exists: bool,
+    pub status: FolderStatus,
     pub photo_count: usize,
-    pub decided_count: usize,
+    pub decided_count: usize, // accepted + rejected + skipped
+    pub accepted_count: usize, // includes favorites
+    pub rejected_count: usize,
+    pub skipped_count: usize, // includes deferred
+    pub accepted_destination: String, // effective destinations, after per-source overrides
+    pub rejected_destination: String,
 }
 
 #[derive(Serialize)]
@@ -109,6 +364,8 @@ pub struct FoldersResponse {
     pub folders: Vec<FolderInfo>,
     pub accepted_folder: String,
     pub rejected_folder: String,
+    pub favorites_folder: String,
+    pub last_removed_folder: Option<String>, // can be brought back with restore_last_removed_folder
 }
 
 #[derive(Serialize)]
@@ -121,6 +378,20 @@ pub struct BrowseResponse {
     pub quick_access: Vec<QuickAccessLocation>,
 }
 
+/// Payload of progress events emitted by long-running commands
+#[derive(Clone, Serialize)]
+pub struct ProgressEvent {
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Serialize)]
+pub struct ExifRatingResult {
+    pub updated: usize,
+    pub skipped: usize, // not a JPEG, never compared, or missing
+    pub failed: usize,
+}
+
 // ============================================================================
 // Configuration commands
 // ============================================================================
@@ -132,78 +403,303 @@ pub fn get_config(state: State<AppState>) -> Config {
 
 #[tauri::command]
 pub fn save_config(config: Config, state: State<AppState>) -> Result<(), String> {
+    config.rating.validate()?;
+    config.validate_pairing()?;
+    config.check_folder_overlap()?;
+
     let mut cfg = state.config.lock().unwrap();
     *cfg = config.clone();
     cfg.save()?;
 
     // Rescan images with new config
-    let records = scan_source_folders(&cfg.source_folders);
+    let mut persistent = state.persistent.lock().unwrap();
+    rescan_sources(&cfg, &mut persistent, &state);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_extension_filter(exts: Vec<String>, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().unwrap();
+
+    config.extension_filter = exts.iter()
+        .map(|e| normalize_extension(e))
+        .filter(|e| !e.is_empty())
+        .collect();
+    config.save()?;
+
+    let mut persistent = state.persistent.lock().unwrap();
+    rescan_sources(&config, &mut persistent, &state);
+
+    Ok(())
+}
+
+/// Rescan source folders and rebuild the pending queue.
+/// Callers hold the config and persistent locks, so lock order stays consistent.
+fn rescan_sources(config: &Config, persistent: &mut PersistentState, state: &State<AppState>) {
+    // Each folder is probed once, for both the scan and the check below
+    let statuses = source_folder_statuses(config);
+    let records = scan_probed_source_folders(config, &statuses);
+
+    // Decisions recorded under the other id scheme are carried over. An unreachable
+    // folder's photos can't be matched, so wait until every folder is back.
+    let all_reachable = statuses.values().all(|status| *status != FolderStatus::Unreachable);
+    if all_reachable && sync_record_ids(persistent, &records, config) {
+        if let Err(e) = persistent.save() {
+            eprintln!("Warning: Could not save migrated ids: {}", e);
+        }
+    }
     let mut image_records = state.image_records.lock().unwrap();
     *image_records = records;
 
-    let persistent = state.persistent.lock().unwrap();
     let pending = build_pending_indices(&image_records, &persistent.decisions);
     let mut pending_indices = state.pending_indices.lock().unwrap();
     *pending_indices = pending;
 
+    // Keep the folder watcher in step with added/removed source folders
+    if let Some(watcher) = state.watcher.lock().unwrap().as_mut() {
+        watcher.set_folders(&config.enabled_source_folders());
+    }
+}
+
+/// Photos in a destination folder (id -> path). The last scan of each folder is
+/// reused while the folder's mtime is unchanged; moves also drop it explicitly,
+/// since mtime resolution can hide a change made within the same tick.
+fn folder_photos(state: &AppState, folder: &str, extensions: &[String]) -> Arc<HashMap<String, PathBuf>> {
+    let mtime = std::fs::metadata(folder).and_then(|m| m.modified()).ok();
+    let mut scans = state.folder_scans.lock().unwrap();
+
+    if let (Some(scan), Some(mtime)) = (scans.get(folder), mtime) {
+        if scan.mtime == mtime && scan.extensions == extensions {
+            return scan.photos.clone();
+        }
+    }
+
+    let photos = Arc::new(scan_accepted_photos(folder, extensions));
+    match mtime {
+        Some(mtime) => {
+            scans.insert(folder.to_string(), FolderScan {
+                extensions: extensions.to_vec(),
+                mtime,
+                photos: photos.clone(),
+            });
+        }
+        None => {
+            scans.remove(folder);
+        }
+    }
+    photos
+}
+
+/// Forget cached destination folder scans after files were moved in or out
+fn invalidate_folder_scans(state: &AppState) {
+    state.folder_scans.lock().unwrap().clear();
+}
+
+/// Limit how deep source folders are scanned (None = unlimited) and rescan.
+/// Decisions for files that fall outside the new depth stay in state but are no longer shown.
+#[tauri::command]
+pub fn set_scan_depth(depth: Option<usize>, state: State<AppState>) -> Result<(), String> {
+    if depth == Some(0) {
+        return Err("Scan depth must be at least 1".to_string());
+    }
+
+    let mut config = state.config.lock().unwrap();
+    config.scan_depth = depth;
+    config.save()?;
+
+    let mut persistent = state.persistent.lock().unwrap();
+    rescan_sources(&config, &mut persistent, &state);
+
+    Ok(())
+}
+
+/// Write the current config to a user-chosen file
+#[tauri::command]
+pub fn export_config(path: String, state: State<AppState>) -> Result<(), String> {
+    state.config.lock().unwrap().export_to(std::path::Path::new(&path))
+}
+
+/// Replace the config with one exported from another machine and rescan.
+/// Returns warnings for folders that don't exist here; they can be fixed afterwards.
+#[tauri::command]
+pub fn import_config(path: String, state: State<AppState>) -> Result<Vec<String>, String> {
+    let imported = Config::import_from(std::path::Path::new(&path))?;
+    if !imported.is_valid() {
+        return Err("Imported config needs source, accepted and rejected folders".to_string());
+    }
+    imported.rating.validate()?;
+    imported.validate_pairing()?;
+    imported.check_folder_overlap()?;
+
+    let warnings: Vec<String> = imported.missing_folders().into_iter()
+        .map(|folder| format!("Folder does not exist on this machine: {}", folder))
+        .collect();
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let mut config = state.config.lock().unwrap();
+    *config = imported;
+    config.save()?;
+
+    let mut persistent = state.persistent.lock().unwrap();
+    rescan_sources(&config, &mut persistent, &state);
+
+    Ok(warnings)
+}
+
+#[derive(Serialize)]
+pub struct ProfileList {
+    pub active: String,
+    pub profiles: Vec<String>,
+}
+
+#[tauri::command]
+pub fn list_profiles() -> ProfileList {
+    ProfileList {
+        active: active_profile(),
+        profiles: crate::config::list_profiles(),
+    }
+}
+
+/// Create a profile with default settings; it becomes active only via `switch_profile`
+#[tauri::command]
+pub fn create_profile(name: String) -> Result<(), String> {
+    crate::config::create_profile(&name)
+}
+
+/// Save the current profile's state, then load another profile's config, state and
+/// hashes and rescan its source folders
+#[tauri::command]
+pub fn switch_profile(name: String, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().unwrap();
+    let mut persistent = state.persistent.lock().unwrap();
+
+    validate_profile_name(&name)?;
+    if name == active_profile() {
+        return Ok(());
+    }
+
+    persistent.save()?;
+    state.state_dirty.store(false, Ordering::SeqCst);
+    set_active_profile(&name)?;
+
+    *config = Config::load();
+    *persistent = PersistentState::load();
+    *state.photo_hashes.lock().unwrap() = load_photo_hashes();
+    *state.triage_hashes.lock().unwrap() = load_triage_hashes();
+    state.exif_cache.lock().unwrap().clear();
+
+    rescan_sources(&config, &mut persistent, &state);
+
     Ok(())
 }
 
+#[tauri::command]
+pub fn get_supported_extensions(state: State<AppState>) -> Vec<String> {
+    state.config.lock().unwrap().supported_extensions()
+}
+
 #[tauri::command]
 pub fn is_config_valid(state: State<AppState>) -> bool {
     state.config.lock().unwrap().is_valid()
 }
 
+/// Why the saved state couldn't be loaded, if it couldn't. Saving is blocked until
+/// `acknowledge_state_recovery` is called.
+#[tauri::command]
+pub fn get_state_recovery(state: State<AppState>) -> Option<StateRecovery> {
+    state.persistent.lock().unwrap().recovery.clone()
+}
+
+/// Accept the salvaged state (the unreadable file stays in its backup) and save it
+#[tauri::command]
+pub fn acknowledge_state_recovery(state: State<AppState>) -> Result<(), String> {
+    let mut persistent = state.persistent.lock().unwrap();
+    if persistent.recovery.take().is_some() {
+        persistent.save()?;
+    }
+    Ok(())
+}
+
 // ============================================================================
 // Triage mode commands
 // ============================================================================
 
 #[tauri::command]
-pub fn initialize_app(state: State<AppState>) -> Result<(), String> {
+pub fn initialize_app(app: AppHandle, state: State<AppState>) -> Result<(), String> {
     let config = state.config.lock().unwrap();
 
     if !config.is_valid() {
         return Ok(()); // Config not set up yet
     }
 
-    // Scan source folders
-    let records = scan_source_folders(&config.source_folders);
-    let mut image_records = state.image_records.lock().unwrap();
-    *image_records = records;
-
-    // Build pending indices
-    let persistent = state.persistent.lock().unwrap();
-    let pending = build_pending_indices(&image_records, &persistent.decisions);
-    let mut pending_indices = state.pending_indices.lock().unwrap();
-    *pending_indices = pending;
+    // Scan source folders and build pending indices
+    let mut persistent = state.persistent.lock().unwrap();
+    rescan_sources(&config, &mut persistent, &state);
+
+    // Pick up photos added to the source folders while the app is open
+    let mut watcher = state.watcher.lock().unwrap();
+    if watcher.is_none() {
+        match SourceWatcher::start(app, &config.enabled_source_folders()) {
+            Ok(w) => *watcher = Some(w),
+            Err(e) => eprintln!("Warning: {}", e),
+        }
+    }
 
     Ok(())
 }
 
 #[tauri::command]
 pub fn get_current_image(state: State<AppState>) -> ImageInfo {
-    let _config = state.config.lock().unwrap();
+    let config = state.config.lock().unwrap();
     let persistent = state.persistent.lock().unwrap();
     let image_records = state.image_records.lock().unwrap();
-    let pending_indices = state.pending_indices.lock().unwrap();
+    let mut pending_indices = state.pending_indices.lock().unwrap();
 
-    let stats = get_stats_data(&image_records, &persistent.decisions);
+    let stats = get_stats_data(&image_records, &persistent, &config);
 
-    let record = get_current_record(&image_records, &pending_indices, persistent.current_index);
+    // Files deleted since the queue was built are dropped so the next photo shows instead
+    let record = loop {
+        let Some(r) = get_current_record(&image_records, &pending_indices, persistent.current_index) else {
+            break None;
+        };
+        if r.full_path().exists() {
+            break Some(r);
+        }
+        eprintln!("Warning: {} no longer exists, skipping it", r.full_path().display());
+        let position = if persistent.current_index < pending_indices.len() { persistent.current_index } else { 0 };
+        pending_indices.remove(position);
+    };
 
     match record {
-        Some(r) => ImageInfo {
-            done: false,
-            id: Some(r.id.clone()),
-            index: persistent.current_index,
-            total_pending: pending_indices.len(),
-            total_images: image_records.len(),
-            filename: Some(r.filename()),
-            source_folder: Some(r.source_name()),
-            file_path: Some(r.full_path().to_string_lossy().to_string()),
-            stats,
-            message: None,
-        },
+        Some(r) => {
+            let path = r.full_path();
+            let exif = {
+                let mut exif_cache = state.exif_cache.lock().unwrap();
+                exif_cache.entry(r.id.clone())
+                    .or_insert_with(|| read_exif_summary(&path))
+                    .clone()
+            };
+
+            ImageInfo {
+                done: false,
+                id: Some(r.id.clone()),
+                index: persistent.current_index,
+                total_pending: pending_indices.len(),
+                total_images: image_records.len(),
+                filename: Some(r.filename()),
+                source_folder: Some(r.source_name()),
+                file_path: Some(path.to_string_lossy().to_string()),
+                file_exists: path.exists(),
+                reason: persistent.decision_reasons.get(&r.id).cloned(),
+                captured_at: exif.captured_at,
+                camera_model: exif.camera_model,
+                stats,
+                message: None,
+            }
+        }
         None => ImageInfo {
             done: true,
             id: None,
@@ -213,6 +709,10 @@ pub fn get_current_image(state: State<AppState>) -> ImageInfo {
             filename: None,
             source_folder: None,
             file_path: None,
+            file_exists: false,
+            reason: None,
+            captured_at: None,
+            camera_model: None,
             stats,
             message: Some("All images have been triaged!".to_string()),
         },
@@ -221,25 +721,73 @@ pub fn get_current_image(state: State<AppState>) -> ImageInfo {
 
 fn get_stats_data(
     image_records: &[crate::state::ImageRecord],
-    decisions: &HashMap<String, String>,
+    persistent: &PersistentState,
+    config: &Config,
 ) -> Stats {
-    let accepted = decisions.values().filter(|d| *d == "accepted").count();
-    let rejected = decisions.values().filter(|d| *d == "rejected").count();
-    let skipped = decisions.values().filter(|d| *d == "skipped").count();
+    // With an extension filter active, only count decisions for matching files
+    let filtered = !config.extension_filter.is_empty();
+    let extensions = config.scan_extensions();
+    let record_paths: HashMap<&String, std::path::PathBuf> = if filtered {
+        image_records.iter().map(|r| (&r.id, r.full_path())).collect()
+    } else {
+        HashMap::new()
+    };
+    let included = |image_id: &String| {
+        if !filtered {
+            return true;
+        }
+        let path = persistent.original_paths.get(image_id)
+            .map(std::path::PathBuf::from)
+            .or_else(|| record_paths.get(image_id).cloned());
+        path.map(|p| has_supported_extension(&p, &extensions)).unwrap_or(true)
+    };
+
+    let count = |decision: &str| {
+        persistent.decisions.iter()
+            .filter(|(id, d)| *d == decision && included(id))
+            .count()
+    };
+
+    let accepted = count("accepted");
+    let rejected = count("rejected");
+    let favorites = count("favorite");
+    let skipped = count("skipped");
+    let deferred = count("deferred");
+
+    // Sizes come from the scan, or for files moved out of the source folders from
+    // when they were moved, so no file is stat-ed here
+    let record_sizes: HashMap<&String, u64> = image_records.iter().map(|r| (&r.id, r.size)).collect();
+    let bytes_decided = |wanted: &[&str]| -> u64 {
+        persistent.decisions.iter()
+            .filter(|(id, d)| wanted.contains(&d.as_str()) && included(id))
+            .filter_map(|(id, _)| record_sizes.get(id).or_else(|| persistent.moved_sizes.get(id)))
+            .sum()
+    };
 
-    let processed = accepted + rejected + skipped;
+    let processed = accepted + rejected + favorites + skipped + deferred;
     Stats {
         total: image_records.len(),
         pending: image_records.len().saturating_sub(processed),
         accepted,
         rejected,
+        favorites,
         skipped,
+        deferred,
         processed,
+        total_bytes: image_records.iter().map(|r| r.size).sum(),
+        accepted_bytes: bytes_decided(&["accepted", "favorite"]),
+        rejected_bytes: bytes_decided(&["rejected"]),
     }
 }
 
 #[tauri::command]
-pub fn swipe(image_id: String, direction: String, state: State<AppState>) -> Result<SwipeResult, String> {
+pub fn swipe(
+    app: AppHandle,
+    image_id: String,
+    direction: String,
+    reason: Option<String>,
+    state: State<AppState>,
+) -> Result<SwipeResult, String> {
     let config = state.config.lock().unwrap();
     let mut persistent = state.persistent.lock().unwrap();
     let image_records = state.image_records.lock().unwrap();
@@ -248,112 +796,1033 @@ pub fn swipe(image_id: String, direction: String, state: State<AppState>) -> Res
     let record = image_records.iter().find(|r| r.id == image_id)
         .ok_or("Image not found")?;
 
-    // Map direction to decision
-    let decision = match direction.as_str() {
-        "left" => "rejected",
-        "right" => "accepted",
-        "down" => "skipped",
-        _ => return Err("Invalid direction".to_string()),
-    };
-
-    // Record old decision for history
-    let old_decision = persistent.decisions.get(&image_id).cloned().unwrap_or("pending".to_string());
-
-    // Move file if accept/reject
-    if let Some(new_path) = move_image(record, decision, &config.accepted_folder, &config.rejected_folder)? {
-        persistent.original_paths.insert(image_id.clone(), record.full_path().to_string_lossy().to_string());
-        persistent.moved_files.insert(image_id.clone(), new_path);
-    }
-
-    // Update state
-    persistent.decisions.insert(image_id.clone(), decision.to_string());
-    persistent.history.push((image_id, old_decision, decision.to_string()));
-
-    // Trim history
-    if persistent.history.len() > 100 {
-        let keep = persistent.history.len() - 100;
-        persistent.history = persistent.history.split_off(keep);
-    }
+    let decision = apply_swipe(record, &direction, reason, &config, &mut persistent, &state)?;
 
     // Rebuild pending list
     let pending = build_pending_indices(&image_records, &persistent.decisions);
     let mut pending_indices = state.pending_indices.lock().unwrap();
+    let was_pending = !pending_indices.is_empty();
     *pending_indices = pending;
 
-    // Save state
-    persistent.save()?;
+    // Written by the background saver; moves are in moves.log meanwhile
+    state.save_later();
 
-    Ok(SwipeResult {
+    if was_pending && pending_indices.is_empty() {
+        emit_triage_complete(&app, &image_records, &persistent, &config);
+    }
+
+    Ok(SwipeResult {
         success: true,
         decision: decision.to_string(),
     })
 }
 
+/// Apply one swipe to the current photo and the next `count - 1` in the queue, e.g.
+/// to reject the rest of a bad burst. Each photo gets its own history entry, so
+/// `undo_many(count)` reverses the batch. A failed move stops the batch there.
 #[tauri::command]
-pub fn undo(state: State<AppState>) -> Result<UndoResult, String> {
+pub fn swipe_batch(
+    app: AppHandle,
+    direction: String,
+    count: usize,
+    state: State<AppState>,
+) -> Result<SwipeBatchResult, String> {
+    if count == 0 || count > MAX_HISTORY {
+        return Err(format!("Batch size must be between 1 and {} so it can be undone", MAX_HISTORY));
+    }
+
+    let config = state.config.lock().unwrap();
     let mut persistent = state.persistent.lock().unwrap();
     let image_records = state.image_records.lock().unwrap();
+    let mut pending_indices = state.pending_indices.lock().unwrap();
+
+    let start = if persistent.current_index < pending_indices.len() { persistent.current_index } else { 0 };
+    let batch: Vec<usize> = pending_indices.iter().skip(start).take(count).copied().collect();
+
+    let mut image_ids = Vec::new();
+    let mut decision = None;
+    let mut error = None;
+    for idx in batch {
+        let record = &image_records[idx];
+        match apply_swipe(record, &direction, None, &config, &mut persistent, &state) {
+            Ok(made) => {
+                decision = Some(made.to_string());
+                image_ids.push(record.id.clone());
+            }
+            Err(e) => {
+                error = Some(e);
+                break;
+            }
+        }
+    }
+
+    // Rebuild pending once for the whole batch
+    let was_pending = !pending_indices.is_empty();
+    *pending_indices = build_pending_indices(&image_records, &persistent.decisions);
+    if !image_ids.is_empty() {
+        state.save_later();
+    }
+
+    if was_pending && pending_indices.is_empty() {
+        emit_triage_complete(&app, &image_records, &persistent, &config);
+    }
+
+    Ok(SwipeBatchResult { image_ids, decision, error })
+}
+
+/// Tell the frontend the last pending photo was just decided, with the final stats.
+/// Callers only emit on the transition to an empty queue.
+fn emit_triage_complete(
+    app: &AppHandle,
+    image_records: &[ImageRecord],
+    persistent: &PersistentState,
+    config: &Config,
+) {
+    let stats = get_stats_data(image_records, persistent, config);
+    if let Err(e) = app.emit("triage-complete", stats) {
+        eprintln!("Warning: Could not emit triage-complete: {}", e);
+    }
+}
+
+/// Move a photo's file for a swipe `direction` and record the decision.
+/// Returns the decision made; the caller rebuilds the queue and saves.
+fn apply_swipe(
+    record: &ImageRecord,
+    direction: &str,
+    reason: Option<String>,
+    config: &Config,
+    persistent: &mut PersistentState,
+    state: &State<AppState>,
+) -> Result<&'static str, String> {
+    let image_id = &record.id;
+
+    // Map direction to decision
+    let decision = match direction {
+        "left" => "rejected",
+        "right" => "accepted",
+        "up" => "favorite",
+        "down" => persistent.count_skip(image_id, config.max_skips),
+        _ => return Err("Invalid direction".to_string()),
+    };
+
+    move_for_decision(record, decision, config, persistent, state)?;
+
+    // Update state
+    persistent.record_decision(image_id, decision, reason, now_secs());
+    Ok(decision)
+}
+
+/// Move (or copy) a photo's file for a decision, if the decision has a destination,
+/// and keep what undo needs. Shared by swipes and redo.
+fn move_for_decision(
+    record: &ImageRecord,
+    decision: &str,
+    config: &Config,
+    persistent: &mut PersistentState,
+    state: &State<AppState>,
+) -> Result<(), String> {
+    if let Some(moved) = move_image(record, decision, config)? {
+        record_app_move(record, decision, moved, config, persistent, state);
+    }
+    Ok(())
+}
+
+/// `record_move`, plus what only the running app keeps up to date: the cached
+/// destination folder scans and, with `hash_on_accept`, the accepted photo's hash
+fn record_app_move(
+    record: &ImageRecord,
+    decision: &str,
+    moved: MovedImage,
+    config: &Config,
+    persistent: &mut PersistentState,
+    state: &State<AppState>,
+) {
+    if !config.dry_run {
+        invalidate_folder_scans(state);
+        if decision == "accepted" && config.hash_on_accept {
+            hash_accepted_photo(&moved.path, config.hash_algorithm, state);
+        }
+    }
+    record_move(record, decision, moved, config, persistent);
+}
+
+/// Save the hash cache after this many newly hashed photos
+const HASH_SAVE_INTERVAL: usize = 10;
+
+/// Hash a just-accepted photo under the id ranking mode will give it
+/// (derived from its path inside the Accepted folder, not the source path)
+fn hash_accepted_photo(accepted_path: &str, algorithm: HashAlgorithm, state: &State<AppState>) {
+    let path = std::path::Path::new(accepted_path);
+    let photo_id = generate_image_id(path);
+
+    let mut photo_hashes = state.photo_hashes.lock().unwrap();
+    if photo_hashes.get(&photo_id).is_some_and(|entry| entry.is_current(path, algorithm)) {
+        return;
+    }
+
+    if let Some(hash) = compute_hash(path, algorithm) {
+        photo_hashes.insert(photo_id, PhotoHash::new(hash, algorithm, path));
+        if photo_hashes.len() % HASH_SAVE_INTERVAL == 0 {
+            if let Err(e) = save_photo_hashes(&photo_hashes) {
+                eprintln!("Warning: Could not save photo hashes: {}", e);
+            }
+        }
+    }
+}
+
+/// Jump to a position in the pending queue (clamped to the last photo).
+/// Returns the index actually set.
+#[tauri::command]
+pub fn set_current_index(index: usize, state: State<AppState>) -> Result<usize, String> {
+    let mut persistent = state.persistent.lock().unwrap();
+    let pending_len = state.pending_indices.lock().unwrap().len();
+
+    if pending_len == 0 {
+        return Err("No pending photos".to_string());
+    }
+
+    persistent.current_index = index.min(pending_len - 1);
+    state.save_later();
+
+    Ok(persistent.current_index)
+}
+
+/// Move forward (positive) or back (negative) through the pending queue
+#[tauri::command]
+pub fn seek(delta: i64, state: State<AppState>) -> Result<usize, String> {
+    let current = state.persistent.lock().unwrap().current_index as i64;
+    set_current_index(current.saturating_add(delta).max(0) as usize, state)
+}
+
+#[tauri::command]
+pub fn undo(state: State<AppState>) -> Result<UndoResult, String> {
+    let mut persistent = state.persistent.lock().unwrap();
+    let mut image_records = state.image_records.lock().unwrap();
 
-    if persistent.history.is_empty() {
+    let Some((image_id, old_decision, new_decision)) = undo_last_decision(&mut persistent, &mut image_records)? else {
         return Ok(UndoResult {
             success: false,
             message: "Nothing to undo".to_string(),
             image_id: None,
         });
+    };
+    invalidate_folder_scans(&state);
+
+    // Rebuild pending
+    let pending = build_pending_indices(&image_records, &persistent.decisions);
+    let mut pending_indices = state.pending_indices.lock().unwrap();
+
+    // Find the undone image in pending
+    for (i, &idx) in pending.iter().enumerate() {
+        if image_records[idx].id == image_id {
+            persistent.current_index = i;
+            break;
+        }
+    }
+
+    *pending_indices = pending;
+    state.save_later();
+
+    Ok(UndoResult {
+        success: true,
+        message: format!("Undone: {} -> {}", new_decision, old_decision),
+        image_id: Some(image_id),
+    })
+}
+
+#[tauri::command]
+pub fn undo_many(count: usize, state: State<AppState>) -> Result<UndoManyResult, String> {
+    let mut persistent = state.persistent.lock().unwrap();
+    let mut image_records = state.image_records.lock().unwrap();
+
+    let mut image_ids = Vec::new();
+    let mut error = None;
+
+    for _ in 0..count {
+        match undo_last_decision(&mut persistent, &mut image_records) {
+            Ok(Some((image_id, _, _))) => image_ids.push(image_id),
+            Ok(None) => break, // History exhausted
+            Err(e) => {
+                error = Some(e);
+                break;
+            }
+        }
     }
 
-    // Pop last decision
-    let (image_id, old_decision, new_decision) = persistent.history.pop().unwrap();
+    invalidate_folder_scans(&state);
+
+    // Rebuild pending once for the whole batch
+    let pending = build_pending_indices(&image_records, &persistent.decisions);
+    let mut pending_indices = state.pending_indices.lock().unwrap();
+
+    // Land on the earliest undone image so the batch can be re-decided in order
+    if let Some(first_id) = image_ids.last() {
+        if let Some(i) = pending.iter().position(|&idx| image_records[idx].id == *first_id) {
+            persistent.current_index = i;
+        }
+    }
+
+    *pending_indices = pending;
+    if !image_ids.is_empty() {
+        state.save_later();
+    }
+
+    Ok(UndoManyResult {
+        undone: image_ids.len(),
+        image_ids,
+        error,
+    })
+}
+
+/// Reverse the most recent triage decision: move the file back and restore the
+/// previous decision. Returns the undone history entry, or None if history is empty.
+/// On a failed file restore the entry stays in history so state remains consistent.
+/// A file restored under a new name (its original path was taken) has its record updated.
+fn undo_last_decision(
+    persistent: &mut PersistentState,
+    image_records: &mut [ImageRecord],
+) -> Result<Option<(String, String, String)>, String> {
+    let Some((image_id, old_decision, new_decision)) = persistent.history.pop() else {
+        return Ok(None);
+    };
 
     // If file was moved, move it back
-    if new_decision == "accepted" || new_decision == "rejected" {
+    if new_decision == "accepted" || new_decision == "rejected" || new_decision == "favorite" {
         if let (Some(moved_path), Some(original_path)) = (
             persistent.moved_files.get(&image_id),
             persistent.original_paths.get(&image_id),
         ) {
-            undo_move(moved_path, original_path)?;
+            // Copy mode: the original never left, so just delete the copy
+            let result = if persistent.copied_files.contains(&image_id) {
+                undo_copy(&image_id, &new_decision, moved_path).map(|_| original_path.clone())
+            } else {
+                undo_move(&image_id, &new_decision, moved_path, original_path)
+            };
+            let restored = match result {
+                Ok(restored) => restored,
+                Err(e) => {
+                    persistent.history.push((image_id, old_decision, new_decision));
+                    return Err(e);
+                }
+            };
+            if restored != *original_path {
+                eprintln!("Warning: {} was taken, restored to {}", original_path, restored);
+                if let Some(record) = image_records.iter_mut().find(|r| r.id == image_id) {
+                    if let Ok(rel_path) = std::path::Path::new(&restored).strip_prefix(&record.source_folder) {
+                        record.relative_path = rel_path.to_string_lossy().to_string();
+                    }
+                }
+            }
             persistent.moved_files.remove(&image_id);
+            persistent.moved_sizes.remove(&image_id);
             persistent.original_paths.remove(&image_id);
+            persistent.copied_files.remove(&image_id);
+            if let Some(copies) = persistent.extra_copies.remove(&image_id) {
+                remove_extra_copies(&image_id, &new_decision, &copies);
+            }
         }
     }
+    persistent.restore_ranked_removal(&image_id);
+
+    persistent.planned_moves.remove(&image_id);
+    if new_decision == "skipped" || new_decision == "deferred" {
+        persistent.uncount_skip(&image_id);
+    }
 
-    // Restore old decision
+    // Restore old decision. The reason belonged to the undone decision; a redo brings it back.
+    match persistent.decision_reasons.remove(&image_id) {
+        Some(reason) => persistent.redo_reasons.insert(image_id.clone(), reason),
+        None => persistent.redo_reasons.remove(&image_id),
+    };
     if old_decision == "pending" {
         persistent.decisions.remove(&image_id);
+        persistent.decided_at.remove(&image_id);
     } else {
         persistent.decisions.insert(image_id.clone(), old_decision.clone());
     }
 
-    // Rebuild pending
+    persistent.redo_stack.push((image_id.clone(), old_decision.clone(), new_decision.clone()));
+    if persistent.redo_stack.len() > MAX_HISTORY {
+        let keep = persistent.redo_stack.len() - MAX_HISTORY;
+        persistent.redo_stack = persistent.redo_stack.split_off(keep);
+    }
+
+    Ok(Some((image_id, old_decision, new_decision)))
+}
+
+/// Free space on each destination folder's volume next to the size of the pending
+/// photos, so the frontend can warn before a session runs a drive full
+#[tauri::command]
+pub fn check_destination_space(state: State<AppState>) -> SpaceReport {
+    let config = state.config.lock().unwrap();
+    let image_records = state.image_records.lock().unwrap();
+    let pending_indices = state.pending_indices.lock().unwrap();
+
+    let destinations = config.destination_folders().into_iter()
+        .map(|folder| DestinationSpace {
+            folder: folder.to_string(),
+            available_bytes: available_space(std::path::Path::new(folder)),
+        })
+        .collect();
+    // Sizes recorded at scan time, so no stat per pending file
+    let pending_bytes = pending_indices.iter()
+        .map(|&idx| image_records[idx].size)
+        .sum();
+
+    SpaceReport { destinations, pending_bytes }
+}
+
+/// Start triage over: forget every decision and the undo/redo history. With
+/// `move_back`, accepted/rejected files are first restored to their source folders;
+/// otherwise they stay where they are. The ranking session is left alone.
+#[tauri::command]
+pub fn reset_triage(move_back: bool, state: State<AppState>) -> Result<TriageResetResult, String> {
+    let config = state.config.lock().unwrap();
+    let mut persistent = state.persistent.lock().unwrap();
+
+    let mut restored = 0;
+    let mut failed = Vec::new();
+    let mut kept: HashSet<String> = HashSet::new();
+
+    if move_back {
+        let moved: Vec<(String, String)> = persistent.moved_files.iter()
+            .map(|(id, path)| (id.clone(), path.clone()))
+            .collect();
+        for (image_id, moved_path) in moved {
+            let Some(original_path) = persistent.original_paths.get(&image_id).cloned() else {
+                failed.push(format!("{}: original path unknown", moved_path));
+                kept.insert(image_id);
+                continue;
+            };
+            let decision = persistent.decisions.get(&image_id).cloned().unwrap_or_default();
+            let result = if persistent.copied_files.contains(&image_id) {
+                undo_copy(&image_id, &decision, &moved_path)
+            } else {
+                undo_move(&image_id, &decision, &moved_path, &original_path).map(|_| ())
+            };
+            match result {
+                Ok(()) => {
+                    if let Some(copies) = persistent.extra_copies.get(&image_id) {
+                        remove_extra_copies(&image_id, &decision, copies);
+                    }
+                    restored += 1;
+                }
+                Err(e) => {
+                    failed.push(e);
+                    kept.insert(image_id);
+                }
+            }
+        }
+        invalidate_folder_scans(&state);
+    }
+
+    // Files that stayed in a destination keep their decision and move record
+    let cleared = persistent.decisions.len() - kept.iter().filter(|id| persistent.decisions.contains_key(*id)).count();
+    persistent.decisions.retain(|id, _| kept.contains(id));
+    persistent.decision_reasons.retain(|id, _| kept.contains(id));
+    persistent.decided_at.retain(|id, _| kept.contains(id));
+    persistent.moved_files.retain(|id, _| kept.contains(id));
+    persistent.original_paths.retain(|id, _| kept.contains(id));
+    persistent.copied_files.retain(|id| kept.contains(id));
+    persistent.extra_copies.retain(|id, _| kept.contains(id));
+    persistent.moved_sizes.retain(|id, _| kept.contains(id));
+    persistent.ranked_removals.clear();
+    persistent.skip_counts.clear();
+    persistent.planned_moves.clear();
+    persistent.history.clear();
+    persistent.redo_stack.clear();
+    persistent.redo_reasons.clear();
+    persistent.current_index = 0;
+
+    // Restored files are back in the source folders, so scan them again
+    rescan_sources(&config, &mut persistent, &state);
+    persistent.save()?;
+
+    Ok(TriageResetResult { cleared, restored, failed })
+}
+
+#[tauri::command]
+pub fn redo(state: State<AppState>) -> Result<UndoResult, String> {
+    let config = state.config.lock().unwrap();
+    let mut persistent = state.persistent.lock().unwrap();
+    let image_records = state.image_records.lock().unwrap();
+
+    let Some((image_id, old_decision, new_decision)) = persistent.redo_stack.last().cloned() else {
+        return Ok(UndoResult {
+            success: false,
+            message: "Nothing to redo".to_string(),
+            image_id: None,
+        });
+    };
+
+    let record = image_records.iter().find(|r| r.id == image_id)
+        .ok_or("Image not found")?;
+
+    // Re-apply the move the way a swipe does; only pop the entry once it succeeded
+    move_for_decision(record, &new_decision, &config, &mut persistent, &state)?;
+    persistent.redo_stack.pop();
+
+    // A redone skip counts again, and may defer the photo under the current max_skips
+    let decision = if new_decision == "skipped" || new_decision == "deferred" {
+        persistent.count_skip(&image_id, config.max_skips).to_string()
+    } else {
+        new_decision
+    };
+    let reason = persistent.redo_reasons.remove(&image_id);
+    persistent.redo_decision(&image_id, &decision, reason, now_secs());
+
+    // Rebuild pending list
     let pending = build_pending_indices(&image_records, &persistent.decisions);
     let mut pending_indices = state.pending_indices.lock().unwrap();
+    *pending_indices = pending;
+
+    state.save_later();
+
+    Ok(UndoResult {
+        success: true,
+        message: format!("Redone: {} -> {}", old_decision, decision),
+        image_id: Some(image_id),
+    })
+}
+
+/// Moves decided during a dry run that haven't been carried out yet, by source path
+#[tauri::command]
+pub fn get_dry_run_plan(state: State<AppState>) -> Vec<PlannedMove> {
+    let persistent = state.persistent.lock().unwrap();
+    let image_records = state.image_records.lock().unwrap();
+
+    let mut plan: Vec<PlannedMove> = image_records.iter()
+        .filter_map(|record| {
+            let destination = persistent.planned_moves.get(&record.id)?;
+            Some(PlannedMove {
+                id: record.id.clone(),
+                decision: persistent.decisions.get(&record.id).cloned().unwrap_or_default(),
+                source: record.full_path().to_string_lossy().to_string(),
+                destination: destination.clone(),
+            })
+        })
+        .collect();
+    plan.sort_by(|a, b| a.source.cmp(&b.source));
+    plan
+}
+
+/// Carry out the moves planned during a dry run, for the decisions they still
+/// match. Plans whose move fails are kept so the commit can be retried.
+#[tauri::command]
+pub fn commit_dry_run(state: State<AppState>) -> Result<DryRunCommitResult, String> {
+    let config = state.config.lock().unwrap();
+    let mut persistent = state.persistent.lock().unwrap();
+    let image_records = state.image_records.lock().unwrap();
+
+    // The moves are real even while dry run stays on for later decisions
+    let live = Config { dry_run: false, ..config.clone() };
+    let planned: Vec<String> = persistent.planned_moves.keys().cloned().collect();
+    let mut result = DryRunCommitResult { moved: 0, dropped: 0, failed: Vec::new() };
+
+    for image_id in planned {
+        let record = image_records.iter().find(|r| r.id == image_id);
+        let decision = persistent.decisions.get(&image_id).cloned();
+        let (Some(record), Some(decision)) = (record, decision) else {
+            persistent.planned_moves.remove(&image_id);
+            result.dropped += 1;
+            continue;
+        };
+
+        match move_image(record, &decision, &live) {
+            Ok(moved) => {
+                persistent.planned_moves.remove(&image_id);
+                match moved {
+                    Some(moved) => {
+                        record_app_move(record, &decision, moved, &live, &mut persistent, &state);
+                        result.moved += 1;
+                    }
+                    None => result.dropped += 1, // no longer a moving decision
+                }
+            }
+            Err(e) => {
+                eprintln!("Warning: {}", e);
+                result.failed.push(record.full_path().to_string_lossy().to_string());
+            }
+        }
+    }
+
+    let pending = build_pending_indices(&image_records, &persistent.decisions);
+    *state.pending_indices.lock().unwrap() = pending;
+    persistent.save()?;
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn get_reason_breakdown(state: State<AppState>) -> HashMap<String, usize> {
+    let persistent = state.persistent.lock().unwrap();
+
+    let mut breakdown = HashMap::new();
+    for (image_id, decision) in &persistent.decisions {
+        if decision != "rejected" {
+            continue;
+        }
+        let reason = persistent.decision_reasons.get(image_id)
+            .cloned()
+            .unwrap_or_else(|| "unspecified".to_string());
+        *breakdown.entry(reason).or_insert(0) += 1;
+    }
+
+    breakdown
+}
+
+/// Most photos returned by get_similar_to_current
+const MAX_SIMILAR_PHOTOS: usize = 10;
+
+/// Photos after the current one that get_similar_to_current hashes itself when
+/// they aren't cached; the rest of the queue is left to precompute_triage_hashes
+const SIMILAR_HASH_WINDOW: usize = 32;
+
+/// Pending photos that look like the current triage photo, closest first.
+/// Hashes are cached in triage_hashes.json. Only the current photo and the next
+/// SIMILAR_HASH_WINDOW are hashed here, so photos further on only match once the
+/// background pass has reached them.
+#[tauri::command]
+pub fn get_similar_to_current(threshold: Option<u32>, state: State<AppState>) -> Result<Vec<SimilarPhoto>, String> {
+    let (algorithm, threshold) = {
+        let config = state.config.lock().unwrap();
+        (config.hash_algorithm, threshold.unwrap_or(config.cluster_threshold))
+    };
+    if threshold > MAX_HAMMING_THRESHOLD {
+        return Err(format!("Threshold must be between 0 and {}", MAX_HAMMING_THRESHOLD));
+    }
+
+    // The current photo and the window after it, wrapping around the end of the queue
+    let window: Vec<(String, PathBuf)> = {
+        let persistent = state.persistent.lock().unwrap();
+        let image_records = state.image_records.lock().unwrap();
+        let pending_indices = state.pending_indices.lock().unwrap();
+
+        let start = if persistent.current_index < pending_indices.len() { persistent.current_index } else { 0 };
+        pending_indices[start..].iter()
+            .chain(&pending_indices[..start])
+            .take(1 + SIMILAR_HASH_WINDOW)
+            .map(|&idx| (image_records[idx].id.clone(), image_records[idx].full_path()))
+            .collect()
+    };
+    let Some((current_id, _)) = window.first() else {
+        return Ok(Vec::new());
+    };
+
+    // Hash without holding the cache lock, so the background pass isn't stalled
+    let stale: Vec<&(String, PathBuf)> = {
+        let triage_hashes = state.triage_hashes.lock().unwrap();
+        window.iter()
+            .filter(|(id, path)| !triage_hashes.get(id).is_some_and(|entry| entry.is_current(path, algorithm)))
+            .collect()
+    };
+    let fresh: Vec<(String, PhotoHash)> = stale.par_iter()
+        .filter_map(|(id, path)| {
+            compute_hash(path, algorithm).map(|hash| (id.clone(), PhotoHash::new(hash, algorithm, path)))
+        })
+        .collect();
+    if !fresh.is_empty() {
+        let mut triage_hashes = state.triage_hashes.lock().unwrap();
+        triage_hashes.extend(fresh);
+        if let Err(e) = save_triage_hashes(&triage_hashes) {
+            eprintln!("Warning: Could not save triage hashes: {}", e);
+        }
+    }
+
+    // Compare against every pending photo with a cached hash
+    let persistent = state.persistent.lock().unwrap();
+    let image_records = state.image_records.lock().unwrap();
+    let pending_indices = state.pending_indices.lock().unwrap();
+    let triage_hashes = state.triage_hashes.lock().unwrap();
+
+    let Some(current_hash) = triage_hashes.get(current_id).map(|entry| &entry.hash) else {
+        return Err("Could not hash the current photo".to_string());
+    };
+
+    let start = if persistent.current_index < pending_indices.len() { persistent.current_index } else { 0 };
+    let mut similar: Vec<SimilarPhoto> = pending_indices[start..].iter()
+        .chain(&pending_indices[..start])
+        .enumerate()
+        .skip(1)
+        .filter_map(|(offset, &idx)| {
+            let record = &image_records[idx];
+            if record.id == *current_id {
+                return None;
+            }
+            let distance = hamming_distance(current_hash, &triage_hashes.get(&record.id)?.hash);
+            (distance <= threshold).then(|| SimilarPhoto {
+                id: record.id.clone(),
+                file_path: record.full_path().to_string_lossy().to_string(),
+                distance,
+                queue_offset: offset,
+            })
+        })
+        .collect();
+    similar.sort_by_key(|photo| (photo.distance, photo.queue_offset));
+    similar.truncate(MAX_SIMILAR_PHOTOS);
+
+    Ok(similar)
+}
+
+/// Hash every pending photo missing from triage_hashes.json on a background thread,
+/// emitting "triage-hash-progress" ({done, total}) per hash and then
+/// "triage-hash-complete" with the number hashed. Returns how many need hashing.
+/// The cache is saved every few hashes, so an interrupted pass resumes where it stopped.
+#[tauri::command]
+pub fn precompute_triage_hashes(app: AppHandle, state: State<AppState>) -> Result<usize, String> {
+    if state.triage_hashing_running.swap(true, Ordering::SeqCst) {
+        return Err("Triage hashing is already running".to_string());
+    }
+
+    let algorithm = state.config.lock().unwrap().hash_algorithm;
+    let stale: Vec<(String, PathBuf)> = {
+        let image_records = state.image_records.lock().unwrap();
+        let pending_indices = state.pending_indices.lock().unwrap();
+        let triage_hashes = state.triage_hashes.lock().unwrap();
+        pending_indices.iter()
+            .map(|&idx| (image_records[idx].id.clone(), image_records[idx].full_path()))
+            .filter(|(id, path)| !triage_hashes.get(id).is_some_and(|entry| entry.is_current(path, algorithm)))
+            .collect()
+    };
+
+    let total = stale.len();
+    std::thread::spawn(move || {
+        let state = app.state::<AppState>();
+        let _running = InitGuard(&state.triage_hashing_running);
+
+        let mut hashed = 0;
+        for (done, (image_id, path)) in stale.into_iter().enumerate() {
+            if let Some(hash) = compute_hash(&path, algorithm) {
+                let mut triage_hashes = state.triage_hashes.lock().unwrap();
+                triage_hashes.insert(image_id, PhotoHash::new(hash, algorithm, &path));
+                hashed += 1;
+                if hashed % HASH_SAVE_INTERVAL == 0 {
+                    if let Err(e) = save_triage_hashes(&triage_hashes) {
+                        eprintln!("Warning: Could not save triage hashes: {}", e);
+                    }
+                }
+            }
+            let _ = app.emit("triage-hash-progress", ProgressEvent { done: done + 1, total });
+        }
+
+        if let Err(e) = save_triage_hashes(&state.triage_hashes.lock().unwrap()) {
+            eprintln!("Warning: Could not save triage hashes: {}", e);
+        }
+        if let Err(e) = app.emit("triage-hash-complete", hashed) {
+            eprintln!("Warning: Could not emit triage-hash-complete: {}", e);
+        }
+    });
+
+    Ok(total)
+}
+
+/// Group near-duplicate photos in the source folders (groups of 2+, largest first)
+#[tauri::command]
+pub fn find_duplicates(threshold: u32, state: State<AppState>) -> Result<Vec<DuplicateGroup>, String> {
+    if threshold > MAX_HAMMING_THRESHOLD {
+        return Err(format!("Threshold must be between 0 and {}", MAX_HAMMING_THRESHOLD));
+    }
+
+    let (algorithm, linkage) = {
+        let config = state.config.lock().unwrap();
+        (config.hash_algorithm, config.cluster_linkage)
+    };
+    let records = state.image_records.lock().unwrap().clone();
+    let mut triage_hashes = state.triage_hashes.lock().unwrap();
+
+    // Hash every source photo, reusing cached hashes where the file is unchanged
+    let mut paths: HashMap<String, std::path::PathBuf> = HashMap::new();
+    let mut hashes: HashMap<String, String> = HashMap::new();
+    for record in &records {
+        let path = record.full_path();
+        let cached = triage_hashes.get(&record.id).is_some_and(|entry| entry.is_current(&path, algorithm));
+        if !cached {
+            if let Some(hash) = compute_hash(&path, algorithm) {
+                triage_hashes.insert(record.id.clone(), PhotoHash::new(hash, algorithm, &path));
+            }
+        }
+        if let Some(entry) = triage_hashes.get(&record.id) {
+            hashes.insert(record.id.clone(), entry.hash.clone());
+        }
+        paths.insert(record.id.clone(), path);
+    }
+    save_triage_hashes(&triage_hashes)?;
+    drop(triage_hashes);
+
+    let (clusters, _) = cluster_photos(&hashes, threshold, linkage);
+
+    let mut groups: Vec<DuplicateGroup> = clusters.into_values()
+        .filter(|ids| ids.len() >= 2)
+        .map(|ids| {
+            let mut distances = Vec::new();
+            for (i, a) in ids.iter().enumerate() {
+                for b in &ids[i + 1..] {
+                    distances.push(DuplicateDistance {
+                        a: a.clone(),
+                        b: b.clone(),
+                        distance: hamming_distance(&hashes[a], &hashes[b]),
+                    });
+                }
+            }
+
+            let photos = ids.into_iter()
+                .map(|id| DuplicatePhoto {
+                    file_path: paths[&id].to_string_lossy().to_string(),
+                    id,
+                })
+                .collect();
+
+            DuplicateGroup { photos, distances }
+        })
+        .collect();
+
+    groups.sort_by_key(|group| std::cmp::Reverse(group.photos.len()));
+    Ok(groups)
+}
+
+#[tauri::command]
+pub fn repair_move_records(state: State<AppState>) -> Result<RepairReport, String> {
+    let config = state.config.lock().unwrap();
+    let mut persistent = state.persistent.lock().unwrap();
+    let image_records = state.image_records.lock().unwrap();
+
+    let mut actions = Vec::new();
+
+    let moved_only: Vec<String> = persistent.moved_files.keys()
+        .filter(|id| !persistent.original_paths.contains_key(*id))
+        .cloned()
+        .collect();
+    let original_only: Vec<String> = persistent.original_paths.keys()
+        .filter(|id| !persistent.moved_files.contains_key(*id))
+        .cloned()
+        .collect();
+    let checked = persistent.moved_files.len().max(persistent.original_paths.len());
+
+    // Moved without an original: recover the source path from the scanned records
+    for image_id in moved_only {
+        match image_records.iter().find(|r| r.id == image_id) {
+            Some(record) => {
+                let original = record.full_path().to_string_lossy().to_string();
+                persistent.original_paths.insert(image_id.clone(), original.clone());
+                actions.push(RepairAction { image_id, action: "restored_original".to_string(), path: original });
+            }
+            None => {
+                let moved = persistent.moved_files.remove(&image_id).unwrap_or_default();
+                actions.push(RepairAction { image_id, action: "dropped_moved".to_string(), path: moved });
+            }
+        }
+    }
+
+    // Original without a destination: look for the file in the folder its decision points at
+    for image_id in original_only {
+        let original = persistent.original_paths.get(&image_id).cloned().unwrap_or_default();
+        let filename = std::path::Path::new(&original).file_name().map(|n| n.to_os_string());
+        let source_folder = image_records.iter().find(|r| r.id == image_id)
+            .map(|r| r.source_folder.as_str())
+            .unwrap_or_default();
+        let folder = persistent.decisions.get(&image_id)
+            .and_then(|decision| config.destination_for_source(decision, source_folder));
+
+        let candidate = folder.zip(filename)
+            .map(|(folder, name)| std::path::Path::new(folder).join(name))
+            .filter(|p| p.exists());
+
+        match candidate {
+            Some(moved) => {
+                let moved = moved.to_string_lossy().to_string();
+                persistent.moved_files.insert(image_id.clone(), moved.clone());
+                actions.push(RepairAction { image_id, action: "restored_moved".to_string(), path: moved });
+            }
+            None => {
+                persistent.original_paths.remove(&image_id);
+                actions.push(RepairAction { image_id, action: "dropped_original".to_string(), path: original });
+            }
+        }
+    }
+
+    if !actions.is_empty() {
+        persistent.save()?;
+    }
+
+    Ok(RepairReport { checked, actions })
+}
+
+/// Point a decision's moved file at `new_path`, after the file was moved by hand
+#[tauri::command]
+pub fn relocate_moved_file(image_id: String, new_path: String, state: State<AppState>) -> Result<(), String> {
+    let config = state.config.lock().unwrap();
+    let mut persistent = state.persistent.lock().unwrap();
+
+    if !persistent.moved_files.contains_key(&image_id) {
+        return Err(format!("No moved file recorded for {}", image_id));
+    }
+
+    let path = std::path::Path::new(&new_path);
+    if !path.is_file() {
+        return Err(format!("File does not exist: {}", new_path));
+    }
+    if !has_supported_extension(path, &config.supported_extensions()) {
+        return Err(format!("Not a supported image: {}", new_path));
+    }
+
+    persistent.moved_files.insert(image_id, new_path);
+    persistent.save()
+}
+
+/// Report moved files that are no longer where they were recorded. With `search`,
+/// each one is looked up by file name under the destination folders and, when
+/// exactly one match turns up, its record is updated so undo works again.
+#[tauri::command]
+pub fn verify_moved_files(search: bool, state: State<AppState>) -> Result<MovedFilesReport, String> {
+    let config = state.config.lock().unwrap();
+    let mut persistent = state.persistent.lock().unwrap();
+
+    // Trashed files are restored through the trash, not by path
+    let mut missing: Vec<MissingMovedFile> = persistent.moved_files.iter()
+        .filter(|(_, path)| !path.starts_with(TRASH_TOKEN_PREFIX) && !std::path::Path::new(path.as_str()).exists())
+        .map(|(id, path)| MissingMovedFile { image_id: id.clone(), path: path.clone(), relocated: None })
+        .collect();
+    missing.sort_by_key(|m| m.path.clone());
+    let checked = persistent.moved_files.len();
+
+    if search && !missing.is_empty() {
+        let index = index_files_by_name(&config.destination_folders(), &config.supported_extensions());
+
+        for entry in &mut missing {
+            let Some(name) = std::path::Path::new(&entry.path).file_name() else {
+                continue;
+            };
+            // Several files with the same name can't be told apart; leave those for relocate_moved_file
+            if let Some([found]) = index.get(name).map(Vec::as_slice) {
+                let found = found.to_string_lossy().to_string();
+                persistent.moved_files.insert(entry.image_id.clone(), found.clone());
+                entry.relocated = Some(found);
+            }
+        }
+
+        if missing.iter().any(|m| m.relocated.is_some()) {
+            persistent.save()?;
+        }
+    }
+
+    Ok(MovedFilesReport { checked, missing })
+}
+
+/// Gaps between swipes longer than this are treated as breaks, not decision time
+const MAX_DECISION_GAP_SECS: f64 = 300.0;
+
+#[tauri::command]
+pub fn estimate_triage_eta(state: State<AppState>) -> EtaEstimate {
+    let persistent = state.persistent.lock().unwrap();
+    let pending = state.pending_indices.lock().unwrap().len();
+
+    // Average the gaps between consecutive recent swipes, ignoring breaks
+    let gaps: Vec<f64> = persistent.recent_decision_times.windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|gap| *gap > 0.0 && *gap <= MAX_DECISION_GAP_SECS)
+        .collect();
+
+    if gaps.is_empty() {
+        // Cold start - not enough recent decisions to estimate from
+        return EtaEstimate {
+            pending,
+            seconds_per_decision: None,
+            seconds_remaining: None,
+            human: None,
+        };
+    }
+
+    let avg = gaps.iter().sum::<f64>() / gaps.len() as f64;
+    let seconds_remaining = (avg * pending as f64).round() as u64;
+
+    EtaEstimate {
+        pending,
+        seconds_per_decision: Some((avg * 100.0).round() / 100.0),
+        seconds_remaining: Some(seconds_remaining),
+        human: Some(format_duration(seconds_remaining)),
+    }
+}
+
+/// Format a duration in seconds as a short human-readable string
+fn format_duration(seconds: u64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+
+    if hours > 0 {
+        format!("about {}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("about {}m", minutes)
+    } else {
+        "less than a minute".to_string()
+    }
+}
+
+/// Decisions made in one hour or day, counted by their current decision
+#[derive(Serialize)]
+pub struct TimelineBucket {
+    pub start: f64, // unix time the bucket starts (local midnight / hour)
+    pub accepted: usize,
+    pub rejected: usize,
+    pub favorite: usize,
+    pub skipped: usize,
+}
+
+/// Triage decisions grouped per "hour" or "day", oldest first. `utc_offset_minutes`
+/// aligns buckets with local time (e.g. 120 for UTC+2). Decisions made before
+/// timestamps were recorded are not included.
+#[tauri::command]
+pub fn get_triage_timeline(
+    bucket: String,
+    utc_offset_minutes: Option<i32>,
+    state: State<AppState>,
+) -> Result<Vec<TimelineBucket>, String> {
+    let persistent = state.persistent.lock().unwrap();
+
+    let size = match bucket.as_str() {
+        "hour" => 3600.0,
+        "day" => 86400.0,
+        _ => return Err("Invalid bucket, expected \"hour\" or \"day\"".to_string()),
+    };
+    let offset = utc_offset_minutes.unwrap_or(0) as f64 * 60.0;
+
+    let mut buckets: std::collections::BTreeMap<i64, TimelineBucket> = std::collections::BTreeMap::new();
+    for (image_id, decision) in &persistent.decisions {
+        let Some(&decided_at) = persistent.decided_at.get(image_id) else {
+            continue;
+        };
 
-    // Find the undone image in pending
-    for (i, &idx) in pending.iter().enumerate() {
-        if image_records[idx].id == image_id {
-            persistent.current_index = i;
-            break;
+        let key = ((decided_at + offset) / size).floor() as i64;
+        let entry = buckets.entry(key).or_insert_with(|| TimelineBucket {
+            start: key as f64 * size - offset,
+            accepted: 0,
+            rejected: 0,
+            favorite: 0,
+            skipped: 0,
+        });
+        match decision.as_str() {
+            "accepted" => entry.accepted += 1,
+            "rejected" => entry.rejected += 1,
+            "favorite" => entry.favorite += 1,
+            _ => entry.skipped += 1,
         }
     }
 
-    *pending_indices = pending;
-    persistent.save()?;
+    Ok(buckets.into_values().collect())
+}
 
-    Ok(UndoResult {
-        success: true,
-        message: format!("Undone: {} -> {}", new_decision, old_decision),
-        image_id: Some(image_id),
-    })
+fn now_secs() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64()
 }
 
 #[tauri::command]
 pub fn get_preload_list(state: State<AppState>) -> Vec<String> {
+    let preload_count = state.config.lock().unwrap().preload_count.min(MAX_PRELOAD_COUNT);
     let persistent = state.persistent.lock().unwrap();
     let image_records = state.image_records.lock().unwrap();
     let pending_indices = state.pending_indices.lock().unwrap();
 
     let mut ids = Vec::new();
-    for i in 1..=6 {
+    for i in 1..=preload_count {
         let idx = persistent.current_index + i;
         if idx < pending_indices.len() {
             if let Some(record) = image_records.get(pending_indices[idx]) {
@@ -379,9 +1848,11 @@ pub fn set_mode(mode: String, state: State<AppState>) -> Result<(), String> {
         return Err("Invalid mode".to_string());
     }
 
+    // Switching modes writes straight away, covering any deferred save
     let mut persistent = state.persistent.lock().unwrap();
     persistent.mode = mode;
-    persistent.save()
+    state.state_dirty.store(false, Ordering::SeqCst);
+    persistent.save().inspect_err(|_| state.save_later())
 }
 
 // ============================================================================
@@ -404,96 +1875,684 @@ pub fn get_ranking_stats(state: State<AppState>) -> RankingStats {
             medium_uncertainty: 0,
             low_uncertainty: 0,
             avg_matches_per_photo: 0.0,
+            uncompared: 0,
+            winner_id: None,
+            estimated_remaining_comparisons: None,
         };
     }
 
-    let ratings = &ranking.ratings;
-    let total_photos = ratings.len();
+    get_ranking_stats_internal(ranking)
+}
 
-    let high_uncertainty = ratings.values().filter(|r| r.sigma >= 200.0).count();
-    let medium_uncertainty = ratings.values().filter(|r| r.sigma >= 100.0 && r.sigma < 200.0).count();
-    let low_uncertainty = ratings.values().filter(|r| r.sigma < 100.0).count();
+#[derive(Serialize)]
+pub struct RankingInitStatus {
+    pub status: String, // "ready" (stats included) or "started" (progress events follow)
+    pub total: usize,   // photos found in the ranking folder
+    pub stats: Option<RankingStats>,
+}
 
-    let avg_matches = if total_photos > 0 {
-        ratings.values().map(|r| r.matches_played).sum::<usize>() as f64 / total_photos as f64
-    } else {
-        0.0
+/// Start (or resume) a ranking session. A resumed session is returned as "ready";
+/// otherwise photos are hashed and clustered on a background thread, emitting
+/// "ranking-init-progress" ({done, total}) per hash, then "ranking-init-complete"
+/// with the RankingStats or "ranking-init-error" with a message.
+#[tauri::command]
+pub fn init_ranking(app: AppHandle, target: Option<String>, state: State<AppState>) -> Result<RankingInitStatus, String> {
+    if state.ranking_init_running.swap(true, Ordering::SeqCst) {
+        return Err("Ranking initialization is already running".to_string());
+    }
+
+    let (target, photos) = match prepare_ranking_init(target, &state) {
+        Ok(RankingInit::Scan(target, photos)) => (target, photos),
+        Ok(RankingInit::Ready(stats)) => {
+            state.ranking_init_running.store(false, Ordering::SeqCst);
+            return Ok(RankingInitStatus { status: "ready".to_string(), total: stats.total_photos, stats: Some(stats) });
+        }
+        Err(e) => {
+            state.ranking_init_running.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
     };
 
-    RankingStats {
-        initialized: true,
-        total_photos,
-        total_comparisons: ranking.total_comparisons,
-        cluster_count: ranking.cluster_count,
-        phase: ranking.phase.clone(),
-        high_uncertainty,
-        medium_uncertainty,
-        low_uncertainty,
-        avg_matches_per_photo: (avg_matches * 100.0).round() / 100.0,
+    let total = photos.len();
+    std::thread::spawn(move || {
+        let state = app.state::<AppState>();
+        let _running = InitGuard(&state.ranking_init_running);
+
+        let result = run_ranking_init(&state, &target, &photos, |done, total| {
+            let _ = app.emit("ranking-init-progress", ProgressEvent { done, total });
+        });
+        let emitted = match result {
+            Ok(stats) => app.emit("ranking-init-complete", stats),
+            Err(e) => app.emit("ranking-init-error", e),
+        };
+        if let Err(e) = emitted {
+            eprintln!("Warning: Could not emit ranking init result: {}", e);
+        }
+    });
+
+    Ok(RankingInitStatus { status: "started".to_string(), total, stats: None })
+}
+
+/// Clears a background pass's running flag when it ends, even if it panics
+struct InitGuard<'a>(&'a AtomicBool);
+
+impl Drop for InitGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+enum RankingInit {
+    /// A parked, already initialized session was resumed
+    Ready(RankingStats),
+    /// Photos (id -> path) in the target folder that still need hashing and clustering
+    Scan(String, HashMap<String, std::path::PathBuf>),
+}
+
+/// Switch to the requested target (parking the current session) and scan its folder
+fn prepare_ranking_init(target: Option<String>, state: &AppState) -> Result<RankingInit, String> {
+    let config = state.config.lock().unwrap();
+    config.rating.validate()?;
+    let mut persistent = state.persistent.lock().unwrap();
+
+    // Default to rescanning the current session's folder
+    let target = target.unwrap_or_else(|| persistent.ranking.target.clone());
+    if target != "accepted" && target != "rejected" {
+        return Err(format!("Invalid ranking target: {}", target));
+    }
+
+    // Switching target parks the current session and resumes the other one, if any
+    if persistent.ranking.target != target {
+        let resumed = persistent.parked_rankings.remove(&target)
+            .unwrap_or_else(|| RankingState { target: target.clone(), ..Default::default() });
+        let parked = std::mem::replace(&mut persistent.ranking, resumed);
+        persistent.parked_rankings.insert(parked.target.clone(), parked);
+
+        if persistent.ranking.initialized {
+            persistent.save()?;
+            return Ok(RankingInit::Ready(get_ranking_stats_internal(&persistent.ranking)));
+        }
+    }
+
+    // Scan the target folder
+    let photos = scan_accepted_photos(config.ranking_folder(&target), &config.supported_extensions());
+    if photos.is_empty() {
+        return Err(format!("No photos found in {} folder", target));
+    }
+
+    Ok(RankingInit::Scan(target, photos))
+}
+
+/// Hash photos that need it, then cluster and reset the ranking session.
+/// No lock is held while hashing; the hash cache is saved every few hashes
+/// so an interrupted run keeps most of its work.
+fn run_ranking_init(
+    state: &AppState,
+    target: &str,
+    photos: &HashMap<String, std::path::PathBuf>,
+    progress: impl Fn(usize, usize),
+) -> Result<RankingStats, String> {
+    let (algorithm, cluster_threshold, linkage, rating) = {
+        let config = state.config.lock().unwrap();
+        (config.hash_algorithm, config.cluster_threshold, config.cluster_linkage, config.rating.clone())
+    };
+
+    // Photos without a hash, hashed with another algorithm, or changed on disk since
+    let stale: Vec<(&String, &std::path::PathBuf)> = {
+        let photo_hashes = state.photo_hashes.lock().unwrap();
+        photos.iter()
+            .filter(|(id, path)| !photo_hashes.get(*id).is_some_and(|entry| entry.is_current(path, algorithm)))
+            .collect()
+    };
+
+    let total = stale.len();
+    for (done, (photo_id, path)) in stale.into_iter().enumerate() {
+        if let Some(hash) = compute_hash(path, algorithm) {
+            let mut photo_hashes = state.photo_hashes.lock().unwrap();
+            photo_hashes.insert(photo_id.clone(), PhotoHash::new(hash, algorithm, path));
+            if (done + 1) % HASH_SAVE_INTERVAL == 0 {
+                if let Err(e) = save_photo_hashes(&photo_hashes) {
+                    eprintln!("Warning: Could not save photo hashes: {}", e);
+                }
+            }
+        }
+        progress(done + 1, total);
+    }
+
+    let mut persistent = state.persistent.lock().unwrap();
+    if persistent.ranking.target != target {
+        return Err("Ranking target changed during initialization".to_string());
+    }
+
+    let photo_hashes = state.photo_hashes.lock().unwrap();
+    save_photo_hashes(&photo_hashes)?;
+
+    // Initialize ratings
+    let photo_ids: Vec<_> = photos.keys().cloned().collect();
+    let ratings = initialize_ratings(&photo_ids, &rating);
+
+    // Cluster photos
+    let hashes: HashMap<String, String> = photos.keys()
+        .filter_map(|id| photo_hashes.get(id).map(|entry| (id.clone(), entry.hash.clone())))
+        .collect();
+    drop(photo_hashes);
+    let (clusters_raw, photo_to_cluster) = cluster_photos(&hashes, cluster_threshold, linkage);
+
+    // Convert to Cluster structs
+    let clusters: HashMap<String, Cluster> = clusters_raw.into_iter()
+        .map(|(id, photo_ids)| {
+            let complete = photo_ids.len() < 2;
+            (id.clone(), Cluster {
+                id,
+                photo_ids,
+                representative_id: None,
+                internal_ranking_complete: complete,
+            })
+        })
+        .collect();
+
+    // Update ranking state
+    persistent.ranking.initialized = true;
+    persistent.ranking.ratings = ratings;
+    persistent.ranking.clusters = clusters.clone();
+    persistent.ranking.photo_to_cluster = photo_to_cluster;
+    persistent.ranking.comparison_history = Vec::new();
+    persistent.ranking.pair_queue = Vec::new();
+    persistent.ranking.current_pair = None;
+    persistent.ranking.bracket = Vec::new();
+    persistent.ranking.winner_id = None;
+    persistent.ranking.total_comparisons = 0;
+    persistent.ranking.phase = if clusters.is_empty() { "global".to_string() } else { "intra_cluster".to_string() };
+    persistent.ranking.photo_count = photos.len();
+    persistent.ranking.cluster_count = clusters.len();
+
+    persistent.save()?;
+
+    Ok(get_ranking_stats_internal(&persistent.ranking))
+}
+
+/// List clusters (largest first) with their members' paths and scores
+#[tauri::command]
+pub fn get_clusters(state: State<AppState>) -> Vec<ClusterInfo> {
+    let config = state.config.lock().unwrap();
+    let persistent = state.persistent.lock().unwrap();
+    let ranking = &persistent.ranking;
+
+    if !ranking.initialized {
+        return Vec::new();
+    }
+
+    let photos = folder_photos(&state, config.ranking_folder(&ranking.target), &config.supported_extensions());
+
+    let mut clusters: Vec<ClusterInfo> = ranking.clusters.values()
+        .map(|cluster| {
+            let mut members: Vec<ClusterMember> = cluster.photo_ids.iter()
+                .map(|id| {
+                    let score = ranking.ratings.get(id)
+                        .map(|r| get_conservative_score(r.mu, r.sigma))
+                        .unwrap_or(0.0);
+                    ClusterMember {
+                        id: id.clone(),
+                        file_path: photos.get(id).map(|p| p.to_string_lossy().to_string()).unwrap_or_default(),
+                        score: (score * 10.0).round() / 10.0,
+                    }
+                })
+                .collect();
+            members.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+
+            ClusterInfo {
+                id: cluster.id.clone(),
+                size: cluster.photo_ids.len(),
+                representative_id: cluster.representative_id.clone(),
+                internal_ranking_complete: cluster.internal_ranking_complete,
+                members,
+            }
+        })
+        .collect();
+
+    clusters.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.id.cmp(&b.id)));
+    clusters
+}
+
+/// Hamming distances within a cluster, from its cached hashes. The representative
+/// is the ranking representative once chosen, otherwise the photo that founded
+/// the cluster (the one others were matched against).
+#[tauri::command]
+pub fn get_cluster_distances(cluster_id: String, state: State<AppState>) -> Result<ClusterDistances, String> {
+    let threshold = state.config.lock().unwrap().cluster_threshold;
+    let persistent = state.persistent.lock().unwrap();
+    let photo_hashes = state.photo_hashes.lock().unwrap();
+
+    let cluster = persistent.ranking.clusters.get(&cluster_id)
+        .ok_or_else(|| format!("Unknown cluster: {}", cluster_id))?;
+    let representative_id = cluster.representative_id.clone()
+        .or_else(|| cluster.photo_ids.first().cloned())
+        .ok_or_else(|| format!("Cluster {} is empty", cluster_id))?;
+
+    let hash_of = |id: &String| {
+        photo_hashes.get(id)
+            .map(|entry| entry.hash.as_str())
+            .ok_or_else(|| format!("No cached hash for photo {}", id))
+    };
+    let hashes = cluster.photo_ids.iter().map(hash_of).collect::<Result<Vec<_>, _>>()?;
+    let representative_hash = hash_of(&representative_id)?;
+
+    Ok(ClusterDistances {
+        to_representative: hashes.iter().map(|h| hamming_distance(h, representative_hash)).collect(),
+        matrix: hashes.iter()
+            .map(|a| hashes.iter().map(|b| hamming_distance(a, b)).collect())
+            .collect(),
+        photo_ids: cluster.photo_ids.clone(),
+        cluster_id,
+        representative_id,
+        threshold,
+    })
+}
+
+/// Merge `cluster_b` into `cluster_a`; the combined cluster is ranked again
+#[tauri::command]
+pub fn merge_clusters(cluster_a: String, cluster_b: String, state: State<AppState>) -> Result<RankingStats, String> {
+    let mut persistent = state.persistent.lock().unwrap();
+    let ranking = &mut persistent.ranking;
+
+    if cluster_a == cluster_b {
+        return Err("Cannot merge a cluster with itself".to_string());
+    }
+    if !ranking.clusters.contains_key(&cluster_a) {
+        return Err(format!("Cluster not found: {}", cluster_a));
+    }
+    let merged = ranking.clusters.remove(&cluster_b)
+        .ok_or_else(|| format!("Cluster not found: {}", cluster_b))?;
+
+    for photo_id in &merged.photo_ids {
+        ranking.photo_to_cluster.insert(photo_id.clone(), cluster_a.clone());
+    }
+
+    let cluster = ranking.clusters.get_mut(&cluster_a).unwrap();
+    cluster.photo_ids.extend(merged.photo_ids);
+    cluster.internal_ranking_complete = false;
+    cluster.representative_id = None;
+    ranking.cluster_count = ranking.cluster_count.saturating_sub(1);
+
+    // Go back to intra-cluster pairing so the merged cluster gets compared
+    if ranking.phase == "global" {
+        ranking.phase = "intra_cluster".to_string();
+    }
+    ranking.pair_queue.clear();
+
+    state.save_later();
+    Ok(get_ranking_stats_internal(&persistent.ranking))
+}
+
+/// Move a photo out of its cluster into a new single-member cluster
+#[tauri::command]
+pub fn split_photo_from_cluster(photo_id: String, state: State<AppState>) -> Result<RankingStats, String> {
+    let mut persistent = state.persistent.lock().unwrap();
+    let ranking = &mut persistent.ranking;
+
+    let old_id = ranking.photo_to_cluster.get(&photo_id)
+        .cloned()
+        .ok_or_else(|| format!("Photo is not in a cluster: {}", photo_id))?;
+    let old_cluster = ranking.clusters.get_mut(&old_id)
+        .ok_or_else(|| format!("Cluster not found: {}", old_id))?;
+
+    if old_cluster.photo_ids.len() < 2 {
+        return Err("Photo is already alone in its cluster".to_string());
+    }
+
+    old_cluster.photo_ids.retain(|id| id != &photo_id);
+    if old_cluster.representative_id.as_deref() == Some(photo_id.as_str()) {
+        old_cluster.representative_id = None;
+    }
+    // Single-member clusters need no internal ranking
+    if old_cluster.photo_ids.len() < 2 {
+        old_cluster.internal_ranking_complete = true;
+    }
+
+    let new_id = (ranking.clusters.len()..)
+        .map(|i| format!("cluster_{:04}", i))
+        .find(|id| !ranking.clusters.contains_key(id))
+        .unwrap();
+    ranking.clusters.insert(new_id.clone(), Cluster {
+        id: new_id.clone(),
+        photo_ids: vec![photo_id.clone()],
+        representative_id: None,
+        internal_ranking_complete: true,
+    });
+    ranking.photo_to_cluster.insert(photo_id, new_id);
+    ranking.cluster_count += 1;
+    ranking.pair_queue.clear();
+
+    state.save_later();
+    Ok(get_ranking_stats_internal(&persistent.ranking))
+}
+
+/// Recompute one photo's hash after it was edited and move it to the cluster it
+/// now matches (or a new one of its own), without re-running init_ranking.
+/// Returns the photo's cluster id.
+#[tauri::command]
+pub fn rehash_photo(photo_id: String, state: State<AppState>) -> Result<String, String> {
+    let config = state.config.lock().unwrap();
+    let mut persistent = state.persistent.lock().unwrap();
+
+    if !persistent.ranking.initialized {
+        return Err("Ranking not initialized".to_string());
+    }
+    let photos = folder_photos(&state, config.ranking_folder(&persistent.ranking.target), &config.supported_extensions());
+    let path = photos.get(&photo_id)
+        .ok_or_else(|| format!("Photo not found: {}", photo_id))?;
+    let hash = compute_hash(path, config.hash_algorithm)
+        .ok_or_else(|| format!("Could not hash {}", path.display()))?;
+
+    let mut photo_hashes = state.photo_hashes.lock().unwrap();
+    photo_hashes.insert(photo_id.clone(), PhotoHash::new(hash.clone(), config.hash_algorithm, path));
+    save_photo_hashes(&photo_hashes)?;
+
+    // First cluster (in id order) the new hash matches, not counting the photo itself
+    let ranking = &mut persistent.ranking;
+    let mut cluster_ids: Vec<&String> = ranking.clusters.keys().collect();
+    cluster_ids.sort();
+    let target = cluster_ids.into_iter()
+        .find(|cluster_id| {
+            let members: Vec<&str> = ranking.clusters[*cluster_id].photo_ids.iter()
+                .filter(|id| **id != photo_id)
+                .filter_map(|id| photo_hashes.get(id).map(|entry| entry.hash.as_str()))
+                .collect();
+            matches_cluster(&hash, &members, config.cluster_threshold, config.cluster_linkage)
+        })
+        .cloned();
+    drop(photo_hashes);
+
+    // Still matches its own cluster: only the hash changed
+    let old_id = ranking.photo_to_cluster.get(&photo_id).cloned();
+    if let Some(old_id) = old_id.as_ref().filter(|id| target.as_ref() == Some(*id)) {
+        return Ok(old_id.clone());
+    }
+
+    // Leave the old cluster
+    if let Some(old_cluster) = old_id.as_ref().and_then(|id| ranking.clusters.get_mut(id)) {
+        old_cluster.photo_ids.retain(|id| id != &photo_id);
+        if old_cluster.representative_id.as_deref() == Some(photo_id.as_str()) {
+            old_cluster.representative_id = None;
+        }
+        // Single-member clusters need no internal ranking
+        if old_cluster.photo_ids.len() < 2 {
+            old_cluster.internal_ranking_complete = true;
+        }
+        if old_cluster.photo_ids.is_empty() {
+            ranking.clusters.remove(old_id.as_ref().unwrap());
+            ranking.cluster_count = ranking.cluster_count.saturating_sub(1);
+        }
+    }
+
+    let new_id = match target {
+        Some(target) => {
+            let cluster = ranking.clusters.get_mut(&target).unwrap();
+            cluster.photo_ids.push(photo_id.clone());
+            cluster.internal_ranking_complete = false;
+            cluster.representative_id = None;
+            // Go back to intra-cluster pairing so the newcomer gets compared
+            if ranking.phase == "global" {
+                ranking.phase = "intra_cluster".to_string();
+            }
+            target
+        }
+        None => {
+            let new_id = (ranking.clusters.len()..)
+                .map(|i| format!("cluster_{:04}", i))
+                .find(|id| !ranking.clusters.contains_key(id))
+                .unwrap();
+            ranking.clusters.insert(new_id.clone(), Cluster {
+                id: new_id.clone(),
+                photo_ids: vec![photo_id.clone()],
+                representative_id: None,
+                internal_ranking_complete: true,
+            });
+            ranking.cluster_count += 1;
+            new_id
+        }
+    };
+    ranking.photo_to_cluster.insert(photo_id, new_id.clone());
+    ranking.pair_queue.clear();
+
+    persistent.save()?;
+    Ok(new_id)
+}
+
+/// Apply a triage decision to every photo in a ranking cluster, moving the files
+/// out of the ranked folder, optionally sparing the representative (or the
+/// best-rated member when none has been chosen yet). Each move is recorded in the
+/// triage history so it can be undone; moved photos leave the ranking session
+/// until then, and undo restores their rating and cluster.
+#[tauri::command]
+pub fn decide_cluster(
+    cluster_id: String,
+    decision: String,
+    keep_representative: bool,
+    state: State<AppState>,
+) -> Result<ClusterDecisionResult, String> {
+    let config = state.config.lock().unwrap();
+    if config.dry_run {
+        return Err("Cluster decisions move files right away; turn off dry run first".to_string());
+    }
+    let mut persistent = state.persistent.lock().unwrap();
+
+    let folder = config.ranking_folder(&persistent.ranking.target).to_string();
+    let destination = config.destination_for(&decision)
+        .ok_or_else(|| format!("Invalid decision: {}", decision))?;
+    if std::path::Path::new(destination) == std::path::Path::new(&folder) {
+        return Err(format!("Photos in this cluster are already in {}", folder));
+    }
+
+    let ranking = &persistent.ranking;
+    let cluster = ranking.clusters.get(&cluster_id)
+        .ok_or_else(|| format!("Unknown cluster: {}", cluster_id))?;
+    let representative = cluster.representative_id.clone()
+        .or_else(|| best_in_cluster(cluster, &ranking.ratings).cloned());
+    let members: Vec<String> = cluster.photo_ids.iter()
+        .filter(|id| !keep_representative || Some(*id) != representative.as_ref())
+        .cloned()
+        .collect();
+    let kept = cluster.photo_ids.len() - members.len();
+
+    let photos = folder_photos(&state, &folder, &config.supported_extensions());
+    let mut moved_ids = Vec::new();
+    let mut failed = 0;
+
+    for photo_id in members {
+        let Some(path) = photos.get(&photo_id) else {
+            eprintln!("Warning: Photo {} is no longer in {}", photo_id, folder);
+            failed += 1;
+            continue;
+        };
+        let record = ImageRecord {
+            id: photo_id.clone(),
+            source_folder: folder.clone(),
+            relative_path: path.strip_prefix(&folder).unwrap_or(path).to_string_lossy().to_string(),
+            timestamp: None,
+            size: 0,
+        };
+
+        match move_image(&record, &decision, &config) {
+            Ok(moved) => {
+                if let Some(moved) = moved {
+                    persistent.original_paths.insert(photo_id.clone(), path.to_string_lossy().to_string());
+                    persistent.moved_files.insert(photo_id.clone(), moved.path);
+                    if config.operation == "copy" {
+                        persistent.copied_files.insert(photo_id.clone());
+                    }
+                    if !moved.extra_copies.is_empty() {
+                        persistent.extra_copies.insert(photo_id.clone(), moved.extra_copies);
+                    }
+                }
+                persistent.record_decision(&photo_id, &decision, None, now_secs());
+                moved_ids.push(photo_id);
+            }
+            Err(e) => {
+                eprintln!("Warning: {}", e);
+                failed += 1;
+            }
+        }
+    }
+
+    // Moved photos can no longer be compared (copies stay in the ranked folder).
+    // What they lose is kept so undoing the decision puts them back.
+    if config.operation != "copy" && !moved_ids.is_empty() {
+        let persistent = &mut *persistent;
+        // Entries whose decision has aged out of the undo history are no use any more
+        let in_history: HashSet<&String> = persistent.history.iter().map(|(id, _, _)| id).collect();
+        persistent.ranked_removals.retain(|id, _| in_history.contains(id));
+
+        let ranking = &mut persistent.ranking;
+        for photo_id in &moved_ids {
+            let Some(rating) = ranking.ratings.remove(photo_id) else {
+                continue;
+            };
+            let cluster_id = ranking.photo_to_cluster.remove(photo_id);
+            let representative = cluster_id.as_ref()
+                .and_then(|id| ranking.clusters.get(id))
+                .is_some_and(|cluster| cluster.representative_id.as_ref() == Some(photo_id));
+            persistent.ranked_removals.insert(photo_id.clone(), RankedRemoval {
+                target: ranking.target.clone(),
+                rating,
+                cluster_id,
+                representative,
+            });
+        }
+        if let Some(cluster) = ranking.clusters.get_mut(&cluster_id) {
+            cluster.photo_ids.retain(|id| !moved_ids.contains(id));
+            if cluster.representative_id.as_ref().is_some_and(|id| moved_ids.contains(id)) {
+                cluster.representative_id = None;
+            }
+            // Single-member clusters need no internal ranking
+            if cluster.photo_ids.len() < 2 {
+                cluster.internal_ranking_complete = true;
+            }
+            if cluster.photo_ids.is_empty() {
+                ranking.clusters.remove(&cluster_id);
+                ranking.cluster_count = ranking.cluster_count.saturating_sub(1);
+            }
+        }
+        ranking.photo_count = ranking.ratings.len();
+        ranking.pair_queue.clear();
+        if ranking.current_pair.as_ref().is_some_and(|(a, b)| moved_ids.contains(a) || moved_ids.contains(b)) {
+            ranking.current_pair = None;
+        }
+    }
+    invalidate_folder_scans(&state);
+    state.save_later();
+
+    Ok(ClusterDecisionResult { moved: moved_ids.len(), kept, failed })
+}
+
+/// Set how global-phase pairs are chosen; queued pairs are dropped so the next pair uses it
+#[tauri::command]
+pub fn set_pair_strategy(strategy: PairStrategy, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().unwrap();
+    config.pair_strategy = strategy;
+    config.save()?;
+
+    let mut persistent = state.persistent.lock().unwrap();
+    persistent.ranking.pair_queue.clear();
+    persistent.save()
+}
+
+/// Finish intra-cluster ranking early: every cluster is finalized with its current
+/// ratings (picking a representative) and pairing moves to the global phase.
+/// Ratings are kept; does nothing outside the intra-cluster phase.
+#[tauri::command]
+pub fn skip_to_global(state: State<AppState>) -> Result<RankingStats, String> {
+    let mut persistent = state.persistent.lock().unwrap();
+    let ranking = &mut persistent.ranking;
+
+    if !ranking.initialized {
+        return Err("Ranking not initialized".to_string());
+    }
+    if ranking.phase != "intra_cluster" {
+        return Ok(get_ranking_stats_internal(ranking));
+    }
+
+    for cluster in ranking.clusters.values_mut() {
+        finalize_cluster(cluster, &ranking.ratings);
+    }
+    ranking.phase = "global".to_string();
+    // Queued pairs were picked within clusters
+    ranking.pair_queue.clear();
+    ranking.current_pair = None;
+
+    persistent.save()?;
+    Ok(get_ranking_stats_internal(&persistent.ranking))
+}
+
+/// Start a single-elimination tournament seeded by conservative score
+#[tauri::command]
+pub fn start_tournament(state: State<AppState>) -> Result<RankingStats, String> {
+    let mut persistent = state.persistent.lock().unwrap();
+    let ranking = &mut persistent.ranking;
+
+    if !ranking.initialized {
+        return Err("Ranking not initialized".to_string());
     }
+
+    let (bracket, champion) = seed_bracket(&ranking.ratings);
+    ranking.bracket = bracket;
+    ranking.winner_id = champion;
+    ranking.phase = "tournament".to_string();
+    ranking.pair_queue.clear();
+    ranking.current_pair = None;
+
+    persistent.save()?;
+    Ok(get_ranking_stats_internal(&persistent.ranking))
 }
 
+/// Leave tournament mode and go back to global Glicko pairing
 #[tauri::command]
-pub fn init_ranking(state: State<AppState>) -> Result<RankingStats, String> {
-    let config = state.config.lock().unwrap();
+pub fn end_tournament(state: State<AppState>) -> Result<RankingStats, String> {
     let mut persistent = state.persistent.lock().unwrap();
-    let mut photo_hashes = state.photo_hashes.lock().unwrap();
-
-    // Scan accepted photos
-    let photos = scan_accepted_photos(&config.accepted_folder);
-    if photos.is_empty() {
-        return Err("No photos found in Accepted folder".to_string());
+    let ranking = &mut persistent.ranking;
+
+    if ranking.phase == "tournament" {
+        ranking.phase = "global".to_string();
+        ranking.bracket.clear();
+        ranking.pair_queue.clear();
+        ranking.current_pair = None;
+        persistent.save()?;
     }
 
-    // Initialize ratings
-    let photo_ids: Vec<_> = photos.keys().cloned().collect();
-    let ratings = initialize_ratings(&photo_ids);
+    Ok(get_ranking_stats_internal(&persistent.ranking))
+}
 
-    // Compute hashes for photos that don't have them
-    for (photo_id, path) in &photos {
-        if !photo_hashes.contains_key(photo_id) {
-            if let Some(hash) = compute_dhash(path) {
-                photo_hashes.insert(photo_id.clone(), hash);
-            }
-        }
+/// Set the clustering threshold; takes effect the next time ranking is initialized
+#[tauri::command]
+pub fn set_cluster_threshold(threshold: u32, state: State<AppState>) -> Result<(), String> {
+    if threshold > MAX_HAMMING_THRESHOLD {
+        return Err(format!("Cluster threshold must be between 0 and {}", MAX_HAMMING_THRESHOLD));
     }
 
-    // Save hashes
-    save_photo_hashes(&photo_hashes)?;
-
-    // Cluster photos
-    let (clusters_raw, photo_to_cluster) = cluster_photos(&photo_hashes);
-
-    // Convert to Cluster structs
-    let clusters: HashMap<String, Cluster> = clusters_raw.into_iter()
-        .map(|(id, photo_ids)| {
-            let complete = photo_ids.len() < 2;
-            (id.clone(), Cluster {
-                id,
-                photo_ids,
-                representative_id: None,
-                internal_ranking_complete: complete,
-            })
-        })
-        .collect();
-
-    // Update ranking state
-    persistent.ranking.initialized = true;
-    persistent.ranking.ratings = ratings;
-    persistent.ranking.clusters = clusters.clone();
-    persistent.ranking.photo_to_cluster = photo_to_cluster;
-    persistent.ranking.comparison_history = Vec::new();
-    persistent.ranking.total_comparisons = 0;
-    persistent.ranking.phase = if clusters.is_empty() { "global".to_string() } else { "intra_cluster".to_string() };
-    persistent.ranking.photo_count = photos.len();
-    persistent.ranking.cluster_count = clusters.len();
+    let mut config = state.config.lock().unwrap();
+    config.cluster_threshold = threshold;
+    config.save()
+}
 
+/// Clear all ranking progress (ratings, clusters, comparisons) without touching triage.
+/// Cached perceptual hashes are kept unless `keep_hashes` is false.
+#[tauri::command]
+pub fn reset_ranking(keep_hashes: Option<bool>, state: State<AppState>) -> Result<RankingStats, String> {
+    let mut persistent = state.persistent.lock().unwrap();
+    let target = persistent.ranking.target.clone();
+    persistent.ranking = RankingState { target, ..Default::default() };
     persistent.save()?;
 
+    if !keep_hashes.unwrap_or(true) {
+        let mut photo_hashes = state.photo_hashes.lock().unwrap();
+        photo_hashes.clear();
+        save_photo_hashes(&photo_hashes)?;
+    }
+
     Ok(get_ranking_stats_internal(&persistent.ranking))
 }
 
-fn get_ranking_stats_internal(ranking: &crate::state::RankingState) -> RankingStats {
+fn get_ranking_stats_internal(ranking: &RankingState) -> RankingStats {
     let ratings = &ranking.ratings;
     let total_photos = ratings.len();
 
@@ -517,13 +2576,16 @@ fn get_ranking_stats_internal(ranking: &crate::state::RankingState) -> RankingSt
         medium_uncertainty,
         low_uncertainty,
         avg_matches_per_photo: (avg_matches * 100.0).round() / 100.0,
+        uncompared: ratings.values().filter(|r| r.matches_played == 0).count(),
+        winner_id: ranking.winner_id.clone(),
+        estimated_remaining_comparisons: estimate_remaining_comparisons(ranking),
     }
 }
 
 #[tauri::command]
 pub fn get_pair(state: State<AppState>) -> PairInfo {
     let config = state.config.lock().unwrap();
-    let persistent = state.persistent.lock().unwrap();
+    let mut persistent = state.persistent.lock().unwrap();
 
     if !persistent.ranking.initialized {
         return PairInfo {
@@ -536,7 +2598,40 @@ pub fn get_pair(state: State<AppState>) -> PairInfo {
         };
     }
 
-    let pair = select_pair(&persistent.ranking);
+    // File paths come from the folder this session ranks
+    let folder = config.ranking_folder(&persistent.ranking.target);
+    let photos = folder_photos(&state, folder, &config.supported_extensions());
+
+    // Re-show the pair that was on screen if it hasn't been decided yet and both
+    // photos are still rated and on disk (files can be removed between sessions)
+    let pending_pair = persistent.ranking.current_pair.clone().filter(|(left, right)| {
+        [left, right].iter().all(|id| persistent.ranking.ratings.contains_key(*id) && photos.contains_key(*id))
+    });
+
+    let pair = match pending_pair {
+        Some(pair) => Some(pair),
+        None => {
+            // Serve from the pre-selected queue, refilling it lazily when empty
+            if persistent.ranking.pair_queue.is_empty() {
+                fill_pair_queue(
+                    &mut persistent.ranking,
+                    config.pair_queue_size.max(1),
+                    config.pair_strategy,
+                    &config.cluster_convergence(),
+                    config.uncompared_pair_probability,
+                );
+            }
+            let next = if persistent.ranking.pair_queue.is_empty() {
+                None
+            } else {
+                Some(persistent.ranking.pair_queue.remove(0))
+            };
+
+            persistent.ranking.current_pair = next.clone();
+            state.save_later();
+            next
+        }
+    };
 
     match pair {
         Some((left_id, right_id)) => {
@@ -545,8 +2640,6 @@ pub fn get_pair(state: State<AppState>) -> PairInfo {
             let left_rating = ratings.get(&left_id).cloned().unwrap_or_default();
             let right_rating = ratings.get(&right_id).cloned().unwrap_or_default();
 
-            // Get file paths from accepted folder
-            let photos = scan_accepted_photos(&config.accepted_folder);
             let left_path = photos.get(&left_id).map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
             let right_path = photos.get(&right_id).map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
 
@@ -582,155 +2675,793 @@ pub fn get_pair(state: State<AppState>) -> PairInfo {
     }
 }
 
+/// Record the outcome of a comparison. `result` is one of:
+/// - "left" / "right": that photo wins (Glicko-2 update for both)
+/// - "tie": equally good; both are rated against each other as a draw
+/// - "both_good" / "both_bad": no winner; both move up / down a little, independent of each other
+/// - "skip": no change to ratings or match counts
+///
+/// `confidence` (0.5-1.0, default 1.0) weights a win: 1.0 is an obvious call, 0.5
+/// a coin flip that moves ratings like a tie.
+#[tauri::command]
+pub fn compare(
+    app: AppHandle,
+    left_id: String,
+    right_id: String,
+    result: String,
+    confidence: Option<f64>,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let confidence = confidence.unwrap_or(1.0);
+    if !(0.5..=1.0).contains(&confidence) {
+        return Err("Confidence must be between 0.5 and 1.0".to_string());
+    }
+
+    let (tau, rating_config) = {
+        let config = state.config.lock().unwrap();
+        (config.glicko_tau, config.rating.clone())
+    };
+    let mut persistent = state.persistent.lock().unwrap();
+
+    if !persistent.ranking.initialized {
+        return Err("Ranking not initialized".to_string());
+    }
+    let had_pair = has_pair(&persistent.ranking);
+
+    let in_tournament = persistent.ranking.phase == "tournament";
+    let bracket_before = in_tournament.then(|| persistent.ranking.bracket.clone());
+
+    let ratings = &mut persistent.ranking.ratings;
+
+    let left = ratings.get(&left_id).ok_or("Left photo not found")?.clone();
+    let right = ratings.get(&right_id).ok_or("Right photo not found")?.clone();
+
+    // Store for undo
+    let record = ComparisonRecord {
+        left_id: left_id.clone(),
+        right_id: right_id.clone(),
+        result: result.clone(),
+        left_mu_before: left.mu,
+        left_sigma_before: left.sigma,
+        right_mu_before: right.mu,
+        right_sigma_before: right.sigma,
+        left_volatility_before: left.volatility,
+        right_volatility_before: right.volatility,
+        bracket_before,
+        batch_with_previous: false,
+        timestamp: now_secs(),
+    };
+
+    if result == "both_good" || result == "both_bad" {
+        let direction = if result == "both_good" { 1.0 } else { -1.0 };
+        ratings.insert(left_id.clone(), glicko_adjust_both(&left, direction));
+        ratings.insert(right_id.clone(), glicko_adjust_both(&right, direction));
+
+        ratings.get_mut(&left_id).unwrap().matches_played += 1;
+        ratings.get_mut(&right_id).unwrap().matches_played += 1;
+    } else if result != "skip" {
+        let is_tie = result == "tie";
+
+        let (winner_id, winner, loser_id, loser) = if result == "left" || is_tie {
+            (&left_id, &left, &right_id, &right)
+        } else {
+            (&right_id, &right, &left_id, &left)
+        };
+
+        let winner_score = if is_tie { 0.5 } else { confidence };
+        let (new_winner, new_loser) = glicko_update(winner, loser, winner_score, tau, &rating_config);
+
+        // Apply updates
+        ratings.insert(winner_id.clone(), new_winner);
+        ratings.insert(loser_id.clone(), new_loser);
+
+        // Increment match counts
+        ratings.get_mut(&left_id).unwrap().matches_played += 1;
+        ratings.get_mut(&right_id).unwrap().matches_played += 1;
+
+        // Tournament: the loser is eliminated; ties are replayed
+        if in_tournament && !is_tie {
+            let ranking = &mut persistent.ranking;
+            let is_current_match = tournament_pair(&ranking.bracket)
+                .is_some_and(|(a, b)| (a == left_id && b == right_id) || (a == right_id && b == left_id));
+            if is_current_match {
+                let winner = if result == "left" { &left_id } else { &right_id };
+                if let Some(champion) = advance_bracket(&mut ranking.bracket, winner) {
+                    ranking.winner_id = Some(champion);
+                }
+            }
+        }
+    }
+
+    persistent.ranking.current_pair = None;
+    log_comparison(&mut persistent.ranking, record);
+    finish_intra_cluster_if_complete(&mut persistent.ranking);
+
+    state.save_later();
+
+    if had_pair && !has_pair(&persistent.ranking) {
+        emit_ranking_complete(&app, &persistent.ranking);
+    }
+    Ok(())
+}
+
+/// Whether `select_pair` still has a pair to offer, without running it: outside a
+/// tournament that only needs two rated photos, in one the next bracket match
+fn has_pair(ranking: &RankingState) -> bool {
+    if ranking.phase == "tournament" {
+        tournament_pair(&ranking.bracket).is_some()
+    } else {
+        ranking.ratings.len() >= 2
+    }
+}
+
+/// Tell the frontend there are no pairs left to compare, with the final stats.
+/// Callers only emit on the transition from having a pair to none.
+fn emit_ranking_complete(app: &AppHandle, ranking: &RankingState) {
+    if let Err(e) = app.emit("ranking-complete", get_ranking_stats_internal(ranking)) {
+        eprintln!("Warning: Could not emit ranking-complete: {}", e);
+    }
+}
+
+/// Add a comparison to the history and forget queued pairs it made stale
+fn log_comparison(ranking: &mut RankingState, record: ComparisonRecord) {
+    // Queued pairs with either photo were chosen from stale ratings
+    invalidate_queued_pairs(&mut ranking.pair_queue, &record.left_id, &record.right_id);
+    record_recent_pair(ranking, &record.left_id, &record.right_id);
+
+    ranking.comparison_history.push(record);
+    ranking.total_comparisons += 1;
+    trim_comparison_history(ranking);
+}
+
+/// Trim history into the lasting win/loss counters, never keeping half of a
+/// batch that undo reverses as a whole
+fn trim_comparison_history(ranking: &mut RankingState) {
+    if ranking.comparison_history.len() <= MAX_COMPARISON_HISTORY {
+        return;
+    }
+    let mut trim = ranking.comparison_history.len() - MAX_COMPARISON_HISTORY;
+    while ranking.comparison_history.get(trim).is_some_and(|r| r.batch_with_previous) {
+        trim += 1;
+    }
+    let trimmed: Vec<ComparisonRecord> = ranking.comparison_history.drain(..trim).collect();
+    for record in &trimmed {
+        fold_comparison(ranking, record);
+    }
+}
+
+/// Switch from intra_cluster to global once every cluster is complete
+fn finish_intra_cluster_if_complete(ranking: &mut RankingState) {
+    if ranking.phase == "intra_cluster" && ranking.clusters.values().all(|c| c.internal_ranking_complete) {
+        ranking.phase = "global".to_string();
+        ranking.pair_queue.clear();
+    }
+}
+
+/// Three photos to pick the best of, chosen like a pair (see `select_triple`)
+#[tauri::command]
+pub fn get_triple(state: State<AppState>) -> Result<TripleInfo, String> {
+    let config = state.config.lock().unwrap();
+    let persistent = state.persistent.lock().unwrap();
+    let ranking = &persistent.ranking;
+
+    if !ranking.initialized {
+        return Err("Ranking not initialized".to_string());
+    }
+
+    let photos = folder_photos(&state, config.ranking_folder(&ranking.target), &config.supported_extensions());
+    let triple = select_triple(
+        ranking,
+        config.pair_strategy,
+        &config.cluster_convergence(),
+        config.uncompared_pair_probability,
+    );
+
+    let photos = triple.map(|ids| ids.into_iter()
+        .map(|id| {
+            let rating = ranking.ratings.get(&id).cloned().unwrap_or_default();
+            PhotoInfo {
+                mu: (rating.mu * 10.0).round() / 10.0,
+                sigma: (rating.sigma * 10.0).round() / 10.0,
+                matches: rating.matches_played,
+                file_path: photos.get(&id).map(|p| p.to_string_lossy().to_string()).unwrap_or_default(),
+                id,
+            }
+        })
+        .collect::<Vec<_>>());
+
+    Ok(TripleInfo {
+        done: photos.is_none(),
+        photos: photos.unwrap_or_default(),
+        stats: get_ranking_stats_internal(ranking),
+    })
+}
+
+/// Record a best-of-three pick: the winner beats each of the other two photos in
+/// turn (two Glicko-2 updates, the second from the winner's updated rating). Both
+/// comparisons are logged and undone together.
+#[tauri::command]
+pub fn compare_triple(ids: Vec<String>, winner_id: String, state: State<AppState>) -> Result<(), String> {
+    let mut unique = ids.clone();
+    unique.sort();
+    unique.dedup();
+    if ids.len() != 3 || unique.len() != 3 {
+        return Err("A triple needs three different photos".to_string());
+    }
+    if !ids.contains(&winner_id) {
+        return Err("The winner must be one of the three photos".to_string());
+    }
+
+    let (tau, rating_config) = {
+        let config = state.config.lock().unwrap();
+        (config.glicko_tau, config.rating.clone())
+    };
+    let mut persistent = state.persistent.lock().unwrap();
+    apply_triple(&mut persistent.ranking, &ids, &winner_id, tau, &rating_config)?;
+
+    // No ranking-complete here: triples are never tournament matches and don't
+    // change how many photos are rated, so a pair is left whenever one was before
+    state.save_later();
+    Ok(())
+}
+
+/// The ratings side of `compare_triple`
+fn apply_triple(
+    ranking: &mut RankingState,
+    ids: &[String],
+    winner_id: &str,
+    tau: f64,
+    rating_config: &RatingConfig,
+) -> Result<(), String> {
+    if !ranking.initialized {
+        return Err("Ranking not initialized".to_string());
+    }
+    if ranking.phase == "tournament" {
+        return Err("Tournament matches are decided one pair at a time".to_string());
+    }
+    for id in ids {
+        if !ranking.ratings.contains_key(id) {
+            return Err(format!("Photo not ranked: {}", id));
+        }
+    }
+    for (i, loser_id) in ids.iter().filter(|id| *id != winner_id).enumerate() {
+        let winner = ranking.ratings[winner_id].clone();
+        let loser = ranking.ratings[loser_id].clone();
+        let record = ComparisonRecord {
+            left_id: winner_id.to_string(),
+            right_id: loser_id.clone(),
+            result: "left".to_string(),
+            left_mu_before: winner.mu,
+            left_sigma_before: winner.sigma,
+            right_mu_before: loser.mu,
+            right_sigma_before: loser.sigma,
+            left_volatility_before: winner.volatility,
+            right_volatility_before: loser.volatility,
+            bracket_before: None,
+            batch_with_previous: i > 0,
+            timestamp: now_secs(),
+        };
+
+        let (mut new_winner, mut new_loser) = glicko_update(&winner, &loser, 1.0, tau, rating_config);
+        new_winner.matches_played += 1;
+        new_loser.matches_played += 1;
+        ranking.ratings.insert(winner_id.to_string(), new_winner);
+        ranking.ratings.insert(loser_id.clone(), new_loser);
+
+        log_comparison(ranking, record);
+    }
+
+    if ranking.current_pair.as_ref().is_some_and(|(a, b)| ids.contains(a) || ids.contains(b)) {
+        ranking.current_pair = None;
+    }
+    finish_intra_cluster_if_complete(ranking);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn undo_ranking(state: State<AppState>) -> Result<UndoResult, String> {
+    let mut persistent = state.persistent.lock().unwrap();
+
+    if persistent.ranking.comparison_history.is_empty() {
+        return Ok(UndoResult {
+            success: false,
+            message: "Nothing to undo".to_string(),
+            image_id: None,
+        });
+    }
+
+    let undone = undo_last_comparison(&mut persistent.ranking);
+    state.save_later();
+
+    Ok(UndoResult {
+        success: true,
+        message: match undone.as_slice() {
+            [result] => format!("Undone comparison: {}", result),
+            batch => format!("Undone {} comparisons", batch.len()),
+        },
+        image_id: None,
+    })
+}
+
+/// Reverse the newest comparison, or the whole batch (a best-of-three pick) it
+/// ends, newest record first. Returns the undone results.
+fn undo_last_comparison(ranking: &mut RankingState) -> Vec<String> {
+    let mut undone = Vec::new();
+    while let Some(record) = ranking.comparison_history.pop() {
+        let ratings = &mut ranking.ratings;
+
+        // Restore ratings
+        if let Some(left) = ratings.get_mut(&record.left_id) {
+            left.mu = record.left_mu_before;
+            left.sigma = record.left_sigma_before;
+            left.volatility = record.left_volatility_before;
+            if record.result != "skip" {
+                left.matches_played = left.matches_played.saturating_sub(1);
+            }
+        }
+
+        if let Some(right) = ratings.get_mut(&record.right_id) {
+            right.mu = record.right_mu_before;
+            right.sigma = record.right_sigma_before;
+            right.volatility = record.right_volatility_before;
+            if record.result != "skip" {
+                right.matches_played = right.matches_played.saturating_sub(1);
+            }
+        }
+
+        if let Some(bracket) = record.bracket_before {
+            ranking.bracket = bracket;
+            ranking.winner_id = None;
+        }
+
+        invalidate_queued_pairs(&mut ranking.pair_queue, &record.left_id, &record.right_id);
+        ranking.total_comparisons = ranking.total_comparisons.saturating_sub(1);
+        let batched = record.batch_with_previous;
+        undone.push(record.result);
+        if !batched {
+            break;
+        }
+    }
+    undone
+}
+
+#[tauri::command]
+pub fn get_leaderboard(limit: usize, state: State<AppState>) -> Vec<LeaderboardPhoto> {
+    let config = state.config.lock().unwrap();
+    let persistent = state.persistent.lock().unwrap();
+
+    if !persistent.ranking.initialized {
+        return Vec::new();
+    }
+
+    let folder = config.ranking_folder(&persistent.ranking.target);
+    let photos = folder_photos(&state, folder, &config.supported_extensions());
+
+    ranked_photos(&persistent.ranking, &photos)
+        .into_iter()
+        .take(limit)
+        .map(|row| leaderboard_photo(&row))
+        .collect()
+}
+
+/// Ranked photos that have never been compared, by file path
+#[tauri::command]
+pub fn get_uncompared_photos(state: State<AppState>) -> Vec<UncomparedPhoto> {
+    let config = state.config.lock().unwrap();
+    let persistent = state.persistent.lock().unwrap();
+    let ranking = &persistent.ranking;
+
+    let photos = folder_photos(&state, config.ranking_folder(&ranking.target), &config.supported_extensions());
+    let mut uncompared: Vec<UncomparedPhoto> = ranking.ratings.iter()
+        .filter(|(_, rating)| rating.matches_played == 0)
+        .map(|(id, _)| UncomparedPhoto {
+            id: id.clone(),
+            file_path: photos.get(id).map(|p| p.to_string_lossy().to_string()).unwrap_or_default(),
+        })
+        .collect();
+    uncompared.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    uncompared
+}
+
+/// Everything known about one ranked photo: rating, cluster and recent comparisons
+#[tauri::command]
+pub fn get_photo_detail(photo_id: String, state: State<AppState>) -> Result<PhotoDetail, String> {
+    let config = state.config.lock().unwrap();
+    let persistent = state.persistent.lock().unwrap();
+    let ranking = &persistent.ranking;
+
+    let rating = ranking.ratings.get(&photo_id)
+        .ok_or_else(|| format!("Photo not ranked: {}", photo_id))?;
+    let photos = folder_photos(&state, config.ranking_folder(&ranking.target), &config.supported_extensions());
+    let file_path = |id: &str| photos.get(id).map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+
+    let cluster_id = ranking.photo_to_cluster.get(&photo_id).cloned();
+    let mut cluster_mates: Vec<ClusterMember> = cluster_id.as_ref()
+        .and_then(|id| ranking.clusters.get(id))
+        .map(|cluster| cluster.photo_ids.iter()
+            .filter(|id| **id != photo_id)
+            .map(|id| ClusterMember {
+                id: id.clone(),
+                file_path: file_path(id),
+                score: ranking.ratings.get(id)
+                    .map(|r| (get_conservative_score(r.mu, r.sigma) * 10.0).round() / 10.0)
+                    .unwrap_or(0.0),
+            })
+            .collect())
+        .unwrap_or_default();
+    cluster_mates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+
+    let (mut wins, mut losses, mut ties) = (rating.wins, rating.losses, rating.ties);
+    let mut recent_comparisons = Vec::new();
+    for record in ranking.comparison_history.iter().rev() {
+        let (opponent_id, mu_before, sigma_before, won) = if record.left_id == photo_id {
+            (&record.right_id, record.left_mu_before, record.left_sigma_before, "left")
+        } else if record.right_id == photo_id {
+            (&record.left_id, record.right_mu_before, record.right_sigma_before, "right")
+        } else {
+            continue;
+        };
+
+        let outcome = match record.result.as_str() {
+            "tie" | "skip" | "both_good" | "both_bad" => record.result.as_str(),
+            result if result == won => "win",
+            _ => "loss",
+        };
+        match outcome {
+            "win" => wins += 1,
+            "loss" => losses += 1,
+            "tie" => ties += 1,
+            _ => {}
+        }
+
+        recent_comparisons.push(PhotoComparison {
+            opponent_id: opponent_id.clone(),
+            outcome: outcome.to_string(),
+            mu_before,
+            sigma_before,
+            timestamp: record.timestamp,
+        });
+    }
+
+    Ok(PhotoDetail {
+        file_path: file_path(&photo_id),
+        mu: rating.mu,
+        sigma: rating.sigma,
+        volatility: rating.volatility,
+        score: get_conservative_score(rating.mu, rating.sigma),
+        matches: rating.matches_played,
+        cluster_id,
+        cluster_mates,
+        wins,
+        losses,
+        ties,
+        recent_comparisons,
+        id: photo_id,
+    })
+}
+
+/// Two photos' record against each other over all their comparisons
+#[tauri::command]
+pub fn get_head_to_head(id_a: String, id_b: String, state: State<AppState>) -> Result<HeadToHead, String> {
+    let persistent = state.persistent.lock().unwrap();
+    let ranking = &persistent.ranking;
+
+    for id in [&id_a, &id_b] {
+        if !ranking.ratings.contains_key(id) {
+            return Err(format!("Photo not ranked: {}", id));
+        }
+    }
+    Ok(head_to_head(ranking, &id_a, &id_b))
+}
+
+/// `get_head_to_head` for two ranked photos
+fn head_to_head(ranking: &RankingState, id_a: &str, id_b: &str) -> HeadToHead {
+    // Comparisons already trimmed from the history
+    let (key, a_first) = pair_key(id_a, id_b);
+    let archived = ranking.head_to_head.get(&key).cloned().unwrap_or_default();
+    let mut record = if a_first {
+        HeadToHead { a_wins: archived.first_wins, b_wins: archived.second_wins, ties: archived.ties }
+    } else {
+        HeadToHead { a_wins: archived.second_wins, b_wins: archived.first_wins, ties: archived.ties }
+    };
+
+    for comparison in &ranking.comparison_history {
+        let a_is_left = if comparison.left_id == id_a && comparison.right_id == id_b {
+            true
+        } else if comparison.left_id == id_b && comparison.right_id == id_a {
+            false
+        } else {
+            continue;
+        };
+        match comparison.result.as_str() {
+            "tie" => record.ties += 1,
+            "left" if a_is_left => record.a_wins += 1,
+            "right" if !a_is_left => record.a_wins += 1,
+            "left" | "right" => record.b_wins += 1,
+            _ => {}
+        }
+    }
+
+    record
+}
+
+/// Write the leaderboard (best first) to a CSV file
 #[tauri::command]
-pub fn compare(left_id: String, right_id: String, result: String, state: State<AppState>) -> Result<(), String> {
-    let mut persistent = state.persistent.lock().unwrap();
+pub fn export_leaderboard(path: String, limit: usize, state: State<AppState>) -> Result<(), String> {
+    let config = state.config.lock().unwrap();
+    let persistent = state.persistent.lock().unwrap();
 
     if !persistent.ranking.initialized {
         return Err("Ranking not initialized".to_string());
     }
 
-    let ratings = &mut persistent.ranking.ratings;
+    let folder = config.ranking_folder(&persistent.ranking.target);
+    let photos = folder_photos(&state, folder, &config.supported_extensions());
+    let mut rows = ranked_photos(&persistent.ranking, &photos);
+    rows.truncate(limit);
 
-    let left = ratings.get(&left_id).ok_or("Left photo not found")?.clone();
-    let right = ratings.get(&right_id).ok_or("Right photo not found")?.clone();
+    write_leaderboard_csv(&path, &rows)
+}
 
-    // Store for undo
-    let record = ComparisonRecord {
-        left_id: left_id.clone(),
-        right_id: right_id.clone(),
-        result: result.clone(),
-        left_mu_before: left.mu,
-        left_sigma_before: left.sigma,
-        right_mu_before: right.mu,
-        right_sigma_before: right.sigma,
-        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64(),
-    };
+#[tauri::command]
+pub fn export_leaderboard_html(
+    path: String,
+    top_n: usize,
+    embed_thumbnails: bool,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let config = state.config.lock().unwrap();
+    let persistent = state.persistent.lock().unwrap();
 
-    if result != "skip" {
-        let is_tie = result == "tie";
+    if !persistent.ranking.initialized {
+        return Err("Ranking not initialized".to_string());
+    }
 
-        let (winner_mu, winner_sigma, loser_mu, loser_sigma) = if result == "left" || is_tie {
-            (left.mu, left.sigma, right.mu, right.sigma)
-        } else {
-            (right.mu, right.sigma, left.mu, left.sigma)
-        };
+    let folder = config.ranking_folder(&persistent.ranking.target);
+    let photos = folder_photos(&state, folder, &config.supported_extensions());
+    let mut rows = ranked_photos(&persistent.ranking, &photos);
+    rows.truncate(top_n);
+
+    write_leaderboard_html(&path, &rows, embed_thumbnails)
+}
 
-        let ((new_winner_mu, new_winner_sigma), (new_loser_mu, new_loser_sigma)) =
-            glicko_update(winner_mu, winner_sigma, loser_mu, loser_sigma, is_tie);
+/// Write each ranked photo's star rating (percentile of its conservative score)
+/// into its EXIF data. Only JPEGs are rewritten; RAW, HEIC and other formats are
+/// skipped to avoid corrupting them. Emits "exif-rating-progress" as files finish.
+/// Cached hashes of rewritten photos are carried over to their new mtime/size.
+#[tauri::command]
+pub fn apply_ratings_to_exif(app: AppHandle, state: State<AppState>) -> Result<ExifRatingResult, String> {
+    let (photos, stars, algorithm) = {
+        let config = state.config.lock().unwrap();
+        let persistent = state.persistent.lock().unwrap();
 
-        // Apply updates
-        if result == "left" || is_tie {
-            ratings.get_mut(&left_id).unwrap().mu = new_winner_mu;
-            ratings.get_mut(&left_id).unwrap().sigma = new_winner_sigma;
-            ratings.get_mut(&right_id).unwrap().mu = new_loser_mu;
-            ratings.get_mut(&right_id).unwrap().sigma = new_loser_sigma;
-        } else {
-            ratings.get_mut(&right_id).unwrap().mu = new_winner_mu;
-            ratings.get_mut(&right_id).unwrap().sigma = new_winner_sigma;
-            ratings.get_mut(&left_id).unwrap().mu = new_loser_mu;
-            ratings.get_mut(&left_id).unwrap().sigma = new_loser_sigma;
+        if !persistent.ranking.initialized {
+            return Err("Ranking not initialized".to_string());
         }
 
-        // Increment match counts
-        ratings.get_mut(&left_id).unwrap().matches_played += 1;
-        ratings.get_mut(&right_id).unwrap().matches_played += 1;
-    }
+        let folder = config.ranking_folder(&persistent.ranking.target);
+        let photos = folder_photos(&state, folder, &config.supported_extensions());
+        (photos, star_ratings(&persistent.ranking.ratings), config.hash_algorithm)
+    };
+
+    let is_jpeg = |path: &std::path::Path| {
+        has_supported_extension(path, &["jpg".to_string(), "jpeg".to_string()])
+    };
+    let targets: Vec<(&String, &std::path::PathBuf, u8)> = stars.iter()
+        .filter_map(|(id, &stars)| photos.get(id).map(|path| (id, path, stars)))
+        .filter(|(_, path, _)| is_jpeg(path))
+        .collect();
+    let skipped = photos.len() - targets.len();
+
+    // Only the EXIF block changes, so hashes that match the files now still will after
+    let current_hashes: HashSet<&String> = {
+        let photo_hashes = state.photo_hashes.lock().unwrap();
+        targets.iter()
+            .filter(|(id, path, _)| photo_hashes.get(*id).is_some_and(|entry| entry.is_current(path, algorithm)))
+            .map(|(id, _, _)| *id)
+            .collect()
+    };
 
-    // Record comparison
-    persistent.ranking.comparison_history.push(record);
-    persistent.ranking.total_comparisons += 1;
+    let total = targets.len();
+    let done = AtomicUsize::new(0);
+
+    let written: Vec<(&String, &std::path::PathBuf)> = targets.par_iter()
+        .filter_map(|(id, path, stars)| {
+            let result = write_exif_rating(path, *stars);
+            let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+            let _ = app.emit("exif-rating-progress", ProgressEvent { done, total });
+            match result {
+                Ok(()) => Some((*id, *path)),
+                Err(e) => {
+                    eprintln!("Warning: {}", e);
+                    None
+                }
+            }
+        })
+        .collect();
 
-    // Trim history
-    if persistent.ranking.comparison_history.len() > 100 {
-        let keep = persistent.ranking.comparison_history.len() - 100;
-        persistent.ranking.comparison_history = persistent.ranking.comparison_history.split_off(keep);
+    let mut photo_hashes = state.photo_hashes.lock().unwrap();
+    let mut refreshed = false;
+    for (id, path) in &written {
+        if let Some(entry) = photo_hashes.get_mut(*id).filter(|_| current_hashes.contains(id)) {
+            entry.refresh_fingerprint(path);
+            refreshed = true;
+        }
     }
-
-    // Check if we should switch from intra_cluster to global
-    if persistent.ranking.phase == "intra_cluster" {
-        let all_complete = persistent.ranking.clusters.values().all(|c| c.internal_ranking_complete);
-        if all_complete {
-            persistent.ranking.phase = "global".to_string();
+    if refreshed {
+        if let Err(e) = save_photo_hashes(&photo_hashes) {
+            eprintln!("Warning: Could not save photo hashes: {}", e);
         }
     }
 
-    persistent.save()
+    let failed = total - written.len();
+    Ok(ExifRatingResult {
+        updated: total - failed,
+        skipped,
+        failed,
+    })
 }
 
+/// Back up ratings and comparison history to a JSON file
 #[tauri::command]
-pub fn undo_ranking(state: State<AppState>) -> Result<UndoResult, String> {
-    let mut persistent = state.persistent.lock().unwrap();
+pub fn export_ratings(path: String, state: State<AppState>) -> Result<(), String> {
+    let persistent = state.persistent.lock().unwrap();
 
-    if persistent.ranking.comparison_history.is_empty() {
-        return Ok(UndoResult {
-            success: false,
-            message: "Nothing to undo".to_string(),
-            image_id: None,
-        });
+    if !persistent.ranking.initialized {
+        return Err("Ranking not initialized".to_string());
     }
 
-    let record = persistent.ranking.comparison_history.pop().unwrap();
-    let ratings = &mut persistent.ranking.ratings;
+    let backup = RatingsBackup {
+        ratings: persistent.ranking.ratings.clone(),
+        comparison_history: persistent.ranking.comparison_history.clone(),
+    };
+    write_ratings_json(&path, &backup)
+}
 
-    // Restore ratings
-    if let Some(left) = ratings.get_mut(&record.left_id) {
-        left.mu = record.left_mu_before;
-        left.sigma = record.left_sigma_before;
-        if record.result != "skip" {
-            left.matches_played = left.matches_played.saturating_sub(1);
+/// Merge ratings from a file written by `export_ratings`: ids in the file overwrite
+/// current ratings, all others are kept. Ids with no photo in the ranked folder are
+/// dropped, and history records already present are not added again.
+#[tauri::command]
+pub fn import_ratings(path: String, state: State<AppState>) -> Result<RatingsImport, String> {
+    let backup = read_ratings_json(&path)?;
+
+    // Scan and initialize first so the imported ratings land in a usable ranking
+    let initialized = state.persistent.lock().unwrap().ranking.initialized;
+    if !initialized {
+        if state.ranking_init_running.swap(true, Ordering::SeqCst) {
+            return Err("Ranking initialization is already running".to_string());
+        }
+        let _running = InitGuard(&state.ranking_init_running);
+        if let RankingInit::Scan(target, photos) = prepare_ranking_init(None, &state)? {
+            run_ranking_init(&state, &target, &photos, |_, _| {})?;
         }
     }
 
-    if let Some(right) = ratings.get_mut(&record.right_id) {
-        right.mu = record.right_mu_before;
-        right.sigma = record.right_sigma_before;
-        if record.result != "skip" {
-            right.matches_played = right.matches_played.saturating_sub(1);
+    let config = state.config.lock().unwrap();
+    let mut persistent = state.persistent.lock().unwrap();
+    let folder = config.ranking_folder(&persistent.ranking.target);
+    let photos = folder_photos(&state, folder, &config.supported_extensions());
+    let ranking = &mut persistent.ranking;
+
+    // Ratings without a photo would be served as pairs with no file
+    let total = backup.ratings.len();
+    let ratings: HashMap<String, PhotoRating> = backup.ratings.into_iter()
+        .filter(|(id, _)| photos.contains_key(id))
+        .collect();
+    let imported = ratings.len();
+    ranking.ratings.extend(ratings);
+
+    // Records of unrated photos couldn't be undone, and a re-import of the same
+    // file brings records that are already here
+    let mut comparisons_added = 0;
+    for record in backup.comparison_history {
+        let rated = ranking.ratings.contains_key(&record.left_id) && ranking.ratings.contains_key(&record.right_id);
+        let known = ranking.comparison_history.iter().any(|r| {
+            r.left_id == record.left_id
+                && r.right_id == record.right_id
+                && r.result == record.result
+                && r.timestamp == record.timestamp
+        });
+        if rated && !known {
+            ranking.comparison_history.push(record);
+            comparisons_added += 1;
         }
     }
+    ranking.total_comparisons += comparisons_added;
 
-    persistent.ranking.total_comparisons = persistent.ranking.total_comparisons.saturating_sub(1);
-    persistent.save()?;
+    // Keep undo history in chronological order, trimmed like compare does
+    ranking.comparison_history.sort_by(|a, b| {
+        a.timestamp.partial_cmp(&b.timestamp).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    trim_comparison_history(ranking);
 
-    Ok(UndoResult {
-        success: true,
-        message: format!("Undone comparison: {}", record.result),
-        image_id: None,
+    // Pairs were chosen from the old ratings
+    ranking.pair_queue.clear();
+    ranking.current_pair = None;
+
+    persistent.save()?;
+    Ok(RatingsImport {
+        imported,
+        dropped: total - imported,
+        comparisons_added,
     })
 }
 
+/// Confidence needed before a top-K cutoff is considered settled
+const TOP_K_SETTLED_CONFIDENCE: f64 = 0.95;
+
 #[tauri::command]
-pub fn get_leaderboard(limit: usize, state: State<AppState>) -> Vec<LeaderboardPhoto> {
+pub fn top_k_confidence(k: usize, state: State<AppState>) -> Result<TopKConfidence, String> {
     let config = state.config.lock().unwrap();
     let persistent = state.persistent.lock().unwrap();
 
     if !persistent.ranking.initialized {
-        return Vec::new();
+        return Err("Ranking not initialized".to_string());
+    }
+
+    let folder = config.ranking_folder(&persistent.ranking.target);
+    let photos = folder_photos(&state, folder, &config.supported_extensions());
+    let rows = ranked_photos(&persistent.ranking, &photos);
+
+    if k == 0 || k >= rows.len() {
+        return Err(format!("k must be between 1 and {}", rows.len().saturating_sub(1)));
     }
 
-    let ratings = &persistent.ranking.ratings;
-    let photos = scan_accepted_photos(&config.accepted_folder);
+    let above = &rows[k - 1];
+    let below = &rows[k];
+    let confidence = win_probability(above.mu, above.sigma, below.mu, below.sigma);
+
+    Ok(TopKConfidence {
+        k,
+        above: leaderboard_photo(above),
+        below: leaderboard_photo(below),
+        confidence: (confidence * 1000.0).round() / 1000.0,
+        settled: confidence >= TOP_K_SETTLED_CONFIDENCE,
+    })
+}
+
+/// Leaderboard entry with values rounded for display
+fn leaderboard_photo(row: &ExportRow) -> LeaderboardPhoto {
+    LeaderboardPhoto {
+        id: row.id.clone(),
+        mu: (row.mu * 10.0).round() / 10.0,
+        sigma: (row.sigma * 10.0).round() / 10.0,
+        matches: row.matches,
+        score: (row.score * 10.0).round() / 10.0,
+        file_path: row.file_path.clone(),
+    }
+}
 
-    let mut scored: Vec<_> = ratings.iter()
+/// All rated photos with unrounded values, sorted best-first by conservative score
+fn ranked_photos(
+    ranking: &RankingState,
+    photos: &HashMap<String, std::path::PathBuf>,
+) -> Vec<ExportRow> {
+    let mut rows: Vec<ExportRow> = ranking.ratings.iter()
         .map(|(id, rating)| {
-            let score = get_conservative_score(rating.mu, rating.sigma);
-            let file_path = photos.get(id).map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
-            LeaderboardPhoto {
+            let path = photos.get(id);
+            ExportRow {
+                rank: 0,
                 id: id.clone(),
-                mu: (rating.mu * 10.0).round() / 10.0,
-                sigma: (rating.sigma * 10.0).round() / 10.0,
+                filename: path
+                    .and_then(|p| p.file_name())
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                file_path: path.map(|p| p.to_string_lossy().to_string()).unwrap_or_default(),
+                mu: rating.mu,
+                sigma: rating.sigma,
+                score: get_conservative_score(rating.mu, rating.sigma),
                 matches: rating.matches_played,
-                score: (score * 10.0).round() / 10.0,
-                file_path,
             }
         })
         .collect();
 
-    scored.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
-    scored.truncate(limit);
+    rows.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    for (i, row) in rows.iter_mut().enumerate() {
+        row.rank = i + 1;
+    }
 
-    scored
+    rows
 }
 
 // ============================================================================
@@ -743,19 +3474,51 @@ pub fn get_folders(state: State<AppState>) -> FoldersResponse {
     let persistent = state.persistent.lock().unwrap();
     let image_records = state.image_records.lock().unwrap();
 
+    // Decisions are counted from state like get_stats_data's: photos moved out of a
+    // folder are no longer in the scan, so they're placed by their original path
+    let record_folders: HashMap<&String, &str> = image_records.iter()
+        .map(|r| (&r.id, r.source_folder.as_str()))
+        .collect();
+    let mut decided: HashMap<&str, [usize; 3]> = HashMap::new();
+    for (id, decision) in &persistent.decisions {
+        let slot = match decision.as_str() {
+            "accepted" | "favorite" => 0,
+            "rejected" => 1,
+            "skipped" | "deferred" => 2,
+            _ => continue,
+        };
+        let folder = record_folders.get(id).copied().or_else(|| {
+            let original = std::path::Path::new(persistent.original_paths.get(id)?);
+            config.source_folders.iter()
+                .find(|folder| original.starts_with(folder.as_str()))
+                .map(String::as_str)
+        });
+        if let Some(folder) = folder {
+            decided.entry(folder).or_default()[slot] += 1;
+        }
+    }
+
     let folders: Vec<FolderInfo> = config.source_folders.iter()
         .map(|folder_path| {
-            let exists = std::path::Path::new(folder_path).exists();
+            let status = folder_status(std::path::Path::new(folder_path));
             let photo_count = image_records.iter().filter(|r| r.source_folder == *folder_path).count();
-            let decided_count = image_records.iter()
-                .filter(|r| r.source_folder == *folder_path && persistent.decisions.contains_key(&r.id))
-                .count();
+            let [accepted_count, rejected_count, skipped_count] =
+                decided.get(folder_path.as_str()).copied().unwrap_or_default();
 
             FolderInfo {
                 path: folder_path.clone(),
-                exists,
+                enabled: !config.disabled_folders.contains(folder_path),
+                exists: status == FolderStatus::Present,
+                status,
                 photo_count,
-                decided_count,
+                decided_count: accepted_count + rejected_count + skipped_count,
+                accepted_count,
+                rejected_count,
+                skipped_count,
+                accepted_destination: config.destination_for_source("accepted", folder_path)
+                    .unwrap_or_default().to_string(),
+                rejected_destination: config.destination_for_source("rejected", folder_path)
+                    .unwrap_or_default().to_string(),
             }
         })
         .collect();
@@ -764,6 +3527,8 @@ pub fn get_folders(state: State<AppState>) -> FoldersResponse {
         folders,
         accepted_folder: config.accepted_folder.clone(),
         rejected_folder: config.rejected_folder.clone(),
+        favorites_folder: config.favorites_folder.clone(),
+        last_removed_folder: persistent.last_removed_folder.as_ref().map(|removed| removed.path.clone()),
     }
 }
 
@@ -779,18 +3544,15 @@ pub fn add_source_folder(path: String, state: State<AppState>) -> Result<(), Str
         return Err("Folder already added".to_string());
     }
 
-    config.source_folders.push(path);
+    let mut updated = config.clone();
+    updated.source_folders.push(path);
+    updated.check_folder_overlap()?;
+    *config = updated;
     config.save()?;
 
     // Rescan
-    let records = scan_source_folders(&config.source_folders);
-    let mut image_records = state.image_records.lock().unwrap();
-    *image_records = records;
-
-    let persistent = state.persistent.lock().unwrap();
-    let pending = build_pending_indices(&image_records, &persistent.decisions);
-    let mut pending_indices = state.pending_indices.lock().unwrap();
-    *pending_indices = pending;
+    let mut persistent = state.persistent.lock().unwrap();
+    rescan_sources(&config, &mut persistent, &state);
 
     Ok(())
 }
@@ -805,6 +3567,14 @@ pub fn remove_source_folder(path: String, clear_decisions: bool, state: State<Ap
         return Err("Folder not found".to_string());
     }
 
+    // Whatever is removed goes into the undo buffer, replacing the previous removal
+    let mut removed = RemovedFolder {
+        path: path.clone(),
+        enabled: !config.disabled_folders.contains(&path),
+        destination_override: config.destination_overrides.get(&path).cloned(),
+        ..RemovedFolder::default()
+    };
+
     // Optionally clear decisions
     if clear_decisions {
         let to_remove: Vec<_> = image_records.iter()
@@ -813,25 +3583,104 @@ pub fn remove_source_folder(path: String, clear_decisions: bool, state: State<Ap
             .collect();
 
         for img_id in to_remove {
-            persistent.decisions.remove(&img_id);
-            persistent.moved_files.remove(&img_id);
-            persistent.original_paths.remove(&img_id);
+            if let Some(decision) = persistent.decisions.remove(&img_id) {
+                removed.decisions.insert(img_id.clone(), decision);
+            }
+            if let Some(at) = persistent.decided_at.remove(&img_id) {
+                removed.decided_at.insert(img_id.clone(), at);
+            }
+            if let Some(dest) = persistent.moved_files.remove(&img_id) {
+                removed.moved_files.insert(img_id.clone(), dest);
+            }
+            if let Some(original) = persistent.original_paths.remove(&img_id) {
+                removed.original_paths.insert(img_id.clone(), original);
+            }
+            if persistent.copied_files.remove(&img_id) {
+                removed.copied_files.insert(img_id.clone());
+            }
+            if let Some(size) = persistent.moved_sizes.remove(&img_id) {
+                removed.moved_sizes.insert(img_id.clone(), size);
+            }
+            if let Some(copies) = persistent.extra_copies.remove(&img_id) {
+                removed.extra_copies.insert(img_id, copies);
+            }
         }
     }
+    persistent.last_removed_folder = Some(removed);
 
     config.source_folders.retain(|f| f != &path);
+    config.disabled_folders.retain(|f| f != &path);
+    config.destination_overrides.remove(&path);
     config.save()?;
     persistent.save()?;
 
     // Rescan
     drop(image_records);
-    let records = scan_source_folders(&config.source_folders);
-    let mut image_records = state.image_records.lock().unwrap();
-    *image_records = records;
+    rescan_sources(&config, &mut persistent, &state);
 
-    let pending = build_pending_indices(&image_records, &persistent.decisions);
-    let mut pending_indices = state.pending_indices.lock().unwrap();
-    *pending_indices = pending;
+    Ok(())
+}
+
+/// Undo the most recent `remove_source_folder`: the folder is added back (with its
+/// destination override, and disabled if it was) and any decisions cleared with it
+/// are restored
+#[tauri::command]
+pub fn restore_last_removed_folder(state: State<AppState>) -> Result<String, String> {
+    let mut config = state.config.lock().unwrap();
+    let mut persistent = state.persistent.lock().unwrap();
+
+    let removed = persistent.last_removed_folder.clone().ok_or("No removed folder to restore")?;
+    if config.source_folders.contains(&removed.path) {
+        return Err(format!("Already a source folder: {}", removed.path));
+    }
+
+    let mut restored = config.clone();
+    restored.source_folders.push(removed.path.clone());
+    if !removed.enabled {
+        restored.disabled_folders.push(removed.path.clone());
+    }
+    if let Some(dest_override) = removed.destination_override {
+        restored.destination_overrides.insert(removed.path.clone(), dest_override);
+    }
+    restored.check_folder_overlap()?;
+    restored.save()?;
+    *config = restored;
+
+    persistent.decisions.extend(removed.decisions);
+    persistent.decided_at.extend(removed.decided_at);
+    persistent.moved_files.extend(removed.moved_files);
+    persistent.original_paths.extend(removed.original_paths);
+    persistent.copied_files.extend(removed.copied_files);
+    persistent.extra_copies.extend(removed.extra_copies);
+    persistent.moved_sizes.extend(removed.moved_sizes);
+    persistent.last_removed_folder = None;
+    persistent.save()?;
+
+    rescan_sources(&config, &mut persistent, &state);
+
+    Ok(removed.path)
+}
+
+/// Include or leave out a source folder without removing it. Decisions are kept
+/// either way, so a re-enabled folder picks up where it left off.
+#[tauri::command]
+pub fn set_folder_enabled(path: String, enabled: bool, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().unwrap();
+
+    if !config.source_folders.contains(&path) {
+        return Err("Folder not found".to_string());
+    }
+
+    let mut updated = config.clone();
+    updated.disabled_folders.retain(|f| f != &path);
+    if !enabled {
+        updated.disabled_folders.push(path);
+    }
+    updated.save()?;
+    *config = updated;
+
+    let mut persistent = state.persistent.lock().unwrap();
+    rescan_sources(&config, &mut persistent, &state);
 
     Ok(())
 }
@@ -840,28 +3689,95 @@ pub fn remove_source_folder(path: String, clear_decisions: bool, state: State<Ap
 pub fn set_destination_folder(folder_type: String, path: String, state: State<AppState>) -> Result<(), String> {
     let mut config = state.config.lock().unwrap();
 
+    let mut updated = config.clone();
+    match folder_type.as_str() {
+        "accepted" => updated.accepted_folder = path.clone(),
+        "rejected" => updated.rejected_folder = path.clone(),
+        "favorites" => updated.favorites_folder = path.clone(),
+        _ => return Err("Invalid folder type".to_string()),
+    }
+    updated.check_folder_overlap()?;
+
     // Create folder if it doesn't exist
     std::fs::create_dir_all(&path).map_err(|e| e.to_string())?;
 
-    match folder_type.as_str() {
-        "accepted" => config.accepted_folder = path,
-        "rejected" => config.rejected_folder = path,
-        _ => return Err("Invalid folder type".to_string()),
+    *config = updated;
+    config.save()
+}
+
+/// Set or clear (None) the Accepted/Rejected destinations used for one source folder
+#[tauri::command]
+pub fn set_destination_override(
+    source: String,
+    accepted: Option<String>,
+    rejected: Option<String>,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let mut config = state.config.lock().unwrap();
+
+    if !config.source_folders.contains(&source) {
+        return Err("Folder not found".to_string());
+    }
+
+    let mut updated = config.clone();
+    if accepted.is_none() && rejected.is_none() {
+        updated.destination_overrides.remove(&source);
+    } else {
+        updated.destination_overrides.insert(source, DestinationOverride {
+            accepted_override: accepted.clone(),
+            rejected_override: rejected.clone(),
+        });
+    }
+    updated.check_folder_overlap()?;
+
+    for path in accepted.iter().chain(rejected.iter()) {
+        std::fs::create_dir_all(path).map_err(|e| e.to_string())?;
+    }
+
+    *config = updated;
+    config.save()
+}
+
+#[tauri::command]
+pub fn add_quick_access(name: String, path: String, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().unwrap();
+
+    if !std::path::Path::new(&path).is_dir() {
+        return Err(format!("Folder does not exist: {}", path));
+    }
+
+    if config.quick_access.iter().any(|location| location.path == path) {
+        return Err("Location already added".to_string());
+    }
+
+    config.quick_access.push(QuickAccessLocation { name, path });
+    config.save()
+}
+
+#[tauri::command]
+pub fn remove_quick_access(path: String, state: State<AppState>) -> Result<(), String> {
+    let mut config = state.config.lock().unwrap();
+
+    if !config.quick_access.iter().any(|location| location.path == path) {
+        return Err("Location not found".to_string());
     }
 
+    config.quick_access.retain(|location| location.path != path);
     config.save()
 }
 
 #[tauri::command]
-pub fn browse(path: String) -> BrowseResponse {
-    match browse_directory(&path) {
+pub fn browse(path: String, state: State<AppState>) -> BrowseResponse {
+    let config = state.config.lock().unwrap();
+    let exclude = ExcludeFilter::new(&config.exclude_patterns);
+    match browse_directory(&path, &exclude) {
         Ok(result) => BrowseResponse {
             error: false,
             message: None,
             current_path: Some(result.current_path),
             parent: result.parent,
             items: result.items,
-            quick_access: QuickAccessLocation::defaults(),
+            quick_access: config.quick_access.clone(),
         },
         Err(e) => BrowseResponse {
             error: true,
@@ -869,7 +3785,7 @@ pub fn browse(path: String) -> BrowseResponse {
             current_path: None,
             parent: None,
             items: Vec::new(),
-            quick_access: QuickAccessLocation::defaults(),
+            quick_access: config.quick_access.clone(),
         },
     }
 }
@@ -881,6 +3797,49 @@ pub fn get_home_dir() -> String {
         .unwrap_or_else(|| "/".to_string())
 }
 
+/// Open the folder holding `path` in the system file manager, with the file
+/// selected on macOS and Windows (Linux file managers just open the folder)
+#[tauri::command]
+pub fn reveal_in_file_manager(path: String) -> Result<(), String> {
+    let file = std::path::Path::new(&path);
+    if !file.exists() {
+        return Err(format!("File not found: {}", path));
+    }
+
+    let mut child = reveal_command(file).spawn()
+        .map_err(|e| format!("Could not open the file manager: {}", e))?;
+    // Reap the launcher so it doesn't linger as a zombie
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn reveal_command(file: &std::path::Path) -> std::process::Command {
+    let mut command = std::process::Command::new("open");
+    command.arg("-R").arg(file);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn reveal_command(file: &std::path::Path) -> std::process::Command {
+    use std::os::windows::process::CommandExt;
+
+    // explorer parses "/select,<path>" itself, so pass it unescaped with the path quoted
+    let mut command = std::process::Command::new("explorer");
+    command.raw_arg(format!("/select,\"{}\"", file.display()));
+    command
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn reveal_command(file: &std::path::Path) -> std::process::Command {
+    let folder = if file.is_dir() { file } else { file.parent().unwrap_or(file) };
+    let mut command = std::process::Command::new("xdg-open");
+    command.arg(folder);
+    command
+}
+
 // ============================================================================
 // Photo browser commands
 // ============================================================================
@@ -903,35 +3862,75 @@ pub struct BrowsePhotoInfo {
     pub sigma: Option<f64>,
     pub score: Option<f64>,
     pub matches: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_path: Option<String>, // cached JPEG preview, only with include_thumbnails
+    pub width: Option<u32>, // as displayed (EXIF orientation applied); None if unreadable
+    pub height: Option<u32>,
 }
 
+/// Set the width and height of a page of photos, reading only files that are new
+/// or changed since they were last looked at
+fn fill_dimensions(photos: &mut [BrowsePhotoInfo], state: &AppState) {
+    photos.par_iter_mut().for_each(|photo| {
+        let path = std::path::Path::new(&photo.file_path);
+        let Ok(mtime) = std::fs::metadata(path).and_then(|m| m.modified()) else {
+            return;
+        };
+
+        let cached = state.dimension_cache.lock().unwrap().get(&photo.file_path)
+            .filter(|entry| entry.mtime == mtime)
+            .map(|entry| entry.dimensions);
+        let dimensions = cached.unwrap_or_else(|| {
+            let dimensions = image_dimensions(path);
+            state.dimension_cache.lock().unwrap()
+                .insert(photo.file_path.clone(), CachedDimensions { mtime, dimensions });
+            dimensions
+        });
+
+        if let Some((width, height)) = dimensions {
+            photo.width = Some(width);
+            photo.height = Some(height);
+        }
+    });
+}
+
+/// Longest edge of the previews returned by get_photos_by_status
+const BROWSE_THUMBNAIL_SIZE: u32 = 400;
+
+/// One page of a destination folder's photos, sorted by `sort`: "ranking" | "ranking_asc" |
+/// "uncertainty" (highest sigma first) | "recent" | "name". `min_matches`/`min_score` keep only
+/// rated photos at or above them. With `include_thumbnails`, each photo on the
+/// page also gets a cached preview path (generated in parallel).
 #[tauri::command]
+#[allow(clippy::too_many_arguments)] // one argument per frontend option
 pub fn get_photos_by_status(
     status: String,
     sort: String,
     page: usize,
     per_page: usize,
+    include_thumbnails: Option<bool>,
+    min_matches: Option<usize>,
+    min_score: Option<f64>,
     state: State<AppState>,
 ) -> BrowsePhotosResponse {
     let config = state.config.lock().unwrap();
     let persistent = state.persistent.lock().unwrap();
 
     // Determine which folder to scan
-    let folder = if status == "accepted" {
-        &config.accepted_folder
-    } else {
-        &config.rejected_folder
+    let folder = match status.as_str() {
+        "accepted" => &config.accepted_folder,
+        "favorite" => config.destination_for("favorite").unwrap_or_default(),
+        _ => &config.rejected_folder,
     };
 
     // Scan the folder for photos
-    let photos_map = scan_accepted_photos(folder);
+    let photos_map = folder_photos(&state, folder, &config.supported_extensions());
 
-    // Get ranking data if available
-    let rankings = if persistent.ranking.initialized {
-        Some(&persistent.ranking.ratings)
-    } else {
-        None
-    };
+    // Get ranking data if available, from the session ranking this folder
+    let target = if status == "accepted" || status == "favorite" { "accepted" } else { "rejected" };
+    let rankings = persistent.ranking_for(target)
+        .filter(|ranking| ranking.initialized)
+        .map(|ranking| &ranking.ratings);
 
     // Build photo list with optional ranking info
     let mut photos: Vec<BrowsePhotoInfo> = photos_map
@@ -965,10 +3964,21 @@ pub fn get_photos_by_status(
                 sigma,
                 score,
                 matches,
+                thumbnail_path: None,
+                width: None,
+                height: None,
             }
         })
         .collect();
 
+    // Unrated photos have neither matches nor a score, so a filter drops them
+    if let Some(min) = min_matches {
+        photos.retain(|photo| photo.matches.is_some_and(|matches| matches >= min));
+    }
+    if let Some(min) = min_score {
+        photos.retain(|photo| photo.score.is_some_and(|score| score >= min));
+    }
+
     // Sort based on the requested sort order
     match sort.as_str() {
         "ranking" => {
@@ -987,6 +3997,15 @@ pub fn get_photos_by_status(
                 score_a.partial_cmp(&score_b).unwrap_or(std::cmp::Ordering::Equal)
             });
         }
+        "uncertainty" => {
+            // Least certain first (highest sigma); unrated photos are the least certain of all.
+            // Only meaningful once ranking is initialized, otherwise nothing is rated.
+            photos.sort_by(|a, b| {
+                let sigma_a = a.sigma.unwrap_or(f64::INFINITY);
+                let sigma_b = b.sigma.unwrap_or(f64::INFINITY);
+                sigma_b.partial_cmp(&sigma_a).unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
         "recent" => {
             // Sort by file modification time (most recent first)
             photos.sort_by(|a, b| {
@@ -1011,12 +4030,25 @@ pub fn get_photos_by_status(
     // Paginate
     let start = page.saturating_sub(1) * per_page;
     let end = (start + per_page).min(total);
-    let paginated = if start < total {
+    let mut paginated = if start < total {
         photos[start..end].to_vec()
     } else {
         Vec::new()
     };
 
+    // Thumbnails can take a while on a cold cache, so don't hold up other commands
+    drop(persistent);
+    drop(config);
+    fill_dimensions(&mut paginated, &state);
+    if include_thumbnails.unwrap_or(false) {
+        paginated.par_iter_mut().for_each(|photo| {
+            match cached_thumbnail(std::path::Path::new(&photo.file_path), BROWSE_THUMBNAIL_SIZE) {
+                Ok(thumb) => photo.thumbnail_path = Some(thumb.to_string_lossy().to_string()),
+                Err(e) => eprintln!("Warning: {}", e),
+            }
+        });
+    }
+
     BrowsePhotosResponse {
         photos: paginated,
         total,
@@ -1025,3 +4057,119 @@ pub fn get_photos_by_status(
         total_pages,
     }
 }
+
+#[tauri::command]
+pub fn find_large_files(threshold_bytes: u64, status: String, state: State<AppState>) -> Vec<(String, u64)> {
+    let config = state.config.lock().unwrap();
+
+    let folder = match status.as_str() {
+        "accepted" => &config.accepted_folder,
+        "favorite" => config.destination_for("favorite").unwrap_or_default(),
+        _ => &config.rejected_folder,
+    };
+
+    let mut large: Vec<(String, u64)> = folder_photos(&state, folder, &config.supported_extensions())
+        .values()
+        .filter_map(|path| {
+            let size = std::fs::metadata(path).ok()?.len();
+            (size > threshold_bytes).then(|| (path.to_string_lossy().to_string(), size))
+        })
+        .collect();
+
+    // Largest first
+    large.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    large
+}
+
+#[tauri::command]
+pub fn get_thumbnail(file_path: String, max_dim: u32) -> Result<String, String> {
+    if max_dim == 0 {
+        return Err("max_dim must be greater than zero".to_string());
+    }
+
+    let thumb = cached_thumbnail(std::path::Path::new(&file_path), max_dim)?;
+    Ok(thumb.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_manager::scan_source_folders;
+    use std::fs;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("photo-tinder-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_decided_bytes_survive_rescan_after_move() {
+        let dir = temp_dir("decided-bytes");
+        let source = dir.join("source");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("a.jpg"), vec![1u8; 300]).unwrap();
+        fs::write(source.join("b.jpg"), vec![2u8; 50]).unwrap();
+
+        let config = Config {
+            source_folders: vec![source.to_string_lossy().to_string()],
+            order: "name".to_string(),
+            ..Config::default()
+        };
+        let mut persistent = PersistentState::default();
+        let records = scan_source_folders(&config);
+        let a = &records[0];
+        let dest = dir.join("accepted.jpg");
+        fs::rename(a.full_path(), &dest).unwrap();
+        let moved = MovedImage { path: dest.to_string_lossy().to_string(), extra_copies: Vec::new() };
+        record_move(a, "accepted", moved, &config, &mut persistent);
+        persistent.record_decision(&a.id, "accepted", None, 0.0);
+
+        let stats = get_stats_data(&records, &persistent, &config);
+        assert_eq!((stats.accepted, stats.accepted_bytes), (1, 300));
+
+        // a.jpg has left the source folder, so only its recorded size is left to count
+        let records = scan_source_folders(&config);
+        assert_eq!(records.len(), 1);
+        let stats = get_stats_data(&records, &persistent, &config);
+        assert_eq!((stats.accepted, stats.accepted_bytes, stats.rejected_bytes), (1, 300, 0));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_undo_reverses_a_whole_triple() {
+        let ids: Vec<String> = ["a", "b", "c"].iter().map(|id| id.to_string()).collect();
+        let mut ranking = RankingState {
+            initialized: true,
+            phase: "global".to_string(),
+            ratings: ids.iter().map(|id| (id.clone(), PhotoRating::default())).collect(),
+            ..RankingState::default()
+        };
+        let rating_config = RatingConfig::default();
+
+        // An earlier pick, so there's history the undo must leave alone
+        apply_triple(&mut ranking, &ids, "a", 0.5, &rating_config).unwrap();
+        let snapshot = |ranking: &RankingState| {
+            let ratings: Vec<(f64, f64, f64, usize)> = ids.iter()
+                .map(|id| &ranking.ratings[id])
+                .map(|r| (r.mu, r.sigma, r.volatility, r.matches_played))
+                .collect();
+            let records: Vec<HeadToHead> = [("a", "b"), ("a", "c"), ("b", "c")].iter()
+                .map(|(x, y)| head_to_head(ranking, x, y))
+                .collect();
+            (ratings, records, ranking.total_comparisons)
+        };
+        let before = snapshot(&ranking);
+
+        apply_triple(&mut ranking, &ids, "c", 0.5, &rating_config).unwrap();
+        assert_eq!(ranking.comparison_history.len(), 4);
+        assert_eq!(head_to_head(&ranking, "c", "b"), HeadToHead { a_wins: 1, b_wins: 0, ties: 0 });
+
+        // One undo takes back both comparisons of the pick, and only those
+        assert_eq!(undo_last_comparison(&mut ranking), vec!["left".to_string(), "left".to_string()]);
+        assert_eq!(snapshot(&ranking), before);
+        assert_eq!(ranking.comparison_history.len(), 2);
+    }
+}
@@ -1,28 +1,233 @@
 //! Configuration management - handles user settings and persistence
 
+use crate::hashing::{ClusterLinkage, HashAlgorithm, DEFAULT_HAMMING_THRESHOLD};
+use crate::ranking::{
+    ClusterConvergence, PairStrategy, CONVERGED_SIGMA, DEFAULT_MU, DEFAULT_SIGMA, DEFAULT_TAU,
+    MAX_REQUIRED_MATCHES, MIN_REQUIRED_MATCHES, MIN_SIGMA, UNCOMPARED_PAIR_PROBABILITY,
+};
+use crate::state::SUPPORTED_EXTENSIONS;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Upper bound on `preload_count`, so a typo can't preload a whole folder
+pub const MAX_PRELOAD_COUNT: usize = 50;
+
+/// Profile that keeps the original single-config file layout
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Active profile name, read from disk on first use
+static ACTIVE_PROFILE: Mutex<Option<String>> = Mutex::new(None);
 
 /// User configuration
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub source_folders: Vec<String>,
+    /// Source folders left out of scans for now; their decisions are kept for when
+    /// they are enabled again
+    pub disabled_folders: Vec<String>,
     pub accepted_folder: String,
     pub rejected_folder: String,
+    /// Destination for "favorite" decisions (falls back to the Accepted folder when unset)
+    pub favorites_folder: String,
+    /// Folders that also get a copy of every accepted (and favorite) photo, e.g. a backup
+    pub extra_accept_destinations: Vec<String>,
+    /// Per-source destinations that replace the global Accepted/Rejected folders, keyed by source path
+    pub destination_overrides: HashMap<String, DestinationOverride>,
+    /// "move" photos out of the source folders, or "copy" them and leave the originals
+    pub operation: String,
+    /// Record triage decisions without touching any files; `commit_dry_run` carries out
+    /// the planned moves later
+    pub dry_run: bool,
+    /// How many folder levels to scan below each source folder (None = unlimited, 1 = top level only)
+    pub scan_depth: Option<usize>,
+    /// Skips after which a photo is deferred and leaves the queue (None = skipped photos always come back)
+    pub max_skips: Option<usize>,
+    /// Glob patterns for files and folders skipped when scanning sources and browsing
+    /// (matched against the path relative to the source folder, or a single name)
+    pub exclude_patterns: Vec<String>,
+    /// Identify source photos by a hash of their content instead of their path, so
+    /// decisions survive renames. Scanning reads the first 64 KB of every file.
+    pub content_ids: bool,
+    /// Recreate each photo's source subfolders under the destination instead of flattening
+    pub preserve_structure: bool,
+    /// Keep each photo's extension as found (IMG_0001.CR2) when moving it; off lowercases
+    /// the extension of the destination name (IMG_0001.cr2)
+    pub preserve_extension_case: bool,
+    /// What happens to rejected photos: "move" (to rejected_folder) | "trash" (system trash)
+    pub rejected_action: String,
+    /// Triage queue order: "interleave" | "date_asc" | "date_desc" | "name"
+    pub order: String,
+    /// Max Hamming distance between hashes for photos to share a cluster
+    pub cluster_threshold: u32,
+    /// Perceptual hash used for clustering: "dhash" | "phash" | "ahash"
+    pub hash_algorithm: HashAlgorithm,
+    /// Cluster membership test: "single_rep" (near the first photo) | "complete" (near every photo)
+    pub cluster_linkage: ClusterLinkage,
+    /// Number of ranking pairs to pre-select ahead of time
+    pub pair_queue_size: usize,
+    /// Global-phase pairing: "uncertainty" | "random" | "closest_score" | "most_uncertain"
+    pub pair_strategy: PairStrategy,
+    /// Glicko-2 system constant: lower values keep ratings steadier across bursts of comparisons
+    pub glicko_tau: f64,
+    /// Starting rating parameters for ranking
+    pub rating: RatingConfig,
+    /// A cluster is ranked internally once its average sigma drops below this.
+    /// Converged clusters stop getting intra-cluster pairs; when none is left, pairs
+    /// come from `pair_strategy` while the phase stays "intra_cluster" until every
+    /// cluster is marked complete. Higher values finish faster, lower rank more carefully.
+    pub cluster_convergence_sigma: f64,
+    /// Fewest matches each member needs before its cluster counts as ranked, even if
+    /// sigma is still high. The requirement is the cluster size minus one, clamped to
+    /// `min_required_matches..=max_required_matches`.
+    pub min_required_matches: usize,
+    /// Most matches each member needs before its cluster counts as ranked
+    pub max_required_matches: usize,
+    /// Chance (0-1) that an "uncertainty" pick is built around a never-compared photo,
+    /// so no photo is starved of comparisons
+    pub uncompared_pair_probability: f64,
+    /// User-added file extensions treated as images alongside the built-in list
+    pub extra_extensions: Vec<String>,
+    /// When non-empty, only these extensions are queued for triage
+    pub extension_filter: Vec<String>,
+    /// Compute perceptual hashes as photos are accepted so ranking init is fast
+    pub hash_on_accept: bool,
+    /// How many upcoming photos the frontend preloads while triaging (capped at MAX_PRELOAD_COUNT)
+    pub preload_count: usize,
+    /// Write an XMP sidecar with the photo's star rating next to each accepted photo
+    pub write_xmp: bool,
+    /// Shortcuts shown in the folder browser
+    pub quick_access: Vec<QuickAccessLocation>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            source_folders: Vec::new(),
+            disabled_folders: Vec::new(),
+            accepted_folder: String::new(),
+            rejected_folder: String::new(),
+            favorites_folder: String::new(),
+            extra_accept_destinations: Vec::new(),
+            destination_overrides: HashMap::new(),
+            operation: "move".to_string(),
+            dry_run: false,
+            scan_depth: None,
+            max_skips: None,
+            exclude_patterns: vec![".*".to_string()],
+            content_ids: false,
+            preserve_structure: false,
+            preserve_extension_case: true,
+            rejected_action: "move".to_string(),
+            order: "interleave".to_string(),
+            cluster_threshold: DEFAULT_HAMMING_THRESHOLD,
+            hash_algorithm: HashAlgorithm::default(),
+            cluster_linkage: ClusterLinkage::default(),
+            pair_queue_size: 5,
+            pair_strategy: PairStrategy::default(),
+            glicko_tau: DEFAULT_TAU,
+            rating: RatingConfig::default(),
+            cluster_convergence_sigma: CONVERGED_SIGMA,
+            min_required_matches: MIN_REQUIRED_MATCHES,
+            max_required_matches: MAX_REQUIRED_MATCHES,
+            uncompared_pair_probability: UNCOMPARED_PAIR_PROBABILITY,
+            extra_extensions: Vec::new(),
+            extension_filter: Vec::new(),
+            hash_on_accept: false,
+            preload_count: 6,
+            write_xmp: false,
+            quick_access: QuickAccessLocation::defaults(),
+        }
+    }
+}
+
+/// Destination folders for one source folder; unset entries use the global folders
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DestinationOverride {
+    pub accepted_override: Option<String>,
+    pub rejected_override: Option<String>,
+}
+
+/// Glicko rating parameters.
+/// Changing them only affects photos initialized afterwards (and the sigma floor
+/// for future comparisons); existing ratings are left as they are.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RatingConfig {
+    /// Rating given to newly initialized photos
+    pub default_mu: f64,
+    /// Starting uncertainty for newly initialized photos
+    pub default_sigma: f64,
+    /// Uncertainty never drops below this, so ratings can keep moving
+    pub min_sigma: f64,
+}
+
+impl Default for RatingConfig {
+    fn default() -> Self {
+        Self {
+            default_mu: DEFAULT_MU,
+            default_sigma: DEFAULT_SIGMA,
+            min_sigma: MIN_SIGMA,
+        }
+    }
+}
+
+impl RatingConfig {
+    /// Check that sigmas are positive and the floor sits below the starting uncertainty
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.default_mu.is_finite() {
+            return Err("default_mu must be a finite number".to_string());
+        }
+        if !(self.min_sigma > 0.0 && self.default_sigma > 0.0) {
+            return Err("default_sigma and min_sigma must be positive".to_string());
+        }
+        if self.min_sigma >= self.default_sigma {
+            return Err("min_sigma must be less than default_sigma".to_string());
+        }
+        Ok(())
+    }
 }
 
 impl Config {
-    /// Get the config directory path (OS-specific)
-    pub fn config_dir() -> PathBuf {
+    /// Get the app directory path (OS-specific), shared by all profiles
+    pub fn base_dir() -> PathBuf {
         dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join("photo-tinder")
     }
 
-    /// Get the config file path
+    /// Get the directory holding named profiles (`<name>.json` plus a `<name>/` data dir)
+    pub fn profiles_dir() -> PathBuf {
+        Self::base_dir().join("profiles")
+    }
+
+    /// Get the active profile's data directory (the app directory itself for "default")
+    pub fn config_dir() -> PathBuf {
+        let profile = active_profile();
+        if profile == DEFAULT_PROFILE {
+            Self::base_dir()
+        } else {
+            Self::profiles_dir().join(profile)
+        }
+    }
+
+    /// Get the config file path of the active profile
     pub fn config_path() -> PathBuf {
-        Self::config_dir().join("config.json")
+        Self::profile_config_path(&active_profile())
+    }
+
+    /// Get the config file path of a named profile
+    pub fn profile_config_path(name: &str) -> PathBuf {
+        if name == DEFAULT_PROFILE {
+            Self::base_dir().join("config.json")
+        } else {
+            Self::profiles_dir().join(format!("{}.json", name))
+        }
     }
 
     /// Get the state file path
@@ -35,6 +240,21 @@ impl Config {
         Self::config_dir().join("photo_hashes.json")
     }
 
+    /// Get the triage (source folder) hashes file path
+    pub fn triage_hashes_path() -> PathBuf {
+        Self::config_dir().join("triage_hashes.json")
+    }
+
+    /// Get the move audit log path
+    pub fn moves_log_path() -> PathBuf {
+        Self::config_dir().join("moves.log")
+    }
+
+    /// Get the thumbnail cache directory (shared by all profiles)
+    pub fn thumbnails_dir() -> PathBuf {
+        Self::base_dir().join("thumbnails")
+    }
+
     /// Load config from file, or return default
     pub fn load() -> Self {
         let path = Self::config_path();
@@ -58,11 +278,187 @@ impl Config {
         }
 
         let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
-        fs::write(&path, json).map_err(|e| e.to_string())?;
+        write_atomic(&path, json.as_bytes()).map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    /// Write the config as JSON to an arbitrary file (for moving a setup between machines)
+    pub fn export_to(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Read a config previously written by `export_to`
+    pub fn import_from(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid config file: {}", e))
+    }
+
+    /// Configured source and destination folders that don't exist on this machine
+    pub fn missing_folders(&self) -> Vec<String> {
+        let destinations = [&self.accepted_folder, &self.rejected_folder, &self.favorites_folder];
+        let overrides = self.destination_overrides.values()
+            .flat_map(|o| o.accepted_override.iter().chain(o.rejected_override.iter()));
+
+        self.source_folders.iter()
+            .chain(destinations)
+            .chain(&self.extra_accept_destinations)
+            .chain(overrides)
+            .filter(|folder| !folder.is_empty() && !Path::new(folder.as_str()).exists())
+            .cloned()
+            .collect()
+    }
+
+    /// Every configured destination folder, overrides included (non-empty, deduplicated)
+    pub fn destination_folders(&self) -> Vec<&str> {
+        let mut folders: Vec<&str> = Vec::new();
+        let overrides = self.destination_overrides.values()
+            .flat_map(|o| o.accepted_override.iter().chain(o.rejected_override.iter()));
+
+        let extras = self.extra_accept_destinations.iter();
+
+        for folder in [&self.accepted_folder, &self.rejected_folder, &self.favorites_folder].into_iter().chain(extras).chain(overrides) {
+            if !folder.is_empty() && !folders.contains(&folder.as_str()) {
+                folders.push(folder);
+            }
+        }
+        folders
+    }
+
+    /// Built-in extensions merged with user extras (lowercase, no leading dot)
+    pub fn supported_extensions(&self) -> Vec<String> {
+        let mut extensions: Vec<String> = SUPPORTED_EXTENSIONS.iter().map(|e| e.to_string()).collect();
+        for ext in &self.extra_extensions {
+            let ext = normalize_extension(ext);
+            if !ext.is_empty() && !extensions.contains(&ext) {
+                extensions.push(ext);
+            }
+        }
+        extensions
+    }
+
+    /// Destination folder for a triage decision, or None if the file stays put
+    pub fn destination_for(&self, decision: &str) -> Option<&str> {
+        match decision {
+            "accepted" => Some(&self.accepted_folder),
+            "rejected" => Some(&self.rejected_folder),
+            "favorite" if !self.favorites_folder.is_empty() => Some(&self.favorites_folder),
+            "favorite" => Some(&self.accepted_folder),
+            _ => None,
+        }
+    }
+
+    /// Destination for a decision on a photo from `source_folder`, honouring that
+    /// source's overrides. Favorites without a favorites folder follow the Accepted destination.
+    pub fn destination_for_source(&self, decision: &str, source_folder: &str) -> Option<&str> {
+        let overrides = self.destination_overrides.get(source_folder);
+        let accepted = overrides.and_then(|o| o.accepted_override.as_deref()).filter(|f| !f.is_empty());
+        let rejected = overrides.and_then(|o| o.rejected_override.as_deref()).filter(|f| !f.is_empty());
+
+        match decision {
+            "accepted" => accepted.or(Some(&self.accepted_folder)),
+            "rejected" => rejected.or(Some(&self.rejected_folder)),
+            "favorite" if !self.favorites_folder.is_empty() => Some(&self.favorites_folder),
+            "favorite" => accepted.or(Some(&self.accepted_folder)),
+            _ => None,
+        }
+    }
+
+    /// Extensions included in the triage scan: the supported list narrowed by
+    /// `extension_filter` when one is set
+    pub fn scan_extensions(&self) -> Vec<String> {
+        let supported = self.supported_extensions();
+        if self.extension_filter.is_empty() {
+            return supported;
+        }
+
+        let filter: Vec<String> = self.extension_filter.iter().map(|e| normalize_extension(e)).collect();
+        supported.into_iter().filter(|e| filter.contains(e)).collect()
+    }
+
+    /// Folder ranked by a ranking session target: "rejected" or (otherwise) "accepted"
+    pub fn ranking_folder(&self, target: &str) -> &str {
+        match target {
+            "rejected" => &self.rejected_folder,
+            _ => &self.accepted_folder,
+        }
+    }
 
+    /// Reject layouts where a destination folder is, contains, or sits inside a source
+    /// folder: moved photos would be rescanned and queued again. Paths are compared
+    /// after resolving symlinks and `..`.
+    pub fn check_folder_overlap(&self) -> Result<(), String> {
+        let mut destinations = vec![
+            ("Accepted", &self.accepted_folder),
+            ("Rejected", &self.rejected_folder),
+            ("Favorites", &self.favorites_folder),
+        ];
+        destinations.extend(self.extra_accept_destinations.iter().map(|f| ("Extra accept", f)));
+        for overrides in self.destination_overrides.values() {
+            destinations.extend(overrides.accepted_override.iter().map(|f| ("Accepted override", f)));
+            destinations.extend(overrides.rejected_override.iter().map(|f| ("Rejected override", f)));
+        }
+
+        for source in &self.source_folders {
+            let source_path = resolve_path(source);
+            for (label, destination) in &destinations {
+                if destination.is_empty() {
+                    continue;
+                }
+                let destination_path = resolve_path(destination);
+
+                if destination_path == source_path {
+                    return Err(format!("{} folder {} is the same as source folder {}", label, destination, source));
+                }
+                if destination_path.starts_with(&source_path) {
+                    return Err(format!("{} folder {} is inside source folder {}", label, destination, source));
+                }
+                if source_path.starts_with(&destination_path) {
+                    return Err(format!("Source folder {} is inside {} folder {}", source, label, destination));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// When intra-cluster ranking considers a cluster done
+    pub fn cluster_convergence(&self) -> ClusterConvergence {
+        ClusterConvergence {
+            sigma: self.cluster_convergence_sigma,
+            min_matches: self.min_required_matches,
+            max_matches: self.max_required_matches,
+        }
+    }
+
+    /// Check the pairing settings: a positive convergence sigma, ordered match bounds,
+    /// a probability for the uncompared-photo bias and a positive Glicko-2 tau
+    pub fn validate_pairing(&self) -> Result<(), String> {
+        if !(self.cluster_convergence_sigma.is_finite() && self.cluster_convergence_sigma > 0.0) {
+            return Err("cluster_convergence_sigma must be positive".to_string());
+        }
+        if self.min_required_matches > self.max_required_matches {
+            return Err("min_required_matches must not exceed max_required_matches".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.uncompared_pair_probability) {
+            return Err("uncompared_pair_probability must be between 0 and 1".to_string());
+        }
+        // tau <= 0 never brackets the volatility root (or divides by zero)
+        if !(self.glicko_tau.is_finite() && self.glicko_tau > 0.0) {
+            return Err("glicko_tau must be positive".to_string());
+        }
         Ok(())
     }
 
+    /// Source folders that are scanned and watched (all but the disabled ones)
+    pub fn enabled_source_folders(&self) -> Vec<String> {
+        self.source_folders.iter()
+            .filter(|folder| !self.disabled_folders.contains(folder))
+            .cloned()
+            .collect()
+    }
+
     /// Check if config is valid (has required folders set)
     pub fn is_valid(&self) -> bool {
         !self.source_folders.is_empty()
@@ -71,6 +467,134 @@ impl Config {
     }
 }
 
+/// Absolute, symlink-free form of a path that may not exist yet: the deepest
+/// existing ancestor is canonicalized and the missing components re-appended
+fn resolve_path(path: &str) -> PathBuf {
+    let mut existing = Path::new(path);
+    let mut missing = Vec::new();
+
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return missing.iter().rev().fold(canonical, |resolved, name| resolved.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return PathBuf::from(path),
+        }
+    }
+}
+
+/// File remembering the active profile across restarts
+fn active_profile_path() -> PathBuf {
+    Config::base_dir().join("active_profile")
+}
+
+/// Name of the active config profile
+pub fn active_profile() -> String {
+    let mut active = ACTIVE_PROFILE.lock().unwrap();
+    active.get_or_insert_with(|| {
+        fs::read_to_string(active_profile_path())
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| validate_profile_name(name).is_ok() && profile_exists(name))
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    })
+    .clone()
+}
+
+/// Make `name` the active profile and remember it for the next launch
+pub fn set_active_profile(name: &str) -> Result<(), String> {
+    validate_profile_name(name)?;
+    if !profile_exists(name) {
+        return Err(format!("Profile not found: {}", name));
+    }
+
+    let path = active_profile_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(&path, name).map_err(|e| e.to_string())?;
+
+    *ACTIVE_PROFILE.lock().unwrap() = Some(name.to_string());
+    Ok(())
+}
+
+/// Whether a profile exists ("default" always does)
+pub fn profile_exists(name: &str) -> bool {
+    name == DEFAULT_PROFILE || Config::profile_config_path(name).exists()
+}
+
+/// All profile names, "default" first
+pub fn list_profiles() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(Config::profiles_dir())
+        .map(|entries| {
+            entries.filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+                .filter(|name| name != DEFAULT_PROFILE && validate_profile_name(name).is_ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names.insert(0, DEFAULT_PROFILE.to_string());
+    names
+}
+
+/// Create a profile with default settings
+pub fn create_profile(name: &str) -> Result<(), String> {
+    validate_profile_name(name)?;
+    if profile_exists(name) {
+        return Err(format!("Profile already exists: {}", name));
+    }
+
+    let path = Config::profile_config_path(name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(&Config::default()).map_err(|e| e.to_string())?;
+    write_atomic(&path, json.as_bytes()).map_err(|e| e.to_string())
+}
+
+/// Write `contents` to a temporary file beside `path`, flush it to disk and rename it
+/// over `path`. The rename is atomic on one filesystem, so a crash mid-write leaves
+/// the previous file intact instead of a truncated one.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Profile names become file names, so keep them to letters, digits, '-' and '_'
+pub fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 64 {
+        return Err("Profile name must be 1-64 characters".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Profile name may only contain letters, digits, '-' and '_'".to_string());
+    }
+    Ok(())
+}
+
+/// Lowercase an extension and strip any leading dots (".CR3" -> "cr3")
+pub fn normalize_extension(ext: &str) -> String {
+    ext.trim().trim_start_matches('.').to_lowercase()
+}
+
 /// Quick access locations for folder browser
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuickAccessLocation {
@@ -104,3 +628,66 @@ impl QuickAccessLocation {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folder_overlap_detects_nesting_through_dot_dot() {
+        let root = std::env::temp_dir().join(format!("photo-tinder-overlap-{}", std::process::id()));
+        fs::create_dir_all(root.join("shoots/day1")).unwrap();
+        let path = |p: &str| root.join(p).to_string_lossy().to_string();
+
+        let mut config = Config {
+            source_folders: vec![path("shoots")],
+            accepted_folder: path("accepted"),
+            rejected_folder: path("rejected"),
+            ..Config::default()
+        };
+        assert!(config.check_folder_overlap().is_ok());
+
+        // Not created yet, and spelled with `..`
+        config.accepted_folder = path("shoots/day1/../keepers");
+        let err = config.check_folder_overlap().unwrap_err();
+        assert!(err.contains("inside source folder"), "{}", err);
+
+        config.accepted_folder = path("accepted");
+        config.rejected_folder = root.to_string_lossy().to_string();
+        let err = config.check_folder_overlap().unwrap_err();
+        assert!(err.starts_with("Source folder"), "{}", err);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_validate_pairing_rejects_non_positive_tau() {
+        assert!(Config::default().validate_pairing().is_ok());
+        for tau in [0.0, -0.5, f64::NAN, f64::INFINITY] {
+            let config = Config { glicko_tau: tau, ..Config::default() };
+            let err = config.validate_pairing().unwrap_err();
+            assert!(err.contains("glicko_tau"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_set_active_profile_rejects_paths() {
+        for name in ["../x", "/etc/passwd", "a/b", ""] {
+            assert!(set_active_profile(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_file_without_leaving_temp() {
+        let dir = std::env::temp_dir().join(format!("photo-tinder-atomic-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json");
+
+        fs::write(&path, "old contents that are longer").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
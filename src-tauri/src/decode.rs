@@ -0,0 +1,127 @@
+//! Image decoding with fallbacks for formats the `image` crate can't read
+
+use crate::metadata::exif_orientation;
+use crate::state::{HEIF_EXTENSIONS, RAW_EXTENSIONS};
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageFormat};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+/// Embedded previews smaller than this are skipped when scanning RAW files
+const MIN_PREVIEW_DIM: u32 = 64;
+
+/// Maximum number of embedded JPEG candidates tried per RAW file
+const MAX_PREVIEW_CANDIDATES: usize = 16;
+
+/// Decode an image for analysis, turned upright per its EXIF orientation.
+/// Returns the pixels and the name of the decoder that produced them
+/// ("image", "raw-preview" or "libheif").
+pub fn open_image(image_path: &Path) -> Result<(DynamicImage, &'static str), String> {
+    let (img, decoder) = decode_image(image_path)?;
+    // libheif already applies the container's irot/imir transforms, which carry
+    // the same rotation as the EXIF tag
+    if decoder == "libheif" {
+        return Ok((img, decoder));
+    }
+    Ok((apply_exif_orientation(img, image_path), decoder))
+}
+
+fn decode_image(image_path: &Path) -> Result<(DynamicImage, &'static str), String> {
+    let ext = image_path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    if RAW_EXTENSIONS.contains(&ext.as_str()) {
+        return decode_raw_preview(image_path).map(|img| (img, "raw-preview"));
+    }
+
+    if HEIF_EXTENSIONS.contains(&ext.as_str()) {
+        return decode_heif(image_path).map(|img| (img, "libheif"));
+    }
+
+    image::open(image_path)
+        .map(|img| (img, "image"))
+        .map_err(|e| format!("Could not open image {}: {}", image_path.display(), e))
+}
+
+/// Rotate/flip decoded pixels the way the file's EXIF orientation says, so a
+/// portrait shot matches its pre-rotated copy. Files without the tag are unchanged.
+fn apply_exif_orientation(mut img: DynamicImage, image_path: &Path) -> DynamicImage {
+    if let Some(orientation) = exif_orientation(image_path).and_then(Orientation::from_exif) {
+        img.apply_orientation(orientation);
+    }
+    img
+}
+
+/// Decode the JPEG preview embedded in a RAW file: the EXIF thumbnail if there is
+/// one, otherwise the first decodable JPEG stream found in the file
+fn decode_raw_preview(image_path: &Path) -> Result<DynamicImage, String> {
+    if let Some(img) = decode_exif_thumbnail(image_path) {
+        return Ok(img);
+    }
+
+    let bytes = fs::read(image_path).map_err(|e| format!("Could not read {}: {}", image_path.display(), e))?;
+
+    // JPEG streams start with an SOI marker followed by another marker
+    bytes.windows(3)
+        .enumerate()
+        .filter(|(_, w)| w == &[0xFF, 0xD8, 0xFF])
+        .take(MAX_PREVIEW_CANDIDATES)
+        .find_map(|(offset, _)| {
+            image::load_from_memory_with_format(&bytes[offset..], ImageFormat::Jpeg)
+                .ok()
+                .filter(|img| img.width() >= MIN_PREVIEW_DIM && img.height() >= MIN_PREVIEW_DIM)
+        })
+        .ok_or_else(|| format!("No embedded preview found in {}", image_path.display()))
+}
+
+/// Decode the IFD1 thumbnail of TIFF-based RAWs (CR2, NEF, ARW, DNG, ...)
+fn decode_exif_thumbnail(image_path: &Path) -> Option<DynamicImage> {
+    let file = File::open(image_path).ok()?;
+    let exif = exif::Reader::new().read_from_container(&mut BufReader::new(file)).ok()?;
+
+    let offset = exif.get_field(exif::Tag::JPEGInterchangeFormat, exif::In::THUMBNAIL)?
+        .value.get_uint(0)? as usize;
+    let length = exif.get_field(exif::Tag::JPEGInterchangeFormatLength, exif::In::THUMBNAIL)?
+        .value.get_uint(0)? as usize;
+
+    let data = exif.buf().get(offset..offset.checked_add(length)?)?;
+    image::load_from_memory_with_format(data, ImageFormat::Jpeg)
+        .ok()
+        .filter(|img| img.width() >= MIN_PREVIEW_DIM && img.height() >= MIN_PREVIEW_DIM)
+}
+
+#[cfg(feature = "heic")]
+fn decode_heif(image_path: &Path) -> Result<DynamicImage, String> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let lib_heif = LibHeif::new();
+    let ctx = HeifContext::read_from_file(&image_path.to_string_lossy()).map_err(|e| e.to_string())?;
+    let handle = ctx.primary_image_handle().map_err(|e| e.to_string())?;
+    let decoded = lib_heif.decode(&handle, ColorSpace::Rgb(RgbChroma::Rgb), None)
+        .map_err(|e| e.to_string())?;
+
+    let plane = decoded.planes().interleaved
+        .ok_or_else(|| format!("No RGB plane decoded from {}", image_path.display()))?;
+
+    // Copy row by row, dropping any stride padding
+    let row_bytes = plane.width as usize * 3;
+    let mut rgb = Vec::with_capacity(row_bytes * plane.height as usize);
+    for row in 0..plane.height as usize {
+        let start = row * plane.stride;
+        rgb.extend_from_slice(&plane.data[start..start + row_bytes]);
+    }
+
+    image::RgbImage::from_raw(plane.width, plane.height, rgb)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| format!("Invalid HEIF buffer for {}", image_path.display()))
+}
+
+#[cfg(not(feature = "heic"))]
+fn decode_heif(image_path: &Path) -> Result<DynamicImage, String> {
+    Err(format!(
+        "Cannot decode {}: built without the `heic` feature",
+        image_path.display()
+    ))
+}
@@ -0,0 +1,237 @@
+//! Exporting ranking results to shareable files
+
+use crate::ranking::get_conservative_score;
+use crate::state::{ComparisonRecord, PhotoRating};
+use crate::thumbnails::render_thumbnail;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Longest edge of thumbnails embedded in HTML exports
+const HTML_THUMBNAIL_SIZE: u32 = 320;
+
+/// A single ranked photo as written to export files
+#[derive(Debug, Clone)]
+pub struct ExportRow {
+    pub rank: usize,
+    pub id: String,
+    pub filename: String,
+    pub file_path: String,
+    pub mu: f64,
+    pub sigma: f64,
+    pub score: f64,
+    pub matches: usize,
+}
+
+/// Ranking progress backed up independently of state.json
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RatingsBackup {
+    pub ratings: HashMap<String, PhotoRating>,
+    #[serde(default)]
+    pub comparison_history: Vec<ComparisonRecord>,
+}
+
+/// Map conservative scores to 1-5 stars by percentile among compared photos.
+/// Photos that were never compared are left out (0 stars).
+pub fn star_ratings(ratings: &HashMap<String, PhotoRating>) -> HashMap<String, u8> {
+    let score = |r: &PhotoRating| get_conservative_score(r.mu, r.sigma);
+    let mut scores: Vec<f64> = ratings.values()
+        .filter(|r| r.matches_played > 0)
+        .map(score)
+        .collect();
+    scores.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    ratings.iter()
+        .filter(|(_, r)| r.matches_played > 0)
+        .map(|(id, r)| {
+            // Ties share the lowest position so equal scores get equal stars
+            let below = scores.partition_point(|&s| s < score(r));
+            (id.clone(), (1 + below * 5 / scores.len()) as u8)
+        })
+        .collect()
+}
+
+/// Sidecar path for a photo: its full file name plus `.xmp` (IMG_1.CR2.xmp), so
+/// the RAW and JPEG of a pair don't share one sidecar
+pub fn xmp_sidecar_path(photo_path: &Path) -> std::path::PathBuf {
+    let mut path = photo_path.as_os_str().to_owned();
+    path.push(".xmp");
+    path.into()
+}
+
+/// Write an XMP sidecar next to a photo carrying its star rating (0 = unrated)
+pub fn write_xmp_sidecar(photo_path: &Path, stars: u8) -> Result<(), String> {
+    let xmp = format!(
+        r#"<?xpacket begin="{bom}" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:Rating="{stars}"/>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>
+"#,
+        bom = '\u{feff}',
+        stars = stars.min(5),
+    );
+
+    let path = xmp_sidecar_path(photo_path);
+    fs::write(&path, xmp).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Write ratings and comparison history as JSON
+pub fn write_ratings_json(path: &str, backup: &RatingsBackup) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(backup).map_err(|e| e.to_string())?;
+    write_export_file(path, &json)
+}
+
+/// Read ratings previously written by `write_ratings_json`
+pub fn read_ratings_json(path: &str) -> Result<RatingsBackup, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Invalid ratings file {}: {}", path, e))
+}
+
+/// Write a standalone HTML leaderboard page
+pub fn write_leaderboard_html(path: &str, rows: &[ExportRow], embed_thumbnails: bool) -> Result<(), String> {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Photo Tinder Leaderboard</title>\n<style>\n");
+    html.push_str("body { font-family: -apple-system, sans-serif; background: #111; color: #eee; margin: 2em; }\n");
+    html.push_str("table { border-collapse: collapse; width: 100%; }\n");
+    html.push_str("th, td { padding: 8px 12px; border-bottom: 1px solid #333; text-align: left; }\n");
+    html.push_str("td.num { text-align: right; font-variant-numeric: tabular-nums; }\n");
+    html.push_str("img { max-width: 320px; max-height: 320px; border-radius: 4px; }\n");
+    html.push_str("</style>\n</head>\n<body>\n<h1>Leaderboard</h1>\n<table>\n<tr>");
+    html.push_str("<th>#</th>");
+    if embed_thumbnails {
+        html.push_str("<th>Photo</th>");
+    }
+    html.push_str("<th>Filename</th><th>Score</th><th>Rating</th><th>Uncertainty</th><th>Matches</th></tr>\n");
+
+    for row in rows {
+        html.push_str(&format!("<tr><td class=\"num\">{}</td>", row.rank));
+
+        if embed_thumbnails {
+            match render_thumbnail(Path::new(&row.file_path), HTML_THUMBNAIL_SIZE) {
+                Ok(bytes) => {
+                    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+                    html.push_str(&format!(
+                        "<td><img src=\"data:image/jpeg;base64,{}\" alt=\"{}\"></td>",
+                        encoded,
+                        escape_html(&row.filename)
+                    ));
+                }
+                Err(e) => {
+                    eprintln!("Warning: {}", e);
+                    html.push_str("<td></td>");
+                }
+            }
+        }
+
+        html.push_str(&format!(
+            "<td>{}</td><td class=\"num\">{:.1}</td><td class=\"num\">{:.1}</td><td class=\"num\">{:.1}</td><td class=\"num\">{}</td></tr>\n",
+            escape_html(&row.filename),
+            row.score,
+            row.mu,
+            row.sigma,
+            row.matches
+        ));
+    }
+
+    html.push_str("</table>\n</body>\n</html>\n");
+
+    write_export_file(path, &html)
+}
+
+/// Write the leaderboard as CSV with full-precision ratings
+pub fn write_leaderboard_csv(path: &str, rows: &[ExportRow]) -> Result<(), String> {
+    let mut csv = String::from("id,filename,file_path,mu,sigma,score,matches\n");
+
+    for row in rows {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            escape_csv(&row.id),
+            escape_csv(&row.filename),
+            escape_csv(&row.file_path),
+            row.mu,
+            row.sigma,
+            row.score,
+            row.matches
+        ));
+    }
+
+    write_export_file(path, &csv)
+}
+
+/// Write export contents, creating parent directories as needed
+fn write_export_file(path: &str, contents: &str) -> Result<(), String> {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Escape text for inclusion in HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Quote a CSV field if it contains separators, quotes or newlines
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rated(mu: f64, matches_played: usize) -> PhotoRating {
+        PhotoRating { mu, matches_played, ..PhotoRating::default() }
+    }
+
+    #[test]
+    fn test_star_ratings_by_percentile() {
+        let mut ratings: HashMap<String, PhotoRating> = (1..=5)
+            .map(|i| (format!("p{}", i), rated(i as f64 * 100.0, 3)))
+            .collect();
+        ratings.insert("tied".to_string(), rated(100.0, 3));
+        ratings.insert("uncompared".to_string(), rated(10_000.0, 0));
+
+        let stars = star_ratings(&ratings);
+        assert_eq!(stars.len(), 6);
+        assert!(!stars.contains_key("uncompared"));
+        // Equal scores share the lower position
+        assert_eq!((stars["p1"], stars["tied"]), (1, 1));
+        assert_eq!(stars["p5"], 5);
+        assert!(stars["p2"] <= stars["p3"] && stars["p3"] <= stars["p4"] && stars["p4"] <= stars["p5"]);
+        assert!(star_ratings(&HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_xmp_sidecar_per_file_of_raw_jpeg_pair() {
+        let dir = std::env::temp_dir().join(format!("photo-tinder-xmp-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (raw, jpeg) = (dir.join("IMG_1.CR2"), dir.join("IMG_1.JPG"));
+
+        write_xmp_sidecar(&raw, 4).unwrap();
+        write_xmp_sidecar(&jpeg, 9).unwrap();
+        assert_eq!(xmp_sidecar_path(&raw), dir.join("IMG_1.CR2.xmp"));
+
+        let raw_xmp = fs::read_to_string(xmp_sidecar_path(&raw)).unwrap();
+        let jpeg_xmp = fs::read_to_string(xmp_sidecar_path(&jpeg)).unwrap();
+        assert!(raw_xmp.contains(r#"xmp:Rating="4""#), "{}", raw_xmp);
+        // Clamped to the 0-5 XMP range
+        assert!(jpeg_xmp.contains(r#"xmp:Rating="5""#), "{}", jpeg_xmp);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
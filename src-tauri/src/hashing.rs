@@ -1,23 +1,109 @@
 //! Perceptual image hashing for similarity detection
 
-use image::GenericImageView;
+use crate::decode::open_image;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 const HASH_SIZE: u32 = 16; // 16x16 = 256 bits
-pub const HAMMING_THRESHOLD: u32 = 10;
+const PHASH_IMAGE_SIZE: u32 = 32; // DCT input is 32x32
+const PHASH_BLOCK_SIZE: u32 = 8; // Low-frequency 8x8 block = 64 bits
+pub const DEFAULT_HAMMING_THRESHOLD: u32 = 10;
+pub const MAX_HAMMING_THRESHOLD: u32 = 64;
+
+/// Perceptual hash algorithm used for clustering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// Difference hash: 256 bits from adjacent-pixel gradients
+    #[default]
+    Dhash,
+    /// DCT hash: 64 bits from low frequencies, tolerant of exposure changes and slight crops
+    Phash,
+    /// Average hash: 256 bits, each pixel against the mean. Fastest; only separates clearly different scenes
+    Ahash,
+}
+
+impl HashAlgorithm {
+    /// Length of the hex string this algorithm produces
+    pub fn hex_len(self) -> usize {
+        match self {
+            HashAlgorithm::Dhash | HashAlgorithm::Ahash => (HASH_SIZE * HASH_SIZE / 4) as usize,
+            HashAlgorithm::Phash => (PHASH_BLOCK_SIZE * PHASH_BLOCK_SIZE / 4) as usize,
+        }
+    }
+}
+
+/// Compute the hash for an image with the given algorithm
+pub fn compute_hash(image_path: &Path, algorithm: HashAlgorithm) -> Option<String> {
+    match algorithm {
+        HashAlgorithm::Dhash => compute_dhash(image_path),
+        HashAlgorithm::Phash => compute_phash(image_path),
+        HashAlgorithm::Ahash => compute_ahash(image_path),
+    }
+}
+
+/// Hash an already decoded image with the given algorithm
+pub fn hash_image(img: &DynamicImage, algorithm: HashAlgorithm) -> String {
+    match algorithm {
+        HashAlgorithm::Dhash => dhash_image(img),
+        HashAlgorithm::Phash => phash_image(img),
+        HashAlgorithm::Ahash => ahash_image(img),
+    }
+}
+
+/// Load an image for hashing, falling back to RAW previews / libheif for formats `image` can't read
+fn load_for_hashing(image_path: &Path) -> Option<DynamicImage> {
+    match open_image(image_path) {
+        Ok((img, decoder)) => {
+            if decoder != "image" {
+                eprintln!("Decoded {} with {}", image_path.display(), decoder);
+            }
+            Some(img)
+        }
+        Err(e) => {
+            eprintln!("Warning: {}", e);
+            None
+        }
+    }
+}
 
 /// Compute dHash (difference hash) for an image
 /// Returns a 64-character hex string (256 bits)
 pub fn compute_dhash(image_path: &Path) -> Option<String> {
-    // Load and resize image
-    let img = match image::open(image_path) {
-        Ok(img) => img,
-        Err(e) => {
-            eprintln!("Warning: Could not open image {}: {}", image_path.display(), e);
-            return None;
-        }
-    };
+    load_for_hashing(image_path).map(|img| dhash_image(&img))
+}
+
+/// Compute pHash (DCT hash) for an image
+/// Returns a 16-character hex string (64 bits)
+pub fn compute_phash(image_path: &Path) -> Option<String> {
+    load_for_hashing(image_path).map(|img| phash_image(&img))
+}
+
+/// Compute aHash (average hash) for an image
+/// Returns a 64-character hex string (256 bits)
+pub fn compute_ahash(image_path: &Path) -> Option<String> {
+    load_for_hashing(image_path).map(|img| ahash_image(&img))
+}
 
+fn ahash_image(img: &DynamicImage) -> String {
+    let gray = img.grayscale();
+    let resized = image::imageops::resize(
+        &gray.to_luma8(),
+        HASH_SIZE,
+        HASH_SIZE,
+        image::imageops::FilterType::Triangle,
+    );
+
+    // 1 if the pixel is brighter than the image mean
+    let pixels: Vec<u32> = resized.pixels().map(|p| p[0] as u32).collect();
+    let mean = pixels.iter().sum::<u32>() as f64 / pixels.len() as f64;
+
+    let hash_bits: Vec<bool> = pixels.iter().map(|&p| p as f64 > mean).collect();
+    bits_to_hex(&hash_bits)
+}
+
+fn dhash_image(img: &DynamicImage) -> String {
     // Convert to grayscale and resize to (HASH_SIZE+1) x HASH_SIZE
     // We need one extra column to compute horizontal differences
     let gray = img.grayscale();
@@ -40,16 +126,70 @@ pub fn compute_dhash(image_path: &Path) -> Option<String> {
         }
     }
 
-    // Convert bits to hex string
-    let mut hex = String::with_capacity(64);
-    for chunk in hash_bits.chunks(4) {
+    bits_to_hex(&hash_bits)
+}
+
+fn phash_image(img: &DynamicImage) -> String {
+    let n = PHASH_IMAGE_SIZE as usize;
+    let block = PHASH_BLOCK_SIZE as usize;
+
+    let gray = img.grayscale();
+    let resized = image::imageops::resize(
+        &gray.to_luma8(),
+        PHASH_IMAGE_SIZE,
+        PHASH_IMAGE_SIZE,
+        image::imageops::FilterType::Lanczos3,
+    );
+
+    // DCT-II basis, only for the low frequencies we keep: cos((2x+1)uπ / 2N)
+    let mut basis = vec![0.0f64; block * n];
+    for u in 0..block {
+        for x in 0..n {
+            basis[u * n + x] =
+                (((2 * x + 1) * u) as f64 * std::f64::consts::PI / (2 * n) as f64).cos();
+        }
+    }
+
+    // Separable 2D DCT: transform rows first, then columns of the result
+    let pixels: Vec<f64> = resized.pixels().map(|p| p[0] as f64).collect();
+    let mut rows = vec![0.0f64; n * block]; // rows[y][u]
+    for y in 0..n {
+        for u in 0..block {
+            rows[y * block + u] = (0..n)
+                .map(|x| pixels[y * n + x] * basis[u * n + x])
+                .sum();
+        }
+    }
+    let mut coefficients = Vec::with_capacity(block * block); // row-major [v][u]
+    for v in 0..block {
+        for u in 0..block {
+            coefficients.push(
+                (0..n)
+                    .map(|y| rows[y * block + u] * basis[v * n + y])
+                    .sum::<f64>(),
+            );
+        }
+    }
+
+    // 1 if the coefficient is above the block's median
+    let mut sorted = coefficients.clone();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = (sorted[sorted.len() / 2 - 1] + sorted[sorted.len() / 2]) / 2.0;
+
+    let hash_bits: Vec<bool> = coefficients.iter().map(|&c| c > median).collect();
+    bits_to_hex(&hash_bits)
+}
+
+/// Pack bits (most significant first) into a hex string
+fn bits_to_hex(bits: &[bool]) -> String {
+    let mut hex = String::with_capacity(bits.len() / 4);
+    for chunk in bits.chunks(4) {
         let nibble = chunk.iter().enumerate().fold(0u8, |acc, (i, &bit)| {
             acc | ((bit as u8) << (3 - i))
         });
         hex.push_str(&format!("{:x}", nibble));
     }
-
-    Some(hex)
+    hex
 }
 
 /// Compute hamming distance between two hex hash strings
@@ -86,45 +226,77 @@ fn hex_to_bytes(hex: &str) -> Option<Vec<u8>> {
         .collect()
 }
 
-/// Cluster photos by perceptual hash similarity
+/// How a photo is matched against an existing cluster
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClusterLinkage {
+    /// Within threshold of the cluster's first photo. Fast, but can chain
+    /// dissimilar photos together through the representative.
+    #[default]
+    SingleRep,
+    /// Within threshold of every photo already in the cluster. Tighter groups,
+    /// at O(n * cluster size) comparisons.
+    Complete,
+}
+
+/// Cluster photos by perceptual hash similarity: a photo joins the first cluster
+/// it matches under `linkage` (within `threshold` bits)
+/// Photos are taken in id order, so the same input always gives the same clusters.
 /// Returns (clusters, photo_to_cluster mapping)
 pub fn cluster_photos(
     photo_hashes: &std::collections::HashMap<String, String>,
+    threshold: u32,
+    linkage: ClusterLinkage,
+) -> (std::collections::HashMap<String, Vec<String>>, std::collections::HashMap<String, String>) {
+    let mut photos: Vec<(&String, &String)> = photo_hashes.iter().collect();
+    photos.sort_unstable_by_key(|(id, _)| *id);
+    cluster_in_order(photos, threshold, linkage)
+}
+
+/// Whether a photo's hash belongs with a cluster's member hashes (first = representative)
+pub fn matches_cluster(hash: &str, members: &[&str], threshold: u32, linkage: ClusterLinkage) -> bool {
+    let within = |other: &&str| hamming_distance(hash, other) <= threshold;
+    match linkage {
+        ClusterLinkage::SingleRep => members.first().is_some_and(within),
+        ClusterLinkage::Complete => !members.is_empty() && members.iter().all(within),
+    }
+}
+
+/// Greedy clustering of (photo_id, hash) pairs in the given order
+fn cluster_in_order(
+    photos: Vec<(&String, &String)>,
+    threshold: u32,
+    linkage: ClusterLinkage,
 ) -> (std::collections::HashMap<String, Vec<String>>, std::collections::HashMap<String, String>) {
     use std::collections::HashMap;
 
     let mut clusters: HashMap<String, Vec<String>> = HashMap::new();
     let mut photo_to_cluster: HashMap<String, String> = HashMap::new();
-    let mut cluster_reps: Vec<(String, String)> = Vec::new(); // (cluster_id, representative_hash)
+    let mut cluster_hashes: Vec<(String, Vec<&str>)> = Vec::new(); // (cluster_id, member hashes, representative first)
 
-    let mut cluster_count = 0;
-
-    for (photo_id, hash) in photo_hashes {
-        if hash.len() != 64 {
+    for (photo_id, hash) in photos {
+        // Hashes of different lengths (dHash vs pHash) never match: hamming_distance returns u32::MAX
+        if hash.is_empty() || hex_to_bytes(hash).is_none() {
             continue;
         }
 
-        let mut assigned = false;
+        let matched = cluster_hashes.iter_mut()
+            .find(|(_, members)| matches_cluster(hash, members, threshold, linkage));
 
-        // Check against existing cluster representatives
-        for (cluster_id, rep_hash) in &cluster_reps {
-            let distance = hamming_distance(hash, rep_hash);
-            if distance <= HAMMING_THRESHOLD {
+        match matched {
+            Some((cluster_id, members)) => {
                 // Add to existing cluster
+                members.push(hash);
                 clusters.get_mut(cluster_id).unwrap().push(photo_id.clone());
                 photo_to_cluster.insert(photo_id.clone(), cluster_id.clone());
-                assigned = true;
-                break;
             }
-        }
-
-        if !assigned {
-            // Create new cluster
-            let cluster_id = format!("cluster_{:04}", cluster_count);
-            clusters.insert(cluster_id.clone(), vec![photo_id.clone()]);
-            cluster_reps.push((cluster_id.clone(), hash.clone()));
-            photo_to_cluster.insert(photo_id.clone(), cluster_id);
-            cluster_count += 1;
+            None => {
+                // Create new cluster
+                let cluster_id = format!("cluster_{:04}", cluster_hashes.len());
+                clusters.insert(cluster_id.clone(), vec![photo_id.clone()]);
+                cluster_hashes.push((cluster_id.clone(), vec![hash]));
+                photo_to_cluster.insert(photo_id.clone(), cluster_id);
+            }
         }
     }
 
@@ -147,4 +319,138 @@ mod tests {
         assert_eq!(hex_to_bytes("ff00"), Some(vec![255, 0]));
         assert_eq!(hex_to_bytes("abc"), None); // Odd length
     }
+
+    /// Smooth low-frequency pattern (values 20..200), optionally brightened and shifted
+    fn sample_image(brightness: f64, shift: u32) -> DynamicImage {
+        let img = image::RgbImage::from_fn(128, 96, |x, y| {
+            let (x, y) = ((x + shift) as f64, y as f64);
+            let v = 110.0 + 50.0 * (x / 13.0).sin() * (y / 9.0).cos() + 40.0 * ((x + y) / 21.0).sin();
+            let v = (v + brightness) as u8;
+            image::Rgb([v, v, v])
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    /// Unrelated image: vertical stripes
+    fn different_image() -> DynamicImage {
+        let img = image::RgbImage::from_fn(128, 96, |x, _| {
+            let v = if (x / 16) % 2 == 0 { 30 } else { 220 };
+            image::Rgb([v, v, v])
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn test_complete_linkage_splits_chains() {
+        // b and c are each 4 bits from a but 8 bits from each other
+        let ids: Vec<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let hashes: Vec<String> = ["0000", "000f", "00f0"].iter().map(|s| s.to_string()).collect();
+        let photos: Vec<(&String, &String)> = ids.iter().zip(&hashes).collect();
+
+        let (single, _) = cluster_in_order(photos.clone(), 4, ClusterLinkage::SingleRep);
+        assert_eq!(single.len(), 1);
+
+        let (complete, photo_to_cluster) = cluster_in_order(photos, 4, ClusterLinkage::Complete);
+        assert_eq!(complete.len(), 2);
+        assert_eq!(photo_to_cluster["a"], photo_to_cluster["b"]);
+        assert_ne!(photo_to_cluster["b"], photo_to_cluster["c"]);
+    }
+
+    #[test]
+    fn test_clustering_is_deterministic() {
+        // A chain where single-rep results depend on which photo founds the cluster
+        let hashes: std::collections::HashMap<String, String> = (0..40)
+            .map(|i| (format!("photo_{:02}", i), format!("{:016x}", (1u64 << (i % 16)) - 1)))
+            .collect();
+
+        let (clusters, photo_to_cluster) = cluster_photos(&hashes, 6, ClusterLinkage::SingleRep);
+        for _ in 0..5 {
+            // Rebuilt maps iterate in a different order each time
+            let shuffled: std::collections::HashMap<String, String> = hashes.clone().into_iter().collect();
+            let (again, again_photo_to_cluster) = cluster_photos(&shuffled, 6, ClusterLinkage::SingleRep);
+
+            let sorted = |m: &std::collections::HashMap<String, String>| {
+                let mut entries: Vec<_> = m.iter().collect();
+                entries.sort();
+                serde_json::to_string(&entries).unwrap()
+            };
+            assert_eq!(sorted(&photo_to_cluster), sorted(&again_photo_to_cluster));
+            assert_eq!(clusters, again);
+        }
+    }
+
+    #[test]
+    fn test_hash_lengths() {
+        let img = sample_image(0.0, 0);
+        assert_eq!(dhash_image(&img).len(), HashAlgorithm::Dhash.hex_len());
+        assert_eq!(phash_image(&img).len(), HashAlgorithm::Phash.hex_len());
+        assert_eq!(ahash_image(&img).len(), HashAlgorithm::Ahash.hex_len());
+    }
+
+    #[test]
+    fn test_dhash_similar_images() {
+        let base = dhash_image(&sample_image(0.0, 0));
+        let similar = dhash_image(&sample_image(25.0, 2));
+        let different = dhash_image(&different_image());
+        assert!(hamming_distance(&base, &similar) < hamming_distance(&base, &different));
+        assert!(hamming_distance(&base, &similar) <= DEFAULT_HAMMING_THRESHOLD * 2);
+    }
+
+    #[test]
+    fn test_phash_similar_images() {
+        let base = phash_image(&sample_image(0.0, 0));
+        let similar = phash_image(&sample_image(25.0, 2));
+        let different = phash_image(&different_image());
+        assert!(hamming_distance(&base, &similar) <= DEFAULT_HAMMING_THRESHOLD);
+        assert!(hamming_distance(&base, &different) > DEFAULT_HAMMING_THRESHOLD);
+    }
+
+    #[test]
+    fn test_phash_ignores_exposure() {
+        assert_eq!(
+            phash_image(&sample_image(0.0, 0)),
+            phash_image(&sample_image(20.0, 0))
+        );
+    }
+
+    /// JPEG with a minimal EXIF block holding only an Orientation tag
+    fn jpeg_with_orientation(jpeg: &[u8], orientation: u8) -> Vec<u8> {
+        let mut tiff = b"II*\0\x08\0\0\0".to_vec(); // little-endian, IFD0 at offset 8
+        tiff.extend_from_slice(&[1, 0]); // one entry
+        tiff.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, orientation, 0, 0, 0]); // SHORT x1
+        tiff.extend_from_slice(&[0, 0, 0, 0]); // no IFD1
+
+        let mut out = jpeg[..2].to_vec(); // SOI
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        out.extend_from_slice(b"Exif\0\0");
+        out.extend_from_slice(&tiff);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    #[test]
+    fn test_exif_orientation_applied_before_hashing() {
+        let dir = std::env::temp_dir().join(format!("photo-tinder-orientation-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut jpeg = Vec::new();
+        sample_image(0.0, 0).write_to(&mut std::io::Cursor::new(&mut jpeg), image::ImageFormat::Jpeg).unwrap();
+        let stored = image::load_from_memory(&jpeg).unwrap();
+
+        // Sideways pixels tagged "rotate 90° clockwise", as a camera held upright writes them
+        let sideways = dir.join("sideways.jpg");
+        std::fs::write(&sideways, jpeg_with_orientation(&jpeg, 6)).unwrap();
+        // The same pixels already turned upright, saved losslessly without EXIF
+        let upright = dir.join("upright.png");
+        stored.rotate90().save(&upright).unwrap();
+
+        for algorithm in [HashAlgorithm::Dhash, HashAlgorithm::Phash, HashAlgorithm::Ahash] {
+            let hash = compute_hash(&sideways, algorithm).unwrap();
+            assert_eq!(Some(hash.clone()), compute_hash(&upright, algorithm));
+            assert_ne!(hash, hash_image(&stored, algorithm));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
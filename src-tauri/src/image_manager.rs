@@ -1,8 +1,19 @@
 //! Image management - scanning, moving, and undo operations
 
-use crate::state::{ImageRecord, SUPPORTED_EXTENSIONS};
+use crate::config::Config;
+use crate::export::{star_ratings, write_xmp_sidecar, xmp_sidecar_path};
+use crate::metadata::capture_timestamp;
+use crate::move_log::log_move;
+use crate::state::{ImageRecord, PersistentState};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
 use std::fs;
+use std::io::{ErrorKind, Read};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
 
 /// Generate a unique ID for an image based on its path
@@ -11,51 +22,316 @@ pub fn generate_image_id(path: &Path) -> String {
     format!("{:x}", hash)[..12].to_string()
 }
 
-/// Scan all source folders and return interleaved image records
-pub fn scan_source_folders(source_folders: &[String]) -> Vec<ImageRecord> {
+/// Bytes read from the start of a file to derive its content id
+const CONTENT_ID_PREFIX_LEN: u64 = 64 * 1024;
+
+/// Generate an ID from a file's content (its first 64 KB plus its size), so it
+/// survives renames and reorganized folders. None if the file can't be read.
+pub fn generate_content_id(path: &Path) -> Option<String> {
+    let file = fs::File::open(path).ok()?;
+    let size = file.metadata().ok()?.len();
+
+    let mut prefix = Vec::new();
+    file.take(CONTENT_ID_PREFIX_LEN).read_to_end(&mut prefix).ok()?;
+
+    let mut context = md5::Context::new();
+    context.consume(&prefix);
+    context.consume(size.to_le_bytes());
+    Some(format!("{:x}", context.compute())[..12].to_string())
+}
+
+/// ID of a source photo: content-based when `content_ids` is set (falling back
+/// to the path for unreadable files), path-based otherwise
+pub fn record_id(path: &Path, content_ids: bool) -> String {
+    if content_ids {
+        if let Some(id) = generate_content_id(path) {
+            return id;
+        }
+    }
+    generate_image_id(path)
+}
+
+/// Check whether a file's extension is in the given lowercase extension list
+pub fn has_supported_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .map(|ext| extensions.contains(&ext.to_string_lossy().to_lowercase()))
+        .unwrap_or(false)
+}
+
+/// Compiled `exclude_patterns`, shared by the source scan, the folder watcher and the browser
+pub struct ExcludeFilter {
+    set: GlobSet,
+}
+
+impl ExcludeFilter {
+    /// Compile the patterns; invalid ones are reported and ignored
+    pub fn new(patterns: &[String]) -> Self {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            match Glob::new(pattern) {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(e) => eprintln!("Warning: Invalid exclude pattern {}: {}", pattern, e),
+            }
+        }
+        let set = builder.build().unwrap_or_else(|e| {
+            eprintln!("Warning: Could not compile exclude patterns: {}", e);
+            GlobSet::empty()
+        });
+        Self { set }
+    }
+
+    /// Whether a file or folder is excluded, by its path relative to the scanned
+    /// folder or by its own name
+    pub fn is_excluded(&self, rel_path: &Path) -> bool {
+        self.set.is_match(rel_path)
+            || rel_path.file_name().is_some_and(|name| self.set.is_match(name))
+    }
+
+    /// Whether a relative path is excluded itself or sits inside an excluded folder
+    pub fn excludes_path(&self, rel_path: &Path) -> bool {
+        rel_path.ancestors()
+            .filter(|p| !p.as_os_str().is_empty())
+            .any(|p| self.is_excluded(p))
+    }
+}
+
+/// Whether a source folder can be scanned right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FolderStatus {
+    Present,
+    /// Gone from a reachable parent, as if deleted or renamed
+    Missing,
+    /// On a volume or share that isn't mounted or isn't answering
+    Unreachable,
+}
+
+/// How long a folder may take to answer before it counts as unreachable
+const FOLDER_ACCESS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Check a folder without hanging on a dead network share. A folder whose parent
+/// is gone too, or is empty (an unmounted mount point), is taken to be unreachable.
+pub fn folder_status(folder: &Path) -> FolderStatus {
+    spawn_probe(folder).recv_timeout(FOLDER_ACCESS_TIMEOUT).unwrap_or(FolderStatus::Unreachable)
+}
+
+/// `folder_status` of every enabled source folder. The probes run side by side, so
+/// dead shares cost one FOLDER_ACCESS_TIMEOUT in all rather than one each.
+pub fn source_folder_statuses(config: &Config) -> HashMap<String, FolderStatus> {
+    let probes: Vec<_> = config.enabled_source_folders().into_iter()
+        .map(|folder| {
+            let probe = spawn_probe(Path::new(&folder));
+            (folder, probe)
+        })
+        .collect();
+
+    let deadline = Instant::now() + FOLDER_ACCESS_TIMEOUT;
+    probes.into_iter()
+        .map(|(folder, probe)| {
+            let status = probe.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .unwrap_or(FolderStatus::Unreachable);
+            (folder, status)
+        })
+        .collect()
+}
+
+/// Probe a folder on its own thread, which may hang on a dead share
+fn spawn_probe(folder: &Path) -> mpsc::Receiver<FolderStatus> {
+    let (tx, rx) = mpsc::channel();
+    let path = folder.to_path_buf();
+    std::thread::spawn(move || {
+        let _ = tx.send(probe_folder(&path));
+    });
+    rx
+}
+
+fn probe_folder(folder: &Path) -> FolderStatus {
+    match fs::read_dir(folder) {
+        Ok(_) => FolderStatus::Present,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            let parent_in_use = folder.parent()
+                .and_then(|parent| fs::read_dir(parent).ok())
+                .is_some_and(|mut entries| entries.next().is_some());
+            if parent_in_use { FolderStatus::Missing } else { FolderStatus::Unreachable }
+        }
+        Err(_) => FolderStatus::Unreachable,
+    }
+}
+
+/// Scan all source folders and return interleaved image records.
+/// Folders that are missing or unreachable are skipped.
+pub fn scan_source_folders(config: &Config) -> Vec<ImageRecord> {
+    scan_probed_source_folders(config, &source_folder_statuses(config))
+}
+
+/// `scan_source_folders` with the folders already probed by `source_folder_statuses`
+pub fn scan_probed_source_folders(config: &Config, statuses: &HashMap<String, FolderStatus>) -> Vec<ImageRecord> {
+    let source_folders = &config.source_folders;
+    let extensions = config.scan_extensions();
+    let by_date = config.order == "date_asc" || config.order == "date_desc";
+    let exclude = ExcludeFilter::new(&config.exclude_patterns);
+    let mut seen = HashSet::new();
     let mut folder_images: Vec<Vec<ImageRecord>> = vec![Vec::new(); source_folders.len()];
 
     for (idx, folder_path) in source_folders.iter().enumerate() {
-        let folder = Path::new(folder_path);
-        if !folder.exists() {
-            eprintln!("Warning: Source folder does not exist: {}", folder_path);
+        if config.disabled_folders.contains(folder_path) {
             continue;
         }
 
-        // Use recursive scan for all folders
-        let walker = WalkDir::new(folder).follow_links(true);
+        let folder = Path::new(folder_path);
+        match statuses.get(folder_path).copied().unwrap_or(FolderStatus::Unreachable) {
+            FolderStatus::Present => {}
+            FolderStatus::Missing => {
+                eprintln!("Warning: Source folder does not exist: {}", folder_path);
+                continue;
+            }
+            FolderStatus::Unreachable => {
+                eprintln!("Warning: Source folder is unreachable, skipping it: {}", folder_path);
+                continue;
+            }
+        }
+
+        // Recursive scan, optionally limited to `scan_depth` levels (1 = top level only)
+        let mut walker = WalkDir::new(folder).follow_links(true);
+        if let Some(depth) = config.scan_depth {
+            walker = walker.max_depth(depth);
+        }
 
-        for entry in walker.into_iter().filter_map(|e| e.ok()) {
+        // Excluded folders are pruned so the walk never descends into them
+        let entries = walker.into_iter().filter_entry(|entry| {
+            entry.depth() == 0
+                || entry.path().strip_prefix(folder).map_or(true, |rel| !exclude.is_excluded(rel))
+        });
+
+        for entry in entries.filter_map(|e| e.ok()) {
             let path = entry.path();
             if !path.is_file() {
                 continue;
             }
 
             // Check extension
-            if let Some(ext) = path.extension() {
-                let ext_lower = ext.to_string_lossy().to_lowercase();
-                if SUPPORTED_EXTENSIONS.contains(&ext_lower.as_str()) {
-                    if let Ok(rel_path) = path.strip_prefix(folder) {
-                        let img_id = generate_image_id(path);
-                        folder_images[idx].push(ImageRecord {
-                            id: img_id,
-                            source_folder: folder_path.clone(),
-                            relative_path: rel_path.to_string_lossy().to_string(),
-                        });
+            if has_supported_extension(path, &extensions) {
+                if let Ok(rel_path) = path.strip_prefix(folder) {
+                    let img_id = record_id(path, config.content_ids);
+                    // Identical copies share a content id; queue only the first
+                    if config.content_ids && !seen.insert(img_id.clone()) {
+                        continue;
                     }
+                    folder_images[idx].push(ImageRecord {
+                        id: img_id,
+                        source_folder: folder_path.clone(),
+                        relative_path: rel_path.to_string_lossy().to_string(),
+                        timestamp: if by_date { capture_timestamp(path) } else { None },
+                        size: entry.metadata().map(|meta| meta.len()).unwrap_or(0),
+                    });
                 }
             }
         }
     }
 
-    // Interleave images from all folders (round-robin)
-    let mut interleaved = Vec::new();
-    let max_len = folder_images.iter().map(|v| v.len()).max().unwrap_or(0);
+    match config.order.as_str() {
+        "date_asc" | "date_desc" | "name" => {
+            let mut all: Vec<ImageRecord> = folder_images.into_iter().flatten().collect();
+            sort_records(&mut all, &config.order);
+            return all;
+        }
+        _ => {}
+    }
+
+    interleave_records(folder_images)
+}
+
+/// Bring stored decisions in line with the configured id scheme. When ids were
+/// recorded under the other scheme (path vs content) they are re-keyed, so toggling
+/// `content_ids` keeps decisions. Returns true if anything in `persistent` changed.
+/// Waits while a folder is disabled, since its photos aren't in `records` to match.
+pub fn sync_record_ids(persistent: &mut PersistentState, records: &[ImageRecord], config: &Config) -> bool {
+    if persistent.content_ids == config.content_ids
+        || config.source_folders.is_empty()
+        || !config.disabled_folders.is_empty()
+    {
+        return false;
+    }
+
+    migrate_record_ids(persistent, records, config.content_ids);
+    persistent.content_ids = config.content_ids;
+    true
+}
+
+/// Re-key every decision to the id scheme selected by `content_ids`. Each decided
+/// photo is found through its moved copy, its original path, or the scanned
+/// records (matched by their id under the old scheme). Returns the number of ids changed.
+fn migrate_record_ids(persistent: &mut PersistentState, records: &[ImageRecord], content_ids: bool) -> usize {
+    let old_ids: HashMap<String, PathBuf> = records.iter()
+        .map(|r| {
+            let path = r.full_path();
+            (record_id(&path, !content_ids), path)
+        })
+        .collect();
+
+    let mut renames: HashMap<String, String> = HashMap::new();
+    for id in persistent.decisions.keys() {
+        let original = persistent.original_paths.get(id).map(PathBuf::from)
+            .or_else(|| old_ids.get(id).cloned());
+
+        let new_id = if content_ids {
+            // Moved copies have the same content; trashed files can't be read
+            let moved = persistent.moved_files.get(id)
+                .filter(|p| !p.starts_with(TRASH_TOKEN_PREFIX))
+                .map(PathBuf::from);
+            moved.into_iter().chain(original).find(|p| p.exists()).and_then(|p| generate_content_id(&p))
+        } else {
+            original.map(|p| generate_image_id(&p))
+        };
+
+        if let Some(new_id) = new_id.filter(|new_id| new_id != id) {
+            renames.insert(id.clone(), new_id);
+        }
+    }
+
+    if renames.is_empty() {
+        return 0;
+    }
+
+    fn rekey<V>(map: HashMap<String, V>, renames: &HashMap<String, String>) -> HashMap<String, V> {
+        map.into_iter()
+            .map(|(id, value)| (renames.get(&id).cloned().unwrap_or(id), value))
+            .collect()
+    }
+    let rename = |id: String| renames.get(&id).cloned().unwrap_or(id);
+
+    persistent.decisions = rekey(std::mem::take(&mut persistent.decisions), &renames);
+    persistent.moved_files = rekey(std::mem::take(&mut persistent.moved_files), &renames);
+    persistent.original_paths = rekey(std::mem::take(&mut persistent.original_paths), &renames);
+    persistent.planned_moves = rekey(std::mem::take(&mut persistent.planned_moves), &renames);
+    persistent.decision_reasons = rekey(std::mem::take(&mut persistent.decision_reasons), &renames);
+    persistent.redo_reasons = rekey(std::mem::take(&mut persistent.redo_reasons), &renames);
+    persistent.decided_at = rekey(std::mem::take(&mut persistent.decided_at), &renames);
+    persistent.skip_counts = rekey(std::mem::take(&mut persistent.skip_counts), &renames);
+    persistent.extra_copies = rekey(std::mem::take(&mut persistent.extra_copies), &renames);
+    persistent.moved_sizes = rekey(std::mem::take(&mut persistent.moved_sizes), &renames);
+    persistent.ranked_removals = rekey(std::mem::take(&mut persistent.ranked_removals), &renames);
+    persistent.copied_files = std::mem::take(&mut persistent.copied_files).into_iter().map(rename).collect();
+    for entry in persistent.history.iter_mut().chain(persistent.redo_stack.iter_mut()) {
+        entry.0 = rename(std::mem::take(&mut entry.0));
+    }
+
+    renames.len()
+}
+
+/// Interleave per-folder records round-robin. Records are moved rather than
+/// cloned, so peak memory stays close to a single copy of the scan.
+pub fn interleave_records(folder_images: Vec<Vec<ImageRecord>>) -> Vec<ImageRecord> {
+    let total = folder_images.iter().map(Vec::len).sum();
+    let mut interleaved = Vec::with_capacity(total);
+    let mut folders: Vec<_> = folder_images.into_iter().map(Vec::into_iter).collect();
 
-    for i in 0..max_len {
-        for folder_imgs in &folder_images {
-            if i < folder_imgs.len() {
-                interleaved.push(folder_imgs[i].clone());
+    while interleaved.len() < total {
+        for folder in &mut folders {
+            if let Some(record) = folder.next() {
+                interleaved.push(record);
             }
         }
     }
@@ -63,6 +339,31 @@ pub fn scan_source_folders(source_folders: &[String]) -> Vec<ImageRecord> {
     interleaved
 }
 
+/// Sort records for the date or name queue orders.
+/// Files without a known date sort last; ties fall back to filename.
+fn sort_records(records: &mut [ImageRecord], order: &str) {
+    let by_name = |a: &ImageRecord, b: &ImageRecord| {
+        a.filename().to_lowercase().cmp(&b.filename().to_lowercase())
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    };
+
+    match order {
+        "date_asc" | "date_desc" => records.sort_by(|a, b| {
+            let ordering = match (a.timestamp, b.timestamp) {
+                (Some(ta), Some(tb)) => {
+                    let cmp = ta.partial_cmp(&tb).unwrap_or(std::cmp::Ordering::Equal);
+                    if order == "date_desc" { cmp.reverse() } else { cmp }
+                }
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            };
+            ordering.then_with(|| by_name(a, b))
+        }),
+        _ => records.sort_by(by_name),
+    }
+}
+
 /// Get destination path, handling filename collisions
 pub fn get_destination_path(filename: &str, destination: &Path) -> PathBuf {
     let mut dest_path = destination.join(filename);
@@ -89,76 +390,403 @@ pub fn get_destination_path(filename: &str, destination: &Path) -> PathBuf {
     dest_path
 }
 
-/// Move image to appropriate destination. Returns new path or None if skip.
+/// Where `move_image` put a photo
+#[derive(Debug, Clone)]
+pub struct MovedImage {
+    pub path: String,              // primary destination (or trash-restore token)
+    pub extra_copies: Vec<String>, // copies in the extra accept destinations
+}
+
+/// Move image to appropriate destination. Returns where it went, or None if skip.
+/// Accepted and favorite photos are also copied into each extra accept destination.
+/// With `dry_run` on nothing is touched and the would-be destination is returned.
 pub fn move_image(
     record: &ImageRecord,
     decision: &str,
-    accepted_folder: &str,
-    rejected_folder: &str,
-) -> Result<Option<String>, String> {
-    if decision == "skipped" {
-        return Ok(None);
+    config: &Config,
+) -> Result<Option<MovedImage>, String> {
+    let destination = match config.destination_for_source(decision, &record.source_folder) {
+        Some(folder) => Path::new(folder),
+        None => return Ok(None),
+    };
+
+    let source_path = record.full_path();
+
+    if !source_path.exists() {
+        return Err(format!("Image not found: {}", source_path.display()));
+    }
+
+    // Copy mode leaves the source untouched
+    let copy_only = config.operation == "copy";
+
+    if config.dry_run {
+        let dest = if decision == "rejected" && config.rejected_action == "trash" && !copy_only {
+            "trash".to_string()
+        } else {
+            let destination = mirrored_destination(destination, record, config);
+            get_destination_path(&destination_filename(record, config), &destination).to_string_lossy().to_string()
+        };
+        return Ok(Some(MovedImage { path: dest, extra_copies: Vec::new() }));
+    }
+
+    // Rejects can go to the system trash instead of the Rejected folder
+    if decision == "rejected" && config.rejected_action == "trash" && !copy_only {
+        let token = trash_file(&source_path)?;
+        log_move("move", &record.id, decision, &source_path.to_string_lossy(), &token);
+        return Ok(Some(MovedImage { path: token, extra_copies: Vec::new() }));
     }
 
-    let destination = if decision == "accepted" {
-        Path::new(accepted_folder)
+    // Extra copies are made first, so a failure leaves nothing half moved
+    let extra_copies = if decision == "accepted" || decision == "favorite" {
+        copy_to_extra_destinations(record, decision, config)?
     } else {
-        Path::new(rejected_folder)
+        Vec::new()
     };
 
-    // Ensure destination exists
-    fs::create_dir_all(destination).map_err(|e| e.to_string())?;
+    let destination = mirrored_destination(destination, record, config);
+    let dest_path = match transfer_file(&source_path, &destination, &destination_filename(record, config), copy_only) {
+        Ok(dest_path) => dest_path,
+        Err(e) => {
+            remove_extra_copies(&record.id, decision, &extra_copies);
+            return Err(e);
+        }
+    };
 
-    let source_path = record.full_path();
+    let dest = dest_path.to_string_lossy().to_string();
+    let action = if copy_only { "copy" } else { "move" };
+    log_move(action, &record.id, decision, &source_path.to_string_lossy(), &dest);
 
-    if !source_path.exists() {
-        return Err(format!("Image not found: {}", source_path.display()));
+    Ok(Some(MovedImage { path: dest, extra_copies }))
+}
+
+/// Bookkeeping after `move_image` for a triage decision: what undo needs to put the
+/// file back, or in a dry run just the planned destination. Shared by the app and
+/// the headless CLI.
+pub fn record_move(
+    record: &ImageRecord,
+    decision: &str,
+    moved: MovedImage,
+    config: &Config,
+    persistent: &mut PersistentState,
+) {
+    let image_id = &record.id;
+    if config.dry_run {
+        persistent.planned_moves.insert(image_id.clone(), moved.path);
+        return;
+    }
+
+    if decision == "accepted" && config.write_xmp {
+        write_accept_sidecar(&moved.path, persistent);
+    }
+    persistent.original_paths.insert(image_id.clone(), record.full_path().to_string_lossy().to_string());
+    persistent.moved_files.insert(image_id.clone(), moved.path);
+    persistent.moved_sizes.insert(image_id.clone(), record.size);
+    if config.operation == "copy" {
+        persistent.copied_files.insert(image_id.clone());
+    }
+    if !moved.extra_copies.is_empty() {
+        persistent.extra_copies.insert(image_id.clone(), moved.extra_copies);
+    }
+}
+
+/// Write the XMP sidecar for a just-accepted photo. Its star rating comes from the
+/// Accepted ranking (0 until the photo has been compared); failures only warn.
+fn write_accept_sidecar(accepted_path: &str, persistent: &PersistentState) {
+    let path = Path::new(accepted_path);
+    let stars = persistent.ranking_for("accepted")
+        .and_then(|ranking| star_ratings(&ranking.ratings).get(&generate_image_id(path)).copied())
+        .unwrap_or(0);
+
+    if let Err(e) = write_xmp_sidecar(path, stars) {
+        eprintln!("Warning: {}", e);
     }
+}
+
+/// Destination folder for a record, mirroring its source subfolders
+/// (accepted/2023/trip/IMG_0001.jpg) when `preserve_structure` is on
+fn mirrored_destination(folder: &Path, record: &ImageRecord, config: &Config) -> PathBuf {
+    match Path::new(&record.relative_path).parent() {
+        Some(subdir) if config.preserve_structure => folder.join(subdir),
+        _ => folder.to_path_buf(),
+    }
+}
+
+/// Name a record is given at its destination: its own filename, with the extension
+/// lowercased when `preserve_extension_case` is off
+fn destination_filename(record: &ImageRecord, config: &Config) -> String {
+    let filename = record.filename();
+    if config.preserve_extension_case {
+        return filename;
+    }
+    match filename.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{}.{}", stem, extension.to_lowercase()),
+        _ => filename,
+    }
+}
+
+/// Copy or move `source` into `destination` under a free name. Returns the new path.
+fn transfer_file(source_path: &Path, destination: &Path, filename: &str, copy_only: bool) -> Result<PathBuf, String> {
+    // Ensure destination exists
+    fs::create_dir_all(destination).map_err(|e| write_error(e, destination))?;
 
-    let dest_path = get_destination_path(&record.filename(), destination);
+    let dest_path = get_destination_path(filename, destination);
 
-    // Move file - try rename first, fall back to copy+delete for cross-filesystem
-    if let Err(rename_err) = fs::rename(&source_path, &dest_path) {
-        // Try copy + delete if rename fails (cross-filesystem)
-        fs::copy(&source_path, &dest_path).map_err(|copy_err| {
+    // Copy, or move file - try rename first, fall back to verified copy+delete for cross-filesystem
+    // A full destination is reported as is rather than buried in the copy/rename detail
+    if copy_only {
+        copy_verified(source_path, &dest_path, |from, to| fs::copy(from, to)).map_err(|copy_err| {
+            if copy_err.starts_with(NO_SPACE_ERROR) {
+                return copy_err;
+            }
+            format!("Failed to copy file: {} ({})", source_path.display(), copy_err)
+        })?;
+    } else if let Err(rename_err) = fs::rename(source_path, &dest_path) {
+        copy_verified(source_path, &dest_path, |from, to| fs::copy(from, to)).map_err(|copy_err| {
+            if copy_err.starts_with(NO_SPACE_ERROR) {
+                return copy_err;
+            }
             format!("Failed to move file: {} (rename: {}, copy: {})",
                 source_path.display(), rename_err, copy_err)
         })?;
-        fs::remove_file(&source_path).map_err(|del_err| {
+        fs::remove_file(source_path).map_err(|del_err| {
             format!("File copied but failed to remove original: {}", del_err)
         })?;
     }
 
-    Ok(Some(dest_path.to_string_lossy().to_string()))
+    Ok(dest_path)
+}
+
+/// Copy a photo into every extra accept destination. If one copy fails, the
+/// copies already made are removed again.
+fn copy_to_extra_destinations(record: &ImageRecord, decision: &str, config: &Config) -> Result<Vec<String>, String> {
+    let source_path = record.full_path();
+    let mut copies = Vec::new();
+
+    for folder in config.extra_accept_destinations.iter().filter(|f| !f.is_empty()) {
+        let destination = mirrored_destination(Path::new(folder), record, config);
+        match transfer_file(&source_path, &destination, &destination_filename(record, config), true) {
+            Ok(dest_path) => {
+                let dest = dest_path.to_string_lossy().to_string();
+                log_move("copy", &record.id, decision, &source_path.to_string_lossy(), &dest);
+                copies.push(dest);
+            }
+            Err(e) => {
+                remove_extra_copies(&record.id, decision, &copies);
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(copies)
+}
+
+/// Delete the extra destination copies of a photo (undo). Failures are only
+/// logged: a leftover copy doesn't affect the photo's own restore.
+pub fn remove_extra_copies(image_id: &str, decision: &str, copies: &[String]) {
+    for copy in copies {
+        if let Err(e) = undo_copy(image_id, decision, copy) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+}
+
+/// Undo a copy-mode decision by deleting the copy; the original never moved
+pub fn undo_copy(image_id: &str, decision: &str, copy_path: &str) -> Result<(), String> {
+    let copy = Path::new(copy_path);
+
+    if copy.exists() {
+        fs::remove_file(copy).map_err(|e| format!("Failed to remove copy {}: {}", copy_path, e))?;
+    } else {
+        eprintln!("Warning: Copy already gone: {}", copy_path);
+    }
+    remove_xmp_sidecar(copy);
+
+    log_move("delete_copy", image_id, decision, copy_path, "");
+
+    Ok(())
+}
+
+/// Prefix marking a `moved_files` entry as a trash-restore token rather than a path
+pub const TRASH_TOKEN_PREFIX: &str = "trash:";
+
+/// Send a file to the system trash. Returns the token to restore it with.
+fn trash_file(path: &Path) -> Result<String, String> {
+    trash::delete(path).map_err(|e| format!("Failed to move {} to trash: {}", path.display(), e))?;
+    Ok(format!("{}{}", TRASH_TOKEN_PREFIX, trashed_item_id(path).unwrap_or_default()))
+}
+
+/// Trash id of the most recently trashed item that came from `path`
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+fn trashed_item_id(path: &Path) -> Option<String> {
+    trash::os_limited::list().ok()?
+        .into_iter()
+        .filter(|item| item.original_path() == path)
+        .max_by_key(|item| item.time_deleted)
+        .map(|item| item.id.to_string_lossy().to_string())
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+fn trashed_item_id(_path: &Path) -> Option<String> {
+    None
+}
+
+/// Restore a trashed file to its original location, by trash id or, failing that, by path
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+fn restore_from_trash(item_id: &str, original: &Path) -> Result<(), String> {
+    let items = trash::os_limited::list().map_err(|e| format!("Failed to read trash: {}", e))?;
+    let item = items.iter()
+        .find(|item| !item_id.is_empty() && item.id.to_string_lossy() == item_id)
+        .or_else(|| {
+            items.iter()
+                .filter(|item| item.original_path() == original)
+                .max_by_key(|item| item.time_deleted)
+        })
+        .cloned()
+        .ok_or_else(|| format!("Trashed file not found: {}", original.display()))?;
+
+    trash::os_limited::restore_all([item])
+        .map_err(|e| format!("Failed to restore {} from trash: {}", original.display(), e))
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+fn restore_from_trash(_item_id: &str, original: &Path) -> Result<(), String> {
+    Err(format!(
+        "Restoring from the trash is not supported on this platform; restore {} manually",
+        original.display()
+    ))
+}
+
+/// Copy `source` to `dest` with `copy`, then compare MD5 digests of both files.
+/// On a mismatch the partial destination is removed, leaving the source untouched.
+fn copy_verified<F>(source: &Path, dest: &Path, copy: F) -> Result<(), String>
+where
+    F: FnOnce(&Path, &Path) -> std::io::Result<u64>,
+{
+    let source_digest = file_digest(source)?;
+
+    copy(source, dest).map_err(|e| {
+        let _ = fs::remove_file(dest); // don't leave a partial copy behind
+        write_error(e, dest.parent().unwrap_or(dest))
+    })?;
+
+    let dest_digest = file_digest(dest);
+    if dest_digest.as_ref() != Ok(&source_digest) {
+        let _ = fs::remove_file(dest);
+        return Err(format!("Copy of {} failed verification", source.display()));
+    }
+
+    Ok(())
+}
+
+/// Start of the error returned when a destination volume has run out of space
+pub const NO_SPACE_ERROR: &str = "Not enough space on destination";
+
+/// Describe a failed write into `dir`, naming a full disk plainly
+fn write_error(e: std::io::Error, dir: &Path) -> String {
+    match e.kind() {
+        ErrorKind::StorageFull | ErrorKind::QuotaExceeded => format!("{} {}", NO_SPACE_ERROR, dir.display()),
+        _ => e.to_string(),
+    }
+}
+
+/// Free bytes on the volume holding `folder`. A folder that doesn't exist yet is
+/// measured at its nearest existing ancestor, where it would be created.
+pub fn available_space(folder: &Path) -> Option<u64> {
+    let existing = folder.ancestors().find(|dir| dir.exists())?;
+    fs2::available_space(existing).ok()
+}
+
+/// MD5 digest of a file's contents, read in chunks
+fn file_digest(path: &Path) -> Result<md5::Digest, String> {
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut context = md5::Context::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        context.consume(&buffer[..read]);
+    }
+
+    Ok(context.compute())
 }
 
-/// Move file back to original location (undo)
-pub fn undo_move(moved_path: &str, original_path: &str) -> Result<(), String> {
+/// Move file back to original location (undo). If another file has taken the
+/// original path since, the file is restored next to it under a suffixed name
+/// (IMG_0001_1.jpg) instead of overwriting it. Returns the path restored to.
+pub fn undo_move(
+    image_id: &str,
+    decision: &str,
+    moved_path: &str,
+    original_path: &str,
+) -> Result<String, String> {
     let moved = Path::new(moved_path);
     let original = Path::new(original_path);
 
+    if let Some(item_id) = moved_path.strip_prefix(TRASH_TOKEN_PREFIX) {
+        restore_from_trash(item_id, original)?;
+        log_move("undo", image_id, decision, moved_path, original_path);
+        return Ok(original_path.to_string());
+    }
+
     if !moved.exists() {
         return Err(format!("Moved file not found: {}", moved_path));
     }
 
-    // Ensure parent directory exists
-    if let Some(parent) = original.parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    let restored = restore_file(moved, original)?.to_string_lossy().to_string();
+    remove_xmp_sidecar(moved);
+    log_move("undo", image_id, decision, moved_path, &restored);
+
+    Ok(restored)
+}
+
+/// Remove the XMP sidecar written beside an accepted photo, once the photo itself
+/// has been taken back out of the destination
+fn remove_xmp_sidecar(photo_path: &Path) {
+    let sidecar = xmp_sidecar_path(photo_path);
+    if sidecar.exists() {
+        if let Err(e) = fs::remove_file(&sidecar) {
+            eprintln!("Warning: Could not remove {}: {}", sidecar.display(), e);
+        }
     }
+}
+
+/// Move `moved` back to `original`, or to a free suffixed name beside it when
+/// `original` is taken. Returns the path the file ended up at.
+fn restore_file(moved: &Path, original: &Path) -> Result<PathBuf, String> {
+    // Ensure parent directory exists
+    let parent = original.parent().unwrap_or(Path::new(""));
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+
+    let filename = original.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let restore_path = get_destination_path(&filename, parent);
 
-    // Move back - try rename first, fall back to copy+delete for cross-filesystem
-    if let Err(rename_err) = fs::rename(moved, original) {
-        // Try copy + delete if rename fails
-        fs::copy(moved, original).map_err(|copy_err| {
+    // Move back - try rename first, fall back to verified copy+delete for cross-filesystem
+    if let Err(rename_err) = fs::rename(moved, &restore_path) {
+        copy_verified(moved, &restore_path, |from, to| fs::copy(from, to)).map_err(|copy_err| {
             format!("Failed to restore file: {} (rename: {}, copy: {})",
-                moved_path, rename_err, copy_err)
+                moved.display(), rename_err, copy_err)
         })?;
         fs::remove_file(moved).map_err(|del_err| {
             format!("File restored but failed to remove from destination: {}", del_err)
         })?;
     }
 
-    Ok(())
+    Ok(restore_path)
 }
 
 /// Build list of indices for images not yet decided
@@ -170,7 +798,8 @@ pub fn build_pending_indices(
 
     for (i, record) in image_records.iter().enumerate() {
         let decision = decisions.get(&record.id);
-        // Include if pending (no decision yet) OR skipped (recycle back into queue)
+        // Include if pending (no decision yet) OR skipped (recycle back into queue).
+        // Deferred photos hit the skip limit and stay out like decided ones.
         if decision.is_none()
             || decision == Some(&"pending".to_string())
             || decision == Some(&"skipped".to_string())
@@ -205,7 +834,10 @@ pub fn get_current_record<'a>(
 }
 
 /// Scan accepted folder for ranking mode
-pub fn scan_accepted_photos(accepted_folder: &str) -> std::collections::HashMap<String, PathBuf> {
+pub fn scan_accepted_photos(
+    accepted_folder: &str,
+    extensions: &[String],
+) -> std::collections::HashMap<String, PathBuf> {
     let mut photos = std::collections::HashMap::new();
     let folder = Path::new(accepted_folder);
 
@@ -219,20 +851,36 @@ pub fn scan_accepted_photos(accepted_folder: &str) -> std::collections::HashMap<
             continue;
         }
 
-        if let Some(ext) = path.extension() {
-            let ext_lower = ext.to_string_lossy().to_lowercase();
-            if SUPPORTED_EXTENSIONS.contains(&ext_lower.as_str()) {
-                let photo_id = generate_image_id(path);
-                photos.insert(photo_id, path.to_path_buf());
-            }
+        if has_supported_extension(path, extensions) {
+            let photo_id = generate_image_id(path);
+            photos.insert(photo_id, path.to_path_buf());
         }
     }
 
     photos
 }
 
+/// Supported files anywhere under `folders`, grouped by file name
+pub fn index_files_by_name(folders: &[&str], extensions: &[String]) -> HashMap<OsString, Vec<PathBuf>> {
+    let mut index: HashMap<OsString, Vec<PathBuf>> = HashMap::new();
+
+    for folder in folders {
+        for entry in WalkDir::new(folder).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if !entry.file_type().is_file() || !has_supported_extension(path, extensions) {
+                continue;
+            }
+            if let Some(name) = path.file_name() {
+                index.entry(name.to_os_string()).or_default().push(path.to_path_buf());
+            }
+        }
+    }
+
+    index
+}
+
 /// Browse a directory and return its contents
-pub fn browse_directory(path: &str) -> Result<BrowseResult, String> {
+pub fn browse_directory(path: &str, exclude: &ExcludeFilter) -> Result<BrowseResult, String> {
     let dir_path = Path::new(path);
 
     if !dir_path.exists() {
@@ -252,8 +900,8 @@ pub fn browse_directory(path: &str) -> Result<BrowseResult, String> {
     for entry in entries.filter_map(|e| e.ok()) {
         let name = entry.file_name().to_string_lossy().to_string();
 
-        // Skip hidden files
-        if name.starts_with('.') {
+        // Skip excluded entries (hidden files by default)
+        if exclude.is_excluded(Path::new(&name)) {
             continue;
         }
 
@@ -296,3 +944,344 @@ pub struct BrowseItem {
     pub path: String,
     pub is_dir: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("photo-tinder-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_folder_status_tells_missing_from_unreachable() {
+        let dir = temp_dir("folder-status");
+        fs::create_dir_all(dir.join("photos")).unwrap();
+        fs::create_dir_all(dir.join("mnt/nas")).unwrap();
+
+        assert_eq!(folder_status(&dir.join("photos")), FolderStatus::Present);
+        // Deleted from a folder that still has other contents
+        assert_eq!(folder_status(&dir.join("shoot")), FolderStatus::Missing);
+        // Under an empty mount point, or a parent that is gone too
+        assert_eq!(folder_status(&dir.join("mnt/nas/photos")), FolderStatus::Unreachable);
+        assert_eq!(folder_status(&dir.join("share/photos")), FolderStatus::Unreachable);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_copy_verified_rejects_short_write() {
+        let dir = temp_dir("short-write");
+        let source = dir.join("photo.jpg");
+        let dest = dir.join("copy.jpg");
+        fs::write(&source, vec![7u8; 10_000]).unwrap();
+
+        // Simulate a truncated copy (disk full, interrupted)
+        let result = copy_verified(&source, &dest, |from, to| {
+            let bytes = fs::read(from)?;
+            fs::write(to, &bytes[..bytes.len() / 2])?;
+            Ok((bytes.len() / 2) as u64)
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&source).unwrap().len(), 10_000);
+        assert!(!dest.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_copy_verified_reports_full_disk() {
+        let dir = temp_dir("disk-full");
+        let source = dir.join("photo.jpg");
+        let dest = dir.join("copy.jpg");
+        fs::write(&source, vec![7u8; 10_000]).unwrap();
+
+        // Half the file lands before the volume fills up
+        let result = copy_verified(&source, &dest, |from, to| {
+            let bytes = fs::read(from)?;
+            fs::write(to, &bytes[..bytes.len() / 2])?;
+            Err(std::io::Error::from(ErrorKind::StorageFull))
+        });
+
+        assert!(result.unwrap_err().starts_with(NO_SPACE_ERROR));
+        assert!(!dest.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_copy_verified_accepts_full_copy() {
+        let dir = temp_dir("full-copy");
+        let source = dir.join("photo.jpg");
+        let dest = dir.join("copy.jpg");
+        fs::write(&source, b"not really a jpeg").unwrap();
+
+        copy_verified(&source, &dest, |from, to| fs::copy(from, to)).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"not really a jpeg");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_prunes_excluded_folders() {
+        let dir = temp_dir("exclude");
+        fs::create_dir_all(dir.join("@eaDir")).unwrap();
+        fs::create_dir_all(dir.join(".thumbnails")).unwrap();
+        fs::create_dir_all(dir.join("trip/exports")).unwrap();
+        for file in ["keep.jpg", "@eaDir/thumb.jpg", ".thumbnails/t.jpg", "trip/exports/e.jpg", "trip/b.jpg"] {
+            fs::write(dir.join(file), b"x").unwrap();
+        }
+
+        let config = Config {
+            source_folders: vec![dir.to_string_lossy().to_string()],
+            exclude_patterns: vec![".*".to_string(), "@eaDir".to_string(), "*/exports".to_string()],
+            order: "name".to_string(),
+            ..Config::default()
+        };
+        let mut found: Vec<String> = scan_source_folders(&config).into_iter().map(|r| r.relative_path).collect();
+        found.sort();
+        assert_eq!(found, vec!["keep.jpg".to_string(), format!("trip{}b.jpg", std::path::MAIN_SEPARATOR)]);
+
+        let exclude = ExcludeFilter::new(&config.exclude_patterns);
+        assert!(exclude.excludes_path(Path::new("@eaDir/thumb.jpg")));
+        assert!(!exclude.excludes_path(Path::new("trip/b.jpg")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_content_ids_keep_decisions_across_renames() {
+        let dir = temp_dir("content-ids");
+        fs::write(dir.join("a.jpg"), b"first photo").unwrap();
+        fs::write(dir.join("b.jpg"), b"second photo").unwrap();
+
+        let mut config = Config {
+            source_folders: vec![dir.to_string_lossy().to_string()],
+            order: "name".to_string(),
+            ..Config::default()
+        };
+        let mut persistent = PersistentState::default();
+        let path_id = generate_image_id(&dir.join("a.jpg"));
+        persistent.record_decision(&path_id, "accepted", None, 0.0);
+
+        // Switching schemes re-keys the stored decision to the content id
+        config.content_ids = true;
+        let records = scan_source_folders(&config);
+        assert!(sync_record_ids(&mut persistent, &records, &config));
+        let content_id = generate_content_id(&dir.join("a.jpg")).unwrap();
+        assert_eq!(persistent.decisions.get(&content_id).map(String::as_str), Some("accepted"));
+        assert_eq!(persistent.history.last().map(|h| h.0.as_str()), Some(content_id.as_str()));
+
+        // A renamed file keeps its decision
+        fs::rename(dir.join("a.jpg"), dir.join("renamed.jpg")).unwrap();
+        let records = scan_source_folders(&config);
+        let pending = build_pending_indices(&records, &persistent.decisions);
+        let pending_paths: Vec<&str> = pending.iter().map(|&i| records[i].relative_path.as_str()).collect();
+        assert_eq!(pending_paths, vec!["b.jpg"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_content_ids_keep_dry_run_plans() {
+        let dir = temp_dir("content-ids-dry-run");
+        fs::write(dir.join("a.jpg"), b"planned photo").unwrap();
+        let planned = dir.join("accepted/a.jpg").to_string_lossy().to_string();
+
+        let mut config = Config {
+            source_folders: vec![dir.to_string_lossy().to_string()],
+            dry_run: true,
+            ..Config::default()
+        };
+        let mut persistent = PersistentState::default();
+        let path_id = generate_image_id(&dir.join("a.jpg"));
+        persistent.record_decision(&path_id, "accepted", None, 0.0);
+        persistent.planned_moves.insert(path_id.clone(), planned.clone());
+
+        // The plan follows its decision to the new id, so commit_dry_run still finds it
+        config.content_ids = true;
+        let records = scan_source_folders(&config);
+        assert!(sync_record_ids(&mut persistent, &records, &config));
+        let content_id = generate_content_id(&dir.join("a.jpg")).unwrap();
+        assert_eq!(persistent.planned_moves.get(&content_id), Some(&planned));
+        assert!(!persistent.planned_moves.contains_key(&path_id));
+        assert!(persistent.decisions.contains_key(&content_id));
+
+        // And back again
+        config.content_ids = false;
+        assert!(sync_record_ids(&mut persistent, &scan_source_folders(&config), &config));
+        assert_eq!(persistent.planned_moves.get(&path_id), Some(&planned));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extra_destination_failure_rolls_back_earlier_copies() {
+        let dir = temp_dir("extra-destinations");
+        let source = dir.join("source");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("a.jpg"), b"photo").unwrap();
+        let folder = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        let mut config = Config {
+            source_folders: vec![folder("source")],
+            accepted_folder: folder("accepted"),
+            rejected_folder: folder("rejected"),
+            extra_accept_destinations: vec![folder("backup1"), folder("backup2")],
+            ..Config::default()
+        };
+        let record = scan_source_folders(&config).remove(0);
+
+        // Every extra destination gets a copy, then the photo moves
+        let moved = move_image(&record, "accepted", &config).unwrap().unwrap();
+        assert_eq!(moved.extra_copies, vec![
+            dir.join("backup1/a.jpg").to_string_lossy().to_string(),
+            dir.join("backup2/a.jpg").to_string_lossy().to_string(),
+        ]);
+        assert_eq!(fs::read(&moved.path).unwrap(), b"photo");
+        assert_eq!(fs::read(dir.join("backup2/a.jpg")).unwrap(), b"photo");
+        assert!(!record.full_path().exists());
+
+        // A third destination that can't be written to (a file where the folder should be)
+        fs::rename(&moved.path, record.full_path()).unwrap();
+        for copy in &moved.extra_copies {
+            fs::remove_file(copy).unwrap();
+        }
+        fs::write(dir.join("blocked"), b"not a folder").unwrap();
+        config.extra_accept_destinations.push(folder("blocked"));
+
+        assert!(move_image(&record, "accepted", &config).is_err());
+        assert!(!dir.join("backup1/a.jpg").exists());
+        assert!(!dir.join("backup2/a.jpg").exists());
+        assert!(!dir.join("accepted/a.jpg").exists());
+        assert_eq!(fs::read(record.full_path()).unwrap(), b"photo");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_restore_file_does_not_overwrite_new_file_at_original_path() {
+        let dir = temp_dir("undo-collision");
+        let accepted = dir.join("accepted");
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::create_dir_all(dir.join("b")).unwrap();
+        fs::create_dir_all(&accepted).unwrap();
+        let original_a = dir.join("a/IMG_0001.jpg");
+        let original_b = dir.join("b/IMG_0001.jpg");
+        fs::write(&original_a, b"from a").unwrap();
+        fs::write(&original_b, b"from b").unwrap();
+
+        // Both land in the flat Accepted folder: the second is renamed on the way in
+        let moved_a = get_destination_path("IMG_0001.jpg", &accepted);
+        fs::rename(&original_a, &moved_a).unwrap();
+        let moved_b = get_destination_path("IMG_0001.jpg", &accepted);
+        fs::rename(&original_b, &moved_b).unwrap();
+        assert_eq!(moved_b, accepted.join("IMG_0001_1.jpg"));
+
+        // A new file takes a's original slot before the undo
+        fs::write(&original_a, b"newcomer").unwrap();
+
+        let restored_a = restore_file(&moved_a, &original_a).unwrap();
+        assert_eq!(restored_a, dir.join("a/IMG_0001_1.jpg"));
+        assert_eq!(fs::read(&original_a).unwrap(), b"newcomer");
+        assert_eq!(fs::read(&restored_a).unwrap(), b"from a");
+
+        // b's slot is still free, so it goes back exactly where it came from
+        let restored_b = restore_file(&moved_b, &original_b).unwrap();
+        assert_eq!(restored_b, original_b);
+        assert_eq!(fs::read(&restored_b).unwrap(), b"from b");
+        assert!(!moved_a.exists() && !moved_b.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extension_case_round_trips_through_scan_move_and_undo() {
+        let dir = temp_dir("extension-case");
+        let source = dir.join("source");
+        let accepted = dir.join("accepted");
+        fs::create_dir_all(&source).unwrap();
+        for file in ["a.JPG", "b.jpg", "c.Cr2", "notes.TXT"] {
+            fs::write(source.join(file), file.as_bytes()).unwrap();
+        }
+
+        // Scanning matches extensions whatever their case
+        let mut config = Config {
+            source_folders: vec![source.to_string_lossy().to_string()],
+            order: "name".to_string(),
+            ..Config::default()
+        };
+        let records = scan_source_folders(&config);
+        let found: Vec<&str> = records.iter().map(|r| r.relative_path.as_str()).collect();
+        assert_eq!(found, vec!["a.JPG", "b.jpg", "c.Cr2"]);
+
+        // Destination names keep the original case unless told otherwise
+        let names = |config: &Config| -> Vec<String> {
+            records.iter().map(|r| destination_filename(r, config)).collect()
+        };
+        assert_eq!(names(&config), vec!["a.JPG", "b.jpg", "c.Cr2"]);
+        config.preserve_extension_case = false;
+        assert_eq!(names(&config), vec!["a.jpg", "b.jpg", "c.cr2"]);
+
+        // Undo puts each file back under its original, mixed-case name
+        for record in &records {
+            let original = record.full_path();
+            let moved = transfer_file(&original, &accepted, &destination_filename(record, &config), false).unwrap();
+            assert_eq!(moved, accepted.join(destination_filename(record, &config)));
+            assert!(!original.exists());
+
+            assert_eq!(restore_file(&moved, &original).unwrap(), original);
+            assert!(!moved.exists());
+            assert_eq!(fs::read(&original).unwrap(), record.filename().as_bytes());
+        }
+
+        // A lowercased name that is already taken gets a counter like any collision
+        fs::create_dir_all(&accepted).unwrap();
+        fs::write(accepted.join("a.jpg"), b"x").unwrap();
+        assert_eq!(get_destination_path(&destination_filename(&records[0], &config), &accepted), accepted.join("a_1.jpg"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_disabled_folder_is_skipped_but_keeps_decisions() {
+        let dir = temp_dir("disabled-folder");
+        let (first, second) = (dir.join("first"), dir.join("second"));
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        fs::write(first.join("a.jpg"), b"a").unwrap();
+        fs::write(second.join("b.jpg"), b"b").unwrap();
+
+        let mut config = Config {
+            source_folders: vec![first.to_string_lossy().to_string(), second.to_string_lossy().to_string()],
+            order: "name".to_string(),
+            ..Config::default()
+        };
+        let mut persistent = PersistentState::default();
+        let b_id = generate_image_id(&second.join("b.jpg"));
+        persistent.record_decision(&b_id, "accepted", None, 0.0);
+
+        config.disabled_folders = vec![config.source_folders[1].clone()];
+        let records = scan_source_folders(&config);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].relative_path, "a.jpg");
+        assert_eq!(config.enabled_source_folders(), vec![config.source_folders[0].clone()]);
+
+        // Ids aren't migrated while the disabled folder's photos can't be matched
+        config.content_ids = true;
+        assert!(!sync_record_ids(&mut persistent, &records, &config));
+        config.content_ids = false;
+
+        // Enabling it again brings its photo back already decided
+        config.disabled_folders.clear();
+        let records = scan_source_folders(&config);
+        assert_eq!(records.len(), 2);
+        assert_eq!(build_pending_indices(&records, &persistent.decisions).len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,13 +1,22 @@
 //! Photo Tinder Desktop - Image Triage and Ranking Application
 
+pub mod cli;
 pub mod commands;
 pub mod config;
+pub mod decode;
+pub mod export;
 pub mod hashing;
 pub mod image_manager;
+pub mod metadata;
+pub mod move_log;
 pub mod ranking;
+pub mod sharpness;
 pub mod state;
+pub mod thumbnails;
+pub mod watcher;
 
 use state::AppState;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -15,37 +24,114 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .manage(AppState::new())
+        .setup(|app| {
+            // Writes state marked by AppState::save_later
+            let handle = app.handle().clone();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(state::SAVE_DEBOUNCE);
+                if let Err(e) = handle.state::<AppState>().flush_state() {
+                    eprintln!("Warning: Could not save state: {}", e);
+                }
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Config
             commands::get_config,
             commands::save_config,
             commands::is_config_valid,
+            commands::get_state_recovery,
+            commands::acknowledge_state_recovery,
+            commands::get_supported_extensions,
+            commands::set_extension_filter,
+            commands::set_scan_depth,
+            commands::export_config,
+            commands::import_config,
+            commands::list_profiles,
+            commands::create_profile,
+            commands::switch_profile,
             // Triage
             commands::initialize_app,
             commands::get_current_image,
+            commands::set_current_index,
+            commands::seek,
             commands::swipe,
+            commands::swipe_batch,
             commands::undo,
+            commands::undo_many,
+            commands::redo,
+            commands::get_dry_run_plan,
+            commands::commit_dry_run,
+            commands::reset_triage,
+            commands::check_destination_space,
+            commands::get_reason_breakdown,
+            commands::find_duplicates,
+            commands::get_similar_to_current,
+            commands::precompute_triage_hashes,
             commands::get_preload_list,
+            commands::estimate_triage_eta,
+            commands::get_triage_timeline,
+            commands::repair_move_records,
+            commands::relocate_moved_file,
+            commands::verify_moved_files,
             // Mode
             commands::get_mode,
             commands::set_mode,
             // Ranking
             commands::get_ranking_stats,
             commands::init_ranking,
+            commands::set_cluster_threshold,
+            commands::reset_ranking,
+            commands::set_pair_strategy,
+            commands::get_clusters,
+            commands::get_cluster_distances,
+            commands::merge_clusters,
+            commands::split_photo_from_cluster,
+            commands::rehash_photo,
+            commands::decide_cluster,
+            commands::skip_to_global,
+            commands::start_tournament,
+            commands::end_tournament,
             commands::get_pair,
             commands::compare,
+            commands::get_triple,
+            commands::compare_triple,
             commands::undo_ranking,
             commands::get_leaderboard,
+            commands::get_uncompared_photos,
+            commands::get_photo_detail,
+            commands::get_head_to_head,
+            commands::export_leaderboard,
+            commands::export_leaderboard_html,
+            commands::export_ratings,
+            commands::apply_ratings_to_exif,
+            commands::import_ratings,
+            commands::top_k_confidence,
             // Folders
             commands::get_folders,
             commands::add_source_folder,
             commands::remove_source_folder,
+            commands::restore_last_removed_folder,
+            commands::set_folder_enabled,
             commands::set_destination_folder,
+            commands::set_destination_override,
             commands::browse,
+            commands::add_quick_access,
+            commands::remove_quick_access,
             commands::get_home_dir,
+            commands::reveal_in_file_manager,
             // Photo browser
             commands::get_photos_by_status,
+            commands::find_large_files,
+            commands::get_thumbnail,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Err(e) = app.state::<AppState>().flush_state() {
+                    eprintln!("Warning: Could not save state on exit: {}", e);
+                }
+            }
+        });
 }
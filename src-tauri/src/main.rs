@@ -5,5 +5,10 @@
 )]
 
 fn main() {
+    // `--headless <command>` runs a CLI command instead of the GUI
+    if std::env::args().any(|arg| arg == "--headless") {
+        std::process::exit(photo_tinder::cli::run());
+    }
+
     photo_tinder::run()
 }
@@ -0,0 +1,426 @@
+//! Photo metadata - EXIF fields read without decoding pixel data
+
+use crate::state::{HEIF_EXTENSIONS, RAW_EXTENSIONS};
+use exif::{Context, In, Tag, Value};
+use img_parts::jpeg::{markers, Jpeg, JpegSegment};
+use img_parts::Bytes;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+/// EXIF "Rating" (IFD0, 0-5 stars) as read by Explorer, Finder and most photo tools
+const RATING_TAG: Tag = Tag(Context::Tiff, 0x4746);
+
+/// EXIF "RatingPercent" (IFD0), kept in step with Rating the way Windows writes it
+const RATING_PERCENT_TAG: Tag = Tag(Context::Tiff, 0x4749);
+const RATING_PERCENTS: [u16; 6] = [0, 1, 25, 50, 75, 99];
+
+/// The EXIF fields shown while triaging
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExifSummary {
+    pub captured_at: Option<String>, // ISO 8601 local time, e.g. "2023-06-01T14:03:22"
+    pub camera_model: Option<String>,
+}
+
+/// Read the EXIF block of an image. Works for JPEG, TIFF-based RAWs, HEIF, PNG and WebP
+/// without decoding the image itself. Missing or unreadable EXIF yields empty fields.
+pub fn read_exif_summary(image_path: &Path) -> ExifSummary {
+    let Some(exif) = read_exif(image_path) else {
+        return ExifSummary::default();
+    };
+
+    let captured_at = exif_datetime(&exif).map(|dt| {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second
+        )
+    });
+
+    let camera_model = ascii_field(&exif, Tag::Model)
+        .map(|bytes| String::from_utf8_lossy(bytes).trim().to_string())
+        .filter(|model| !model.is_empty());
+
+    ExifSummary {
+        captured_at,
+        camera_model,
+    }
+}
+
+/// Capture time as seconds since the epoch, for ordering. Uses the EXIF date when
+/// present (camera local time, treated as UTC) and falls back to the file mtime.
+pub fn capture_timestamp(image_path: &Path) -> Option<f64> {
+    if let Some(dt) = read_exif(image_path).as_ref().and_then(exif_datetime) {
+        let days = days_from_civil(dt.year as i64, dt.month as i64, dt.day as i64);
+        let secs = days * 86400 + dt.hour as i64 * 3600 + dt.minute as i64 * 60 + dt.second as i64;
+        return Some(secs as f64);
+    }
+
+    let modified = std::fs::metadata(image_path).and_then(|m| m.modified()).ok()?;
+    modified.duration_since(std::time::UNIX_EPOCH).ok().map(|d| d.as_secs_f64())
+}
+
+/// EXIF orientation (1-8: how to turn the stored pixels upright), if the file has one
+pub fn exif_orientation(image_path: &Path) -> Option<u8> {
+    let exif = read_exif(image_path)?;
+    let value = exif.get_field(Tag::Orientation, In::PRIMARY)?.value.get_uint(0)?;
+    u8::try_from(value).ok()
+}
+
+/// Width and height of an image without decoding it: read from the file header for
+/// formats the `image` crate handles, and from EXIF for RAW and HEIF files (whose
+/// headers describe a preview, if anything). Reported as displayed, so an EXIF
+/// orientation that turns the photo sideways swaps the two. None if neither works.
+pub fn image_dimensions(image_path: &Path) -> Option<(u32, u32)> {
+    let ext = image_path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let exif = read_exif(image_path);
+
+    let header = if RAW_EXTENSIONS.contains(&ext.as_str()) || HEIF_EXTENSIONS.contains(&ext.as_str()) {
+        None
+    } else {
+        image::image_dimensions(image_path).ok()
+    };
+    let (width, height) = header.or_else(|| exif.as_ref().and_then(exif_dimensions))?;
+
+    let orientation = exif.as_ref()
+        .and_then(|exif| exif.get_field(Tag::Orientation, In::PRIMARY)?.value.get_uint(0));
+    match orientation {
+        Some(5..=8) => Some((height, width)),
+        _ => Some((width, height)),
+    }
+}
+
+/// Full image size from EXIF, preferring the Exif IFD's pixel dimensions over
+/// IFD0's, which in many RAWs describe an embedded preview
+fn exif_dimensions(exif: &exif::Exif) -> Option<(u32, u32)> {
+    [(Tag::PixelXDimension, Tag::PixelYDimension), (Tag::ImageWidth, Tag::ImageLength)]
+        .iter()
+        .find_map(|&(width_tag, height_tag)| {
+            let width = exif.get_field(width_tag, In::PRIMARY)?.value.get_uint(0)?;
+            let height = exif.get_field(height_tag, In::PRIMARY)?.value.get_uint(0)?;
+            (width > 0 && height > 0).then_some((width, height))
+        })
+}
+
+/// When the photo was taken, preferring the original capture tag
+fn exif_datetime(exif: &exif::Exif) -> Option<exif::DateTime> {
+    [Tag::DateTimeOriginal, Tag::DateTimeDigitized, Tag::DateTime]
+        .iter()
+        .find_map(|tag| exif::DateTime::from_ascii(ascii_field(exif, *tag)?).ok())
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Parse the EXIF container of a file, if it has one
+fn read_exif(image_path: &Path) -> Option<exif::Exif> {
+    let file = File::open(image_path).ok()?;
+    let mut reader = BufReader::new(file);
+    exif::Reader::new().read_from_container(&mut reader).ok()
+}
+
+/// First ASCII value of a primary-image tag
+fn ascii_field(exif: &exif::Exif, tag: Tag) -> Option<&[u8]> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => values.first().map(|v| v.as_slice()),
+        _ => None,
+    }
+}
+
+/// Set the EXIF star rating (0-5) of a JPEG file. Existing EXIF fields and the
+/// embedded thumbnail are carried over, and the file is replaced in one rename so
+/// a failure never leaves it half-written.
+pub fn write_exif_rating(image_path: &Path, stars: u8) -> Result<(), String> {
+    let bytes = fs::read(image_path).map_err(|e| format!("Could not read {}: {}", image_path.display(), e))?;
+    let updated = set_jpeg_exif_rating(&bytes, stars)
+        .map_err(|e| format!("{}: {}", image_path.display(), e))?;
+
+    let temp_path = image_path.with_extension("rating-tmp");
+    fs::write(&temp_path, updated)
+        .and_then(|_| fs::rename(&temp_path, image_path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            format!("Could not write {}: {}", image_path.display(), e)
+        })
+}
+
+/// Return a copy of the JPEG with Rating/RatingPercent set in its Exif APP1 segment.
+/// Only that segment changes (one is added after the JFIF header if there is none);
+/// every other segment and the image data are copied through untouched.
+fn set_jpeg_exif_rating(jpeg: &[u8], stars: u8) -> Result<Vec<u8>, String> {
+    let mut image = Jpeg::from_bytes(Bytes::copy_from_slice(jpeg)).map_err(|e| format!("not a readable JPEG: {}", e))?;
+
+    let stars = stars.min(5);
+    let tags = [
+        (RATING_TAG.number(), stars as u16),
+        (RATING_PERCENT_TAG.number(), RATING_PERCENTS[stars as usize]),
+    ];
+
+    let segments = image.segments_mut();
+    let existing = segments.iter()
+        .position(|segment| segment.marker() == markers::APP1 && segment.contents().starts_with(EXIF_PREFIX));
+    let tiff = match existing {
+        Some(i) => set_tiff_shorts(&segments[i].contents()[EXIF_PREFIX.len()..], &tags)?,
+        None => set_tiff_shorts(&EMPTY_TIFF, &tags)?,
+    };
+
+    let mut contents = EXIF_PREFIX.to_vec();
+    contents.extend_from_slice(&tiff);
+    if contents.len() + 2 > u16::MAX as usize {
+        return Err("EXIF block too large to rewrite".to_string());
+    }
+    let segment = JpegSegment::new_with_contents(markers::APP1, Bytes::from(contents));
+
+    match existing {
+        Some(i) => segments[i] = segment,
+        None => {
+            let after_jfif = segments.iter().take_while(|segment| segment.marker() == markers::APP0).count();
+            segments.insert(after_jfif, segment);
+        }
+    }
+    Ok(image.encoder().bytes().to_vec())
+}
+
+/// APP1 payload prefix that marks an Exif segment
+const EXIF_PREFIX: &[u8] = b"Exif\0\0";
+
+/// Big-endian TIFF header followed by an empty IFD0, for JPEGs without EXIF
+const EMPTY_TIFF: [u8; 14] = [b'M', b'M', 0, 42, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0];
+
+/// TIFF field type of a 16-bit unsigned value
+const TIFF_SHORT: u16 = 3;
+
+/// Set single SHORT values in IFD0 of a TIFF block without moving any existing
+/// bytes, so offsets into the block (Exif and GPS IFDs, thumbnails, MakerNotes that
+/// address their own data) stay valid. Tags already stored as one SHORT are patched
+/// in place; otherwise IFD0 is copied to the end of the block with the tags added,
+/// and the header is pointed at the copy. The old IFD0 is left as unused bytes.
+fn set_tiff_shorts(tiff: &[u8], tags: &[(u16, u16)]) -> Result<Vec<u8>, String> {
+    const MALFORMED: &str = "malformed EXIF block";
+
+    let little_endian = match tiff.get(..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return Err(MALFORMED.to_string()),
+    };
+    let u16_at = |buf: &[u8], at: usize| -> Result<u16, String> {
+        let bytes: [u8; 2] = buf.get(at..at + 2).and_then(|b| b.try_into().ok()).ok_or(MALFORMED)?;
+        Ok(if little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    };
+    let u32_at = |buf: &[u8], at: usize| -> Result<u32, String> {
+        let bytes: [u8; 4] = buf.get(at..at + 4).and_then(|b| b.try_into().ok()).ok_or(MALFORMED)?;
+        Ok(if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    };
+    let put_u16 = |value: u16| if little_endian { value.to_le_bytes() } else { value.to_be_bytes() };
+    let put_u32 = |value: u32| if little_endian { value.to_le_bytes() } else { value.to_be_bytes() };
+
+    if u16_at(tiff, 2)? != 42 {
+        return Err(MALFORMED.to_string());
+    }
+    let ifd = u32_at(tiff, 4)? as usize;
+    let count = u16_at(tiff, ifd)? as usize;
+    let entries_end = ifd + 2 + count * 12;
+    let next_ifd = tiff.get(entries_end..entries_end + 4).ok_or(MALFORMED)?;
+
+    let mut out = tiff.to_vec();
+    let mut entries: Vec<[u8; 12]> = Vec::with_capacity(count + tags.len());
+    let mut missing = Vec::new();
+
+    for i in 0..count {
+        let entry: [u8; 12] = tiff[ifd + 2 + i * 12..ifd + 14 + i * 12].try_into().unwrap();
+        entries.push(entry);
+    }
+    for &(tag, value) in tags {
+        let position = entries.iter().position(|entry| u16_at(entry, 0) == Ok(tag));
+        let patchable = position.filter(|&i| {
+            u16_at(&entries[i], 2) == Ok(TIFF_SHORT) && u32_at(&entries[i], 4) == Ok(1)
+        });
+        match patchable {
+            Some(i) => {
+                let at = ifd + 2 + i * 12 + 8;
+                out[at..at + 2].copy_from_slice(&put_u16(value));
+                entries[i][8..10].copy_from_slice(&put_u16(value));
+            }
+            None => {
+                if let Some(i) = position {
+                    entries.remove(i); // stored some other way; replaced by a SHORT
+                }
+                missing.push((tag, value));
+            }
+        }
+    }
+    if missing.is_empty() {
+        return Ok(out);
+    }
+
+    // IFD entries must stay sorted by tag
+    for (tag, value) in missing {
+        let mut entry = [0u8; 12];
+        entry[0..2].copy_from_slice(&put_u16(tag));
+        entry[2..4].copy_from_slice(&put_u16(TIFF_SHORT));
+        entry[4..8].copy_from_slice(&put_u32(1));
+        entry[8..10].copy_from_slice(&put_u16(value));
+        entries.push(entry);
+    }
+    entries.sort_by_key(|entry| u16_at(entry, 0).unwrap_or(u16::MAX));
+
+    if out.len() % 2 == 1 {
+        out.push(0); // IFDs start on a word boundary
+    }
+    let new_ifd = u32::try_from(out.len()).map_err(|_| MALFORMED)?;
+    out.extend_from_slice(&put_u16(entries.len() as u16));
+    for entry in &entries {
+        out.extend_from_slice(entry);
+    }
+    out.extend_from_slice(next_ifd);
+    out[4..8].copy_from_slice(&put_u32(new_ifd));
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::experimental::Writer;
+    use exif::Field;
+    use std::io::Cursor;
+
+    fn encoded_jpeg() -> Vec<u8> {
+        let img = image::RgbImage::from_pixel(16, 16, image::Rgb([200, 120, 40]));
+        let mut buf = Cursor::new(Vec::new());
+        img.write_to(&mut buf, image::ImageFormat::Jpeg).unwrap();
+        buf.into_inner()
+    }
+
+    fn rating_of(jpeg: &[u8]) -> Option<u32> {
+        let exif = exif::Reader::new().read_from_container(&mut Cursor::new(jpeg)).ok()?;
+        exif.get_field(RATING_TAG, In::PRIMARY)?.value.get_uint(0)
+    }
+
+    #[test]
+    fn test_exif_rating_added_and_replaced() {
+        let jpeg = encoded_jpeg();
+        assert_eq!(rating_of(&jpeg), None);
+
+        let rated = set_jpeg_exif_rating(&jpeg, 4).unwrap();
+        assert_eq!(rating_of(&rated), Some(4));
+        assert!(image::load_from_memory(&rated).is_ok());
+
+        // Rewriting replaces the tag instead of duplicating it
+        let rerated = set_jpeg_exif_rating(&rated, 2).unwrap();
+        assert_eq!(rating_of(&rerated), Some(2));
+        assert_eq!(rerated.len(), rated.len());
+    }
+
+    /// A camera-like JPEG: Exif, GPS and an out-of-line MakerNote in its APP1
+    fn jpeg_with_camera_exif() -> Vec<u8> {
+        let fields = [
+            (Tag::Make, Value::Ascii(vec![b"Canon".to_vec()])),
+            (Tag::DateTimeOriginal, Value::Ascii(vec![b"2023:06:01 14:03:22".to_vec()])),
+            (Tag::GPSLatitudeRef, Value::Ascii(vec![b"N".to_vec()])),
+            (Tag::MakerNote, Value::Undefined((0..64).collect(), 0)),
+        ]
+        .map(|(tag, value)| Field { tag, ifd_num: In::PRIMARY, value });
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, true).unwrap();
+
+        let mut contents = EXIF_PREFIX.to_vec();
+        contents.extend_from_slice(&tiff.into_inner());
+        let mut image = Jpeg::from_bytes(encoded_jpeg().into()).unwrap();
+        image.segments_mut().insert(1, JpegSegment::new_with_contents(markers::APP1, contents.into()));
+        image.encoder().bytes().to_vec()
+    }
+
+    fn exif_tiff(jpeg: &[u8]) -> Vec<u8> {
+        let image = Jpeg::from_bytes(Bytes::copy_from_slice(jpeg)).unwrap();
+        let segment = image.segments().iter().find(|s| s.marker() == markers::APP1).unwrap();
+        segment.contents()[EXIF_PREFIX.len()..].to_vec()
+    }
+
+    #[test]
+    fn test_exif_rating_keeps_camera_exif_in_place() {
+        let jpeg = jpeg_with_camera_exif();
+        let before = exif::Reader::new().read_from_container(&mut Cursor::new(&jpeg)).unwrap();
+
+        let rated = set_jpeg_exif_rating(&jpeg, 4).unwrap();
+        assert_eq!(rating_of(&rated), Some(4));
+
+        // The old block is kept byte for byte (bar the IFD0 pointer), so offsets
+        // into it, like a MakerNote's, still point at the same data
+        let (old_tiff, new_tiff) = (exif_tiff(&jpeg), exif_tiff(&rated));
+        assert_eq!(old_tiff[..4], new_tiff[..4]);
+        assert_eq!(old_tiff[8..], new_tiff[8..old_tiff.len()]);
+
+        let after = exif::Reader::new().read_from_container(&mut Cursor::new(&rated)).unwrap();
+        for tag in [Tag::Make, Tag::DateTimeOriginal, Tag::GPSLatitudeRef, Tag::MakerNote] {
+            let value = |exif: &exif::Exif| exif.get_field(tag, In::PRIMARY).map(|f| f.display_value().to_string());
+            assert!(value(&before).is_some());
+            assert_eq!(value(&before), value(&after), "{}", tag);
+        }
+
+        // Other segments and the image data are untouched
+        let segments = |jpeg: &[u8]| -> Vec<Bytes> {
+            let image = Jpeg::from_bytes(Bytes::copy_from_slice(jpeg)).unwrap();
+            image.segments().iter().filter(|s| s.marker() != markers::APP1).map(|s| s.clone().encoder().bytes()).collect()
+        };
+        assert_eq!(segments(&jpeg), segments(&rated));
+
+        // Once the tags exist, a new rating is patched in place
+        let rerated = set_jpeg_exif_rating(&rated, 1).unwrap();
+        assert_eq!(rating_of(&rerated), Some(1));
+        assert_eq!(exif_tiff(&rerated).len(), new_tiff.len());
+    }
+
+    #[test]
+    fn test_exif_rating_rejects_non_jpeg() {
+        assert!(set_jpeg_exif_rating(b"\x89PNG\r\n", 3).is_err());
+    }
+
+    #[test]
+    fn test_image_dimensions_from_header_or_exif() {
+        let dir = std::env::temp_dir().join(format!("photo-tinder-dimensions-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // Standard formats: read from the header
+        let jpeg_path = dir.join("wide.jpg");
+        image::RgbImage::new(24, 16).save(&jpeg_path).unwrap();
+        assert_eq!(image_dimensions(&jpeg_path), Some((24, 16)));
+
+        // RAW: from EXIF, turned upright by its orientation
+        let mut writer = Writer::new();
+        let fields = [
+            (Tag::PixelXDimension, Value::Long(vec![6000])),
+            (Tag::PixelYDimension, Value::Long(vec![4000])),
+            (Tag::Orientation, Value::Short(vec![6])),
+        ]
+        .map(|(tag, value)| Field { tag, ifd_num: In::PRIMARY, value });
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, true).unwrap();
+        let raw_path = dir.join("portrait.CR2");
+        fs::write(&raw_path, tiff.into_inner()).unwrap();
+        assert_eq!(image_dimensions(&raw_path), Some((4000, 6000)));
+
+        // Neither a readable header nor EXIF
+        let junk_path = dir.join("junk.jpg");
+        fs::write(&junk_path, b"not an image").unwrap();
+        assert_eq!(image_dimensions(&junk_path), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
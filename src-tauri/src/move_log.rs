@@ -0,0 +1,59 @@
+//! Audit log of file moves, one JSON object per line
+
+use crate::config::Config;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Rotate the log to `moves.log.1` once it grows past this size
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+#[derive(Serialize)]
+struct MoveLogEntry<'a> {
+    timestamp: f64,
+    action: &'a str, // "move" or "undo"
+    image_id: &'a str,
+    decision: &'a str,
+    source: &'a str,
+    destination: &'a str,
+}
+
+/// Append a move to the log. Failures are only warned about so they never block the move.
+pub fn log_move(action: &str, image_id: &str, decision: &str, source: &str, destination: &str) {
+    let entry = MoveLogEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0),
+        action,
+        image_id,
+        decision,
+        source,
+        destination,
+    };
+
+    if let Err(e) = append_entry(&entry) {
+        eprintln!("Warning: Could not write move log: {}", e);
+    }
+}
+
+fn append_entry(entry: &MoveLogEntry) -> Result<(), String> {
+    let path = Config::moves_log_path();
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    if fs::metadata(&path).map(|m| m.len() > MAX_LOG_BYTES).unwrap_or(false) {
+        fs::rename(&path, path.with_extension("log.1")).map_err(|e| e.to_string())?;
+    }
+
+    let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
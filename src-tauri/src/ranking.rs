@@ -1,15 +1,51 @@
-//! Glicko rating system for photo ranking
+//! Glicko-2 rating system for photo ranking
 
-use crate::state::{PhotoRating, RankingState, Cluster};
+use crate::config::RatingConfig;
+use crate::state::{Cluster, ComparisonRecord, PhotoRating, RankingState};
 use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::f64::consts::PI;
 
 // Glicko constants
 const GLICKO_Q: f64 = 0.0057565; // ln(10) / 400
-const DEFAULT_MU: f64 = 1500.0;
-const DEFAULT_SIGMA: f64 = 350.0;
-const MIN_SIGMA: f64 = 50.0;
+pub const DEFAULT_MU: f64 = 1500.0;
+pub const DEFAULT_SIGMA: f64 = 350.0;
+pub const MIN_SIGMA: f64 = 50.0;
+pub const CONVERGED_SIGMA: f64 = 100.0; // default for the cluster convergence sigma
+/// Default chance that a global pick is built around a photo that has never been compared
+pub const UNCOMPARED_PAIR_PROBABILITY: f64 = 0.1;
+/// Default bounds on the matches every cluster member needs before the cluster is ranked
+pub const MIN_REQUIRED_MATCHES: usize = 1;
+pub const MAX_REQUIRED_MATCHES: usize = 3;
+
+// Glicko-2 constants
+const GLICKO2_SCALE: f64 = 173.7178; // 400 / ln(10)
+const GLICKO2_EPSILON: f64 = 0.000001; // volatility convergence tolerance
+const GLICKO2_MAX_ITERATIONS: usize = 100;
+pub const DEFAULT_VOLATILITY: f64 = 0.06;
+pub const DEFAULT_TAU: f64 = 0.5;
+
+/// Compared pairs remembered so they aren't offered again straight away
+pub const RECENT_PAIRS_LEN: usize = 10;
+/// Selections tried before a recently compared pair is accepted anyway
+const FRESH_PAIR_ATTEMPTS: usize = 8;
+
+/// How global-phase pairs are chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PairStrategy {
+    /// A high-sigma photo against a similar-mu opponent
+    #[default]
+    Uncertainty,
+    /// Two photos picked uniformly at random
+    Random,
+    /// The two photos with the smallest mu gap
+    ClosestScore,
+    /// The two highest-sigma photos against each other
+    MostUncertain,
+}
 
 /// Glicko g-function: reduces impact based on opponent uncertainty
 fn glicko_g(sigma: f64) -> f64 {
@@ -17,46 +53,125 @@ fn glicko_g(sigma: f64) -> f64 {
 }
 
 /// Expected score for player A vs player B
-fn glicko_expected_score(mu_a: f64, mu_b: f64, sigma_b: f64) -> f64 {
+pub fn glicko_expected_score(mu_a: f64, mu_b: f64, sigma_b: f64) -> f64 {
     1.0 / (1.0 + 10_f64.powf(-glicko_g(sigma_b) * (mu_a - mu_b) / 400.0))
 }
 
-/// Update both ratings after a comparison
-/// Returns ((new_winner_mu, new_winner_sigma), (new_loser_mu, new_loser_sigma))
+/// Update both ratings after a comparison (Glicko-2, one-game rating period)
+/// `winner_score` is the winner's actual score: 1.0 for a decisive win, 0.5 for a
+/// tie, in between for a close call; the loser scores the rest.
+/// `tau` is the system constant limiting how fast volatility can change;
+/// sigma never drops below `rating_config.min_sigma`.
+/// Returns (new_winner, new_loser); matches_played is left to the caller.
 pub fn glicko_update(
-    winner_mu: f64, winner_sigma: f64,
-    loser_mu: f64, loser_sigma: f64,
-    is_tie: bool,
-) -> ((f64, f64), (f64, f64)) {
+    winner: &PhotoRating,
+    loser: &PhotoRating,
+    winner_score: f64,
+    tau: f64,
+    rating_config: &RatingConfig,
+) -> (PhotoRating, PhotoRating) {
     // Actual scores
-    let (s_winner, s_loser) = if is_tie { (0.5, 0.5) } else { (1.0, 0.0) };
+    let (s_winner, s_loser) = (winner_score, 1.0 - winner_score);
 
-    // Expected scores
-    let e_winner = glicko_expected_score(winner_mu, loser_mu, loser_sigma);
-    let e_loser = glicko_expected_score(loser_mu, winner_mu, winner_sigma);
+    let new_winner = glicko2_rate(winner, loser, s_winner, tau, rating_config.min_sigma);
+    let new_loser = glicko2_rate(loser, winner, s_loser, tau, rating_config.min_sigma);
 
-    // d-squared (rating change magnitude)
-    let d_squared = |sigma_opp: f64, e: f64| -> f64 {
-        let g_val = glicko_g(sigma_opp);
-        1.0 / (GLICKO_Q.powi(2) * g_val.powi(2) * e * (1.0 - e) + 1e-10)
-    };
+    (new_winner, new_loser)
+}
+
+/// Share of a photo's sigma that a "both good" / "both bad" verdict moves its mu
+pub const BOTH_NUDGE_FRACTION: f64 = 0.1;
+
+/// Nudge a rating after a verdict on both photos with no winner: `direction` 1.0
+/// for "both_good", -1.0 for "both_bad". mu moves by a small share of sigma, so
+/// well-established photos barely move; sigma is unchanged because the verdict
+/// says nothing about how the two photos compare to each other.
+pub fn glicko_adjust_both(rating: &PhotoRating, direction: f64) -> PhotoRating {
+    PhotoRating {
+        mu: rating.mu + direction.signum() * BOTH_NUDGE_FRACTION * rating.sigma,
+        ..rating.clone()
+    }
+}
 
-    let d2_winner = d_squared(loser_sigma, e_winner);
-    let d2_loser = d_squared(winner_sigma, e_loser);
+/// Glicko-2 g-function on the internal scale
+fn glicko2_g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi.powi(2) / PI.powi(2)).sqrt()
+}
+
+/// Rate `player` after scoring `score` against `opponent` (Glickman's Glicko-2 steps 2-8)
+fn glicko2_rate(
+    player: &PhotoRating,
+    opponent: &PhotoRating,
+    score: f64,
+    tau: f64,
+    min_sigma: f64,
+) -> PhotoRating {
+    // Convert to the Glicko-2 scale (only rating differences matter, so the origin is fixed)
+    let mu = (player.mu - DEFAULT_MU) / GLICKO2_SCALE;
+    let phi = player.sigma / GLICKO2_SCALE;
+    let mu_opp = (opponent.mu - DEFAULT_MU) / GLICKO2_SCALE;
+    let phi_opp = opponent.sigma / GLICKO2_SCALE;
+
+    // Estimated variance and improvement from this game
+    let g = glicko2_g(phi_opp);
+    let expected = 1.0 / (1.0 + (-g * (mu - mu_opp)).exp());
+    let v = 1.0 / (g.powi(2) * expected * (1.0 - expected) + 1e-10);
+    let delta = v * g * (score - expected);
+
+    let volatility = glicko2_volatility(phi, player.volatility, v, delta, tau);
+
+    // New deviation and rating
+    let phi_star = (phi.powi(2) + volatility.powi(2)).sqrt();
+    let new_phi = 1.0 / (1.0 / phi_star.powi(2) + 1.0 / v).sqrt();
+    let new_mu = mu + new_phi.powi(2) * g * (score - expected);
+
+    PhotoRating {
+        mu: new_mu * GLICKO2_SCALE + DEFAULT_MU,
+        // Apply floor to sigma
+        sigma: (new_phi * GLICKO2_SCALE).max(min_sigma),
+        volatility,
+        ..player.clone()
+    }
+}
 
-    // New sigmas (uncertainty decreases with each match)
-    let new_sigma_winner = (1.0 / (1.0 / winner_sigma.powi(2) + 1.0 / d2_winner)).sqrt();
-    let new_sigma_loser = (1.0 / (1.0 / loser_sigma.powi(2) + 1.0 / d2_loser)).sqrt();
+/// Iterative volatility update (Illinois variant of regula falsi)
+fn glicko2_volatility(phi: f64, sigma: f64, v: f64, delta: f64, tau: f64) -> f64 {
+    let a = sigma.powi(2).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        ex * (delta.powi(2) - phi.powi(2) - v - ex) / (2.0 * (phi.powi(2) + v + ex).powi(2))
+            - (x - a) / tau.powi(2)
+    };
 
-    // New mus
-    let new_mu_winner = winner_mu + GLICKO_Q * new_sigma_winner.powi(2) * glicko_g(loser_sigma) * (s_winner - e_winner);
-    let new_mu_loser = loser_mu + GLICKO_Q * new_sigma_loser.powi(2) * glicko_g(winner_sigma) * (s_loser - e_loser);
+    let mut big_a = a;
+    let mut big_b = if delta.powi(2) > phi.powi(2) + v {
+        (delta.powi(2) - phi.powi(2) - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * tau) < 0.0 {
+            k += 1.0;
+        }
+        a - k * tau
+    };
 
-    // Apply floor to sigma
-    let new_sigma_winner = new_sigma_winner.max(MIN_SIGMA);
-    let new_sigma_loser = new_sigma_loser.max(MIN_SIGMA);
+    let mut f_a = f(big_a);
+    let mut f_b = f(big_b);
+    let mut iterations = 0;
+    while (big_b - big_a).abs() > GLICKO2_EPSILON && iterations < GLICKO2_MAX_ITERATIONS {
+        iterations += 1;
+        let big_c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+        let f_c = f(big_c);
+        if f_c * f_b <= 0.0 {
+            big_a = big_b;
+            f_a = f_b;
+        } else {
+            f_a /= 2.0;
+        }
+        big_b = big_c;
+        f_b = f_c;
+    }
 
-    ((new_mu_winner, new_sigma_winner), (new_mu_loser, new_sigma_loser))
+    (big_a / 2.0).exp()
 }
 
 /// Get conservative score (lower bound estimate): mu - 2*sigma
@@ -64,31 +179,332 @@ pub fn get_conservative_score(mu: f64, sigma: f64) -> f64 {
     mu - 2.0 * sigma
 }
 
-/// Select optimal pair for next comparison
-pub fn select_pair(ranking: &RankingState) -> Option<(String, String)> {
+/// Probability that A would beat B, accounting for both players' uncertainty
+/// (Glicko outcome prediction uses the combined rating deviation)
+pub fn win_probability(mu_a: f64, sigma_a: f64, mu_b: f64, sigma_b: f64) -> f64 {
+    let combined_sigma = (sigma_a.powi(2) + sigma_b.powi(2)).sqrt();
+    glicko_expected_score(mu_a, mu_b, combined_sigma)
+}
+
+/// Rough number of comparisons left until every photo's sigma is below
+/// CONVERGED_SIGMA. Extrapolates from the average sigma drop per match seen in
+/// `comparison_history`; each comparison counts as a match for two photos.
+/// None until the history shows sigma falling.
+pub fn estimate_remaining_comparisons(ranking: &RankingState) -> Option<usize> {
+    // Per photo: sigma before its earliest retained comparison, and matches since
+    let mut first_sigma: HashMap<&str, f64> = HashMap::new();
+    let mut matches: HashMap<&str, usize> = HashMap::new();
+    for record in ranking.comparison_history.iter().filter(|r| r.result != "skip") {
+        for (id, sigma) in [(&record.left_id, record.left_sigma_before), (&record.right_id, record.right_sigma_before)] {
+            first_sigma.entry(id).or_insert(sigma);
+            *matches.entry(id).or_insert(0) += 1;
+        }
+    }
+
+    let (mut total_drop, mut total_matches) = (0.0, 0);
+    for (id, sigma_before) in &first_sigma {
+        if let Some(rating) = ranking.ratings.get(*id) {
+            total_drop += sigma_before - rating.sigma;
+            total_matches += matches[id];
+        }
+    }
+    if total_matches == 0 || total_drop <= 0.0 {
+        return None;
+    }
+    let drop_per_match = total_drop / total_matches as f64;
+
+    let matches_needed: f64 = ranking.ratings.values()
+        .filter(|r| r.sigma >= CONVERGED_SIGMA)
+        .map(|r| ((r.sigma - CONVERGED_SIGMA) / drop_per_match).ceil().max(1.0))
+        .sum();
+
+    Some((matches_needed / 2.0).ceil() as usize)
+}
+
+/// When a cluster counts as internally ranked: its average sigma is below `sigma`,
+/// or every member has played the required number of matches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClusterConvergence {
+    pub sigma: f64,
+    pub min_matches: usize,
+    pub max_matches: usize,
+}
+
+impl Default for ClusterConvergence {
+    fn default() -> Self {
+        Self {
+            sigma: CONVERGED_SIGMA,
+            min_matches: MIN_REQUIRED_MATCHES,
+            max_matches: MAX_REQUIRED_MATCHES,
+        }
+    }
+}
+
+impl ClusterConvergence {
+    /// Matches each member of an `n_photos` cluster needs: one fewer than the cluster
+    /// size (every photo meets each other once), clamped to `min_matches..=max_matches`
+    pub fn required_matches(&self, n_photos: usize) -> usize {
+        n_photos.saturating_sub(1).clamp(self.min_matches, self.max_matches.max(self.min_matches))
+    }
+}
+
+/// Select optimal pair for next comparison, avoiding the recently compared pairs
+/// where possible (a small collection may have nothing else to offer)
+/// (`uncompared_bias` is the chance an uncertainty pick starts from a never-compared photo)
+pub fn select_pair(
+    ranking: &RankingState,
+    strategy: PairStrategy,
+    convergence: &ClusterConvergence,
+    uncompared_bias: f64,
+) -> Option<(String, String)> {
     let ratings = &ranking.ratings;
     if ratings.len() < 2 {
         return None;
     }
 
+    // Tournament: only the next match in the bracket
+    if ranking.phase == "tournament" {
+        return tournament_pair(&ranking.bracket);
+    }
+
+    let mut fallback = None;
+    for _ in 0..FRESH_PAIR_ATTEMPTS {
+        let pair = select_candidate_pair(ranking, strategy, convergence, uncompared_bias)?;
+        if !is_recent_pair(&ranking.recent_pairs, &pair.0, &pair.1) {
+            return Some(pair);
+        }
+        fallback.get_or_insert(pair);
+    }
+    fallback
+}
+
+/// Select three photos for a best-of-three pick: a pair from `select_pair` plus the
+/// photo rated closest to them, taken from their cluster while it is being ranked
+/// internally. None in tournament mode or with fewer than three photos.
+pub fn select_triple(
+    ranking: &RankingState,
+    strategy: PairStrategy,
+    convergence: &ClusterConvergence,
+    uncompared_bias: f64,
+) -> Option<[String; 3]> {
+    if ranking.phase == "tournament" || ranking.ratings.len() < 3 {
+        return None;
+    }
+    let (left, right) = select_pair(ranking, strategy, convergence, uncompared_bias)?;
+    let ratings = &ranking.ratings;
+    let mu = |id: &str| ratings.get(id).map(|r| r.mu).unwrap_or(DEFAULT_MU);
+    let target_mu = (mu(&left) + mu(&right)) / 2.0;
+
+    let cluster_mates: Option<Vec<&String>> = (ranking.phase == "intra_cluster")
+        .then(|| ranking.photo_to_cluster.get(&left))
+        .flatten()
+        .filter(|cluster_id| ranking.photo_to_cluster.get(&right) == Some(*cluster_id))
+        .and_then(|cluster_id| ranking.clusters.get(cluster_id))
+        .map(|cluster| cluster.photo_ids.iter()
+            .filter(|id| ratings.contains_key(*id) && **id != left && **id != right)
+            .collect())
+        .filter(|mates: &Vec<&String>| !mates.is_empty());
+    let candidates = cluster_mates.unwrap_or_else(|| {
+        ratings.keys().filter(|id| **id != left && **id != right).collect()
+    });
+
+    let third = candidates.into_iter().min_by(|a, b| {
+        (mu(a) - target_mu).abs().partial_cmp(&(mu(b) - target_mu).abs())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    })?;
+    Some([left, right, third.clone()])
+}
+
+/// Key of two photos' head-to-head record: their ids in sorted order, so it doesn't
+/// depend on which side each was shown. The flag says whether `a` sorts first.
+pub fn pair_key(a: &str, b: &str) -> (String, bool) {
+    if a <= b {
+        (format!("{}|{}", a, b), true)
+    } else {
+        (format!("{}|{}", b, a), false)
+    }
+}
+
+/// Fold a comparison being trimmed from `comparison_history` into the lasting
+/// win/loss/tie counters of both ratings and the pair's head-to-head record.
+/// Skips and "both good/bad" verdicts have no winner and aren't counted.
+pub fn fold_comparison(ranking: &mut RankingState, record: &ComparisonRecord) {
+    let (left, right) = (&record.left_id, &record.right_id);
+    let winner = match record.result.as_str() {
+        "left" => Some(left),
+        "right" => Some(right),
+        "tie" => None,
+        _ => return,
+    };
+
+    for id in [left, right] {
+        if let Some(rating) = ranking.ratings.get_mut(id) {
+            match winner {
+                None => rating.ties += 1,
+                Some(winner) if winner == id => rating.wins += 1,
+                Some(_) => rating.losses += 1,
+            }
+        }
+    }
+
+    let (key, left_first) = pair_key(left, right);
+    let pair = ranking.head_to_head.entry(key).or_default();
+    match winner {
+        None => pair.ties += 1,
+        Some(winner) if (winner == left) == left_first => pair.first_wins += 1,
+        Some(_) => pair.second_wins += 1,
+    }
+}
+
+/// Remember a compared pair, forgetting the oldest beyond RECENT_PAIRS_LEN
+pub fn record_recent_pair(ranking: &mut RankingState, left: &str, right: &str) {
+    ranking.recent_pairs.push((left.to_string(), right.to_string()));
+    if ranking.recent_pairs.len() > RECENT_PAIRS_LEN {
+        let keep = ranking.recent_pairs.len() - RECENT_PAIRS_LEN;
+        ranking.recent_pairs = ranking.recent_pairs.split_off(keep);
+    }
+}
+
+/// Whether two photos were compared recently, in either order
+fn is_recent_pair(recent: &[(String, String)], a: &str, b: &str) -> bool {
+    recent.iter().any(|(left, right)| (left == a && right == b) || (left == b && right == a))
+}
+
+/// One pick for the current phase and strategy
+fn select_candidate_pair(
+    ranking: &RankingState,
+    strategy: PairStrategy,
+    convergence: &ClusterConvergence,
+    uncompared_bias: f64,
+) -> Option<(String, String)> {
+    let ratings = &ranking.ratings;
     let phase = &ranking.phase;
 
     // Try intra-cluster pairing first
     if phase == "intra_cluster" && !ranking.clusters.is_empty() {
-        if let Some(pair) = select_intra_cluster_pair(&ranking.clusters, ratings) {
+        if let Some(pair) = select_intra_cluster_pair(&ranking.clusters, ratings, convergence) {
             return Some(pair);
         }
-        // All clusters done - caller should switch to global
+        // All clusters converged - pair globally until the phase switches
     }
 
     // Global pairing
-    select_global_pair(ratings)
+    match strategy {
+        PairStrategy::Uncertainty => select_global_pair(ratings, uncompared_bias),
+        PairStrategy::Random => select_random_pair(ratings),
+        PairStrategy::ClosestScore => select_closest_score_pair(ratings),
+        PairStrategy::MostUncertain => select_most_uncertain_pair(ratings),
+    }
+}
+
+/// Seed a single-elimination bracket by conservative score. Round one pairs the
+/// best remaining seed with the worst; with an odd count the top seed gets a bye.
+/// Returns the bracket and, for a lone photo, its champion.
+pub fn seed_bracket(ratings: &HashMap<String, PhotoRating>) -> (Vec<Vec<String>>, Option<String>) {
+    let mut seeds: Vec<(&String, f64)> = ratings.iter()
+        .map(|(id, r)| (id, get_conservative_score(r.mu, r.sigma)))
+        .collect();
+    seeds.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.0.cmp(b.0))
+    });
+    let mut seeds: Vec<String> = seeds.into_iter().map(|(id, _)| id.clone()).collect();
+
+    let top_seed = if seeds.len() % 2 == 1 { Some(seeds.remove(0)) } else { None };
+
+    let mut round = Vec::with_capacity(seeds.len() + 1);
+    for i in 0..seeds.len() / 2 {
+        round.push(seeds[i].clone());
+        round.push(seeds[seeds.len() - 1 - i].clone());
+    }
+    // The bye sits last, where it is advanced once the paired matches are decided
+    round.extend(top_seed);
+
+    let mut bracket = vec![round, Vec::new()];
+    let champion = settle_bracket(&mut bracket);
+    (bracket, champion)
+}
+
+/// Next undecided match: entrants 2i and 2i+1 of the current round, i = winners so far
+pub fn tournament_pair(bracket: &[Vec<String>]) -> Option<(String, String)> {
+    let [.., round, winners] = bracket else {
+        return None;
+    };
+    let i = winners.len() * 2;
+    Some((round.get(i)?.clone(), round.get(i + 1)?.clone()))
+}
+
+/// Advance the winner of the current match. Returns the champion once one photo remains.
+pub fn advance_bracket(bracket: &mut Vec<Vec<String>>, winner: &str) -> Option<String> {
+    let winners = bracket.last_mut()?;
+    winners.push(winner.to_string());
+    settle_bracket(bracket)
+}
+
+/// Apply a pending bye and open the next round once the current one is decided
+fn settle_bracket(bracket: &mut Vec<Vec<String>>) -> Option<String> {
+    loop {
+        let n = bracket.len();
+        if n < 2 {
+            return None;
+        }
+        let round_len = bracket[n - 2].len();
+        let winners_len = bracket[n - 1].len();
+
+        if round_len % 2 == 1 && winners_len == round_len / 2 {
+            let bye = bracket[n - 2][round_len - 1].clone();
+            bracket[n - 1].push(bye);
+            continue;
+        }
+
+        if winners_len < round_len.div_ceil(2) {
+            return None;
+        }
+        if winners_len == 1 {
+            return bracket[n - 1].first().cloned();
+        }
+        bracket.push(Vec::new());
+    }
 }
 
-/// Select a pair from within an incomplete cluster
+/// Top up the pair queue to `batch` entries using `select_pair`.
+/// Queued pairs never share a photo, since comparing one invalidates the others.
+pub fn fill_pair_queue(
+    ranking: &mut RankingState,
+    batch: usize,
+    strategy: PairStrategy,
+    convergence: &ClusterConvergence,
+    uncompared_bias: f64,
+) {
+    let max_attempts = batch * 4;
+    let mut attempts = 0;
+
+    while ranking.pair_queue.len() < batch && attempts < max_attempts {
+        attempts += 1;
+
+        let Some((left, right)) = select_pair(ranking, strategy, convergence, uncompared_bias) else {
+            break;
+        };
+
+        let overlaps = ranking.pair_queue.iter()
+            .any(|(a, b)| a == &left || a == &right || b == &left || b == &right);
+        if !overlaps {
+            ranking.pair_queue.push((left, right));
+        }
+    }
+}
+
+/// Drop queued pairs involving either photo (their ratings just changed)
+pub fn invalidate_queued_pairs(queue: &mut Vec<(String, String)>, photo_a: &str, photo_b: &str) {
+    queue.retain(|(left, right)| {
+        left != photo_a && left != photo_b && right != photo_a && right != photo_b
+    });
+}
+
+/// Select a pair from within an incomplete cluster that hasn't converged yet
 fn select_intra_cluster_pair(
     clusters: &HashMap<String, Cluster>,
     ratings: &HashMap<String, PhotoRating>,
+    convergence: &ClusterConvergence,
 ) -> Option<(String, String)> {
     for cluster in clusters.values() {
         if cluster.internal_ranking_complete {
@@ -116,10 +532,9 @@ fn select_intra_cluster_pair(
             .unwrap_or(0);
 
         // For small clusters, fewer matches needed
-        let n_photos = valid_ids.len();
-        let required_matches = if n_photos == 2 { 1 } else if n_photos == 3 { 2 } else { 3 };
+        let required_matches = convergence.required_matches(valid_ids.len());
 
-        if avg_sigma < 100.0 || min_matches >= required_matches {
+        if avg_sigma < convergence.sigma || min_matches >= required_matches {
             continue; // Cluster is converged
         }
 
@@ -149,8 +564,10 @@ fn select_intra_cluster_pair(
     None
 }
 
-/// Select pair for global ranking phase
-fn select_global_pair(ratings: &HashMap<String, PhotoRating>) -> Option<(String, String)> {
+/// Select pair for global ranking phase. With probability `uncompared_bias` the
+/// primary is a photo that has never been compared, so none sits at its default
+/// rating forever while others keep outranking it on sigma.
+fn select_global_pair(ratings: &HashMap<String, PhotoRating>, uncompared_bias: f64) -> Option<(String, String)> {
     let all_photos: Vec<_> = ratings.keys().cloned().collect();
     if all_photos.len() < 2 {
         return None;
@@ -167,7 +584,15 @@ fn select_global_pair(ratings: &HashMap<String, PhotoRating>) -> Option<(String,
     let primary_candidates: Vec<_> = sorted_photos.iter().take(top_n).map(|(p, _)| p.clone()).collect();
 
     let mut rng = rand::thread_rng();
-    let primary = primary_candidates.choose(&mut rng)?;
+    let uncompared: Vec<_> = ratings.iter()
+        .filter(|(_, r)| r.matches_played == 0)
+        .map(|(id, _)| id.clone())
+        .collect();
+    let primary = if !uncompared.is_empty() && rng.gen_bool(uncompared_bias.clamp(0.0, 1.0)) {
+        uncompared.choose(&mut rng)?
+    } else {
+        primary_candidates.choose(&mut rng)?
+    };
     let primary_mu = ratings.get(primary).map(|r| r.mu).unwrap_or(DEFAULT_MU);
 
     // Find similar-mu opponent from a random sample
@@ -191,10 +616,46 @@ fn select_global_pair(ratings: &HashMap<String, PhotoRating>) -> Option<(String,
     Some((primary.clone(), opponent.clone()))
 }
 
+/// Select two distinct photos at random
+fn select_random_pair(ratings: &HashMap<String, PhotoRating>) -> Option<(String, String)> {
+    let all_photos: Vec<_> = ratings.keys().collect();
+    let mut rng = rand::thread_rng();
+    let picked: Vec<_> = all_photos.choose_multiple(&mut rng, 2).collect();
+
+    match picked.as_slice() {
+        [a, b] => Some(((**a).clone(), (**b).clone())),
+        _ => None,
+    }
+}
+
+/// Select the two photos whose mu values are closest
+fn select_closest_score_pair(ratings: &HashMap<String, PhotoRating>) -> Option<(String, String)> {
+    let mut by_mu: Vec<(&String, f64)> = ratings.iter().map(|(id, r)| (id, r.mu)).collect();
+    by_mu.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    // The closest pair is always adjacent once sorted by mu
+    by_mu.windows(2)
+        .min_by(|a, b| {
+            (a[1].1 - a[0].1).partial_cmp(&(b[1].1 - b[0].1)).unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .map(|w| (w[0].0.clone(), w[1].0.clone()))
+}
+
+/// Select the two photos with the highest sigma
+fn select_most_uncertain_pair(ratings: &HashMap<String, PhotoRating>) -> Option<(String, String)> {
+    let mut by_sigma: Vec<(&String, f64)> = ratings.iter().map(|(id, r)| (id, r.sigma)).collect();
+    by_sigma.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    match by_sigma.as_slice() {
+        [a, b, ..] => Some((a.0.clone(), b.0.clone())),
+        _ => None,
+    }
+}
+
 /// Initialize ratings for a set of photos
-pub fn initialize_ratings(photo_ids: &[String]) -> HashMap<String, PhotoRating> {
+pub fn initialize_ratings(photo_ids: &[String], rating_config: &RatingConfig) -> HashMap<String, PhotoRating> {
     photo_ids.iter()
-        .map(|id| (id.clone(), PhotoRating::default()))
+        .map(|id| (id.clone(), PhotoRating::initial(rating_config)))
         .collect()
 }
 
@@ -211,13 +672,297 @@ pub fn finalize_cluster(
     cluster.internal_ranking_complete = true;
 
     // Set representative as highest-rated photo
-    let best_id = cluster.photo_ids.iter()
+    cluster.representative_id = best_in_cluster(cluster, ratings).cloned();
+}
+
+/// Highest-rated member of a cluster by conservative score
+pub fn best_in_cluster<'a>(cluster: &'a Cluster, ratings: &HashMap<String, PhotoRating>) -> Option<&'a String> {
+    cluster.photo_ids.iter()
         .filter(|pid| ratings.contains_key(*pid))
         .max_by(|a, b| {
             let score_a = ratings.get(*a).map(|r| get_conservative_score(r.mu, r.sigma)).unwrap_or(0.0);
             let score_b = ratings.get(*b).map(|r| get_conservative_score(r.mu, r.sigma)).unwrap_or(0.0);
             score_a.partial_cmp(&score_b).unwrap_or(std::cmp::Ordering::Equal)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(a: &str, b: &str) -> (String, String) {
+        (a.to_string(), b.to_string())
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 0.01, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_glicko2_single_win_from_default() {
+        let player = PhotoRating::default();
+        let opponent = PhotoRating::default();
+
+        let (winner, loser) = glicko_update(&player, &opponent, 1.0, DEFAULT_TAU, &RatingConfig::default());
+        assert_close(winner.mu, 1662.31);
+        assert_close(winner.sigma, 290.32);
+        assert_close(loser.mu, 1337.69);
+        assert_close(loser.sigma, 290.32);
+        assert!((winner.volatility - DEFAULT_VOLATILITY).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_glicko2_tie_between_equals_keeps_mu() {
+        let player = PhotoRating::default();
+        let (a, b) = glicko_update(&player, &player, 0.5, DEFAULT_TAU, &RatingConfig::default());
+        assert_close(a.mu, 1500.0);
+        assert_close(b.mu, 1500.0);
+        assert!(a.sigma < player.sigma);
+    }
+
+    #[test]
+    fn test_glicko2_low_confidence_barely_moves_mu() {
+        let player = PhotoRating::default();
+        let config = RatingConfig::default();
+
+        let (coin_flip, _) = glicko_update(&player, &player, 0.5, DEFAULT_TAU, &config);
+        let (close_call, _) = glicko_update(&player, &player, 0.55, DEFAULT_TAU, &config);
+        let (decisive, _) = glicko_update(&player, &player, 1.0, DEFAULT_TAU, &config);
+
+        assert!((coin_flip.mu - player.mu).abs() < 0.01);
+        assert!(close_call.mu - player.mu < (decisive.mu - player.mu) * 0.15);
+        assert!(close_call.mu > player.mu);
+    }
+
+    #[test]
+    fn test_estimate_remaining_comparisons_from_sigma_drop() {
+        let mut ranking = RankingState::default();
+        assert_eq!(estimate_remaining_comparisons(&ranking), None);
+
+        // One match each took sigma from 350 to 300: 50 per match, 4 more matches each
+        for id in ["a", "b"] {
+            ranking.ratings.insert(id.to_string(), PhotoRating { sigma: 300.0, ..PhotoRating::default() });
+        }
+        ranking.ratings.insert("c".to_string(), PhotoRating { sigma: 80.0, ..PhotoRating::default() });
+        ranking.comparison_history.push(ComparisonRecord {
+            left_id: "a".to_string(),
+            right_id: "b".to_string(),
+            result: "left".to_string(),
+            left_mu_before: DEFAULT_MU,
+            left_sigma_before: DEFAULT_SIGMA,
+            right_mu_before: DEFAULT_MU,
+            right_sigma_before: DEFAULT_SIGMA,
+            left_volatility_before: DEFAULT_VOLATILITY,
+            right_volatility_before: DEFAULT_VOLATILITY,
+            bracket_before: None,
+            batch_with_previous: false,
+            timestamp: 0.0,
         });
 
-    cluster.representative_id = best_id.cloned();
+        assert_eq!(estimate_remaining_comparisons(&ranking), Some(4));
+    }
+
+    #[test]
+    fn test_adjust_both_nudges_by_uncertainty() {
+        let uncertain = PhotoRating::default();
+        let settled = PhotoRating { sigma: MIN_SIGMA, ..PhotoRating::default() };
+
+        let up = glicko_adjust_both(&uncertain, 1.0);
+        let down = glicko_adjust_both(&settled, -1.0);
+        assert_close(up.mu, DEFAULT_MU + BOTH_NUDGE_FRACTION * DEFAULT_SIGMA);
+        assert_close(down.mu, DEFAULT_MU - BOTH_NUDGE_FRACTION * MIN_SIGMA);
+        assert_eq!(up.sigma, uncertain.sigma);
+        assert_eq!(down.sigma, settled.sigma);
+    }
+
+    #[test]
+    fn test_tournament_bracket_with_bye() {
+        let ids: Vec<String> = (0..5).map(|i| format!("photo_{}", i)).collect();
+        let mut ratings = initialize_ratings(&ids, &RatingConfig::default());
+        for (i, id) in ids.iter().enumerate() {
+            ratings.get_mut(id).unwrap().mu = 1500.0 + 100.0 * (5 - i) as f64;
+        }
+
+        let (mut bracket, champion) = seed_bracket(&ratings);
+        assert_eq!(champion, None);
+        assert_eq!(bracket[0], vec!["photo_1", "photo_4", "photo_2", "photo_3", "photo_0"]);
+
+        // Higher seed always wins; photo_0 skips round one
+        let mut champion = None;
+        let mut matches = 0;
+        while let Some((left, right)) = tournament_pair(&bracket) {
+            let winner = if left < right { left } else { right };
+            champion = advance_bracket(&mut bracket, &winner);
+            matches += 1;
+        }
+
+        assert_eq!(matches, 4);
+        assert_eq!(champion.as_deref(), Some("photo_0"));
+    }
+
+    #[test]
+    fn test_tournament_single_photo_wins_immediately() {
+        let ratings = initialize_ratings(&["only".to_string()], &RatingConfig::default());
+        let (bracket, champion) = seed_bracket(&ratings);
+        assert_eq!(tournament_pair(&bracket), None);
+        assert_eq!(champion.as_deref(), Some("only"));
+    }
+
+    fn global_ranking(mus: &[f64]) -> RankingState {
+        let ids: Vec<String> = (0..mus.len()).map(|i| format!("photo_{}", i)).collect();
+        let mut ratings = initialize_ratings(&ids, &RatingConfig::default());
+        for (id, mu) in ids.iter().zip(mus) {
+            ratings.get_mut(id).unwrap().mu = *mu;
+        }
+        RankingState {
+            initialized: true,
+            ratings,
+            phase: "global".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_random_strategy_never_pairs_a_photo_with_itself() {
+        let ranking = global_ranking(&[1500.0, 1510.0, 1490.0]);
+        for _ in 0..200 {
+            let (left, right) = select_pair(&ranking, PairStrategy::Random, &ClusterConvergence::default(), 0.0).unwrap();
+            assert_ne!(left, right);
+        }
+
+        let ranking = global_ranking(&[1500.0]);
+        assert_eq!(select_pair(&ranking, PairStrategy::Random, &ClusterConvergence::default(), 0.0), None);
+    }
+
+    #[test]
+    fn test_uncompared_bias_picks_never_compared_photo() {
+        let mut ranking = global_ranking(&[1500.0; 30]);
+        for rating in ranking.ratings.values_mut() {
+            rating.matches_played = 5;
+        }
+        // Lowest sigma, so the uncertainty pick alone would never start from it
+        let starved = ranking.ratings.keys().next().unwrap().clone();
+        let rating = ranking.ratings.get_mut(&starved).unwrap();
+        rating.matches_played = 0;
+        rating.sigma = MIN_SIGMA;
+
+        for _ in 0..20 {
+            let (primary, _) = select_global_pair(&ranking.ratings, 1.0).unwrap();
+            assert_eq!(primary, starved);
+        }
+    }
+
+    #[test]
+    fn test_select_triple_returns_three_distinct_photos() {
+        let ranking = global_ranking(&[1500.0, 1510.0, 1490.0, 1700.0]);
+        for _ in 0..50 {
+            let [a, b, c] = select_triple(&ranking, PairStrategy::Random, &ClusterConvergence::default(), 0.0).unwrap();
+            assert!(a != b && b != c && a != c);
+        }
+
+        let ranking = global_ranking(&[1500.0, 1510.0]);
+        assert_eq!(select_triple(&ranking, PairStrategy::Random, &ClusterConvergence::default(), 0.0), None);
+    }
+
+    #[test]
+    fn test_fold_comparison_counts_wins_by_pair_order() {
+        let mut ranking = global_ranking(&[1500.0, 1500.0]);
+        let record = |left: &str, right: &str, result: &str| ComparisonRecord {
+            left_id: left.to_string(),
+            right_id: right.to_string(),
+            result: result.to_string(),
+            left_mu_before: DEFAULT_MU,
+            left_sigma_before: DEFAULT_SIGMA,
+            right_mu_before: DEFAULT_MU,
+            right_sigma_before: DEFAULT_SIGMA,
+            left_volatility_before: DEFAULT_VOLATILITY,
+            right_volatility_before: DEFAULT_VOLATILITY,
+            bracket_before: None,
+            batch_with_previous: false,
+            timestamp: 0.0,
+        };
+        for (left, right, result) in [
+            ("photo_1", "photo_0", "left"),
+            ("photo_0", "photo_1", "right"),
+            ("photo_0", "photo_1", "tie"),
+            ("photo_0", "photo_1", "skip"),
+        ] {
+            fold_comparison(&mut ranking, &record(left, right, result));
+        }
+
+        let pair = &ranking.head_to_head[&pair_key("photo_1", "photo_0").0];
+        assert_eq!((pair.first_wins, pair.second_wins, pair.ties), (0, 2, 1));
+        let winner = &ranking.ratings["photo_1"];
+        assert_eq!((winner.wins, winner.losses, winner.ties), (2, 0, 1));
+    }
+
+    #[test]
+    fn test_recent_pairs_are_order_independent_and_bounded() {
+        let mut ranking = global_ranking(&[1500.0, 1510.0]);
+        for i in 0..RECENT_PAIRS_LEN {
+            record_recent_pair(&mut ranking, &format!("old_{}", i), "x");
+        }
+        record_recent_pair(&mut ranking, "photo_1", "photo_0");
+
+        assert_eq!(ranking.recent_pairs.len(), RECENT_PAIRS_LEN);
+        assert!(!is_recent_pair(&ranking.recent_pairs, "old_0", "x"));
+        assert!(is_recent_pair(&ranking.recent_pairs, "photo_0", "photo_1"));
+
+        // Two photos only: the repeat is allowed rather than stalling
+        let (left, right) = select_pair(&ranking, PairStrategy::Random, &ClusterConvergence::default(), 0.0).unwrap();
+        assert!(is_recent_pair(&ranking.recent_pairs, &left, &right));
+    }
+
+    #[test]
+    fn test_closest_score_strategy_picks_minimal_mu_gap() {
+        let ranking = global_ranking(&[1200.0, 1500.0, 1700.0, 1520.0, 1900.0]);
+        let (left, right) = select_pair(&ranking, PairStrategy::ClosestScore, &ClusterConvergence::default(), 0.0).unwrap();
+
+        let mut pair = [left, right];
+        pair.sort();
+        assert_eq!(pair, ["photo_1".to_string(), "photo_3".to_string()]);
+    }
+
+    #[test]
+    fn test_invalidate_queued_pairs() {
+        let mut queue = vec![pair("a", "b"), pair("c", "d"), pair("e", "a"), pair("b", "f"), pair("g", "h")];
+        invalidate_queued_pairs(&mut queue, "a", "b");
+        assert_eq!(queue, vec![pair("c", "d"), pair("g", "h")]);
+
+        // Unrelated ids leave the queue untouched
+        invalidate_queued_pairs(&mut queue, "x", "y");
+        assert_eq!(queue, vec![pair("c", "d"), pair("g", "h")]);
+    }
+
+    #[test]
+    fn test_fill_pair_queue_has_no_overlapping_pairs() {
+        let ids: Vec<String> = (0..20).map(|i| format!("photo_{}", i)).collect();
+        let mut ranking = RankingState {
+            initialized: true,
+            ratings: initialize_ratings(&ids, &RatingConfig::default()),
+            phase: "global".to_string(),
+            ..Default::default()
+        };
+
+        fill_pair_queue(&mut ranking, 5, PairStrategy::default(), &ClusterConvergence::default(), 0.0);
+        assert!(!ranking.pair_queue.is_empty());
+
+        let mut seen = std::collections::HashSet::new();
+        for (left, right) in &ranking.pair_queue {
+            assert_ne!(left, right);
+            assert!(seen.insert(left.clone()));
+            assert!(seen.insert(right.clone()));
+        }
+    }
+
+    #[test]
+    fn test_required_matches_curve() {
+        let default = ClusterConvergence::default();
+        let required: Vec<usize> = (2..=6).map(|n| default.required_matches(n)).collect();
+        assert_eq!(required, vec![1, 2, 3, 3, 3]);
+
+        let thorough = ClusterConvergence { min_matches: 2, max_matches: 5, ..default };
+        assert_eq!(thorough.required_matches(2), 2);
+        assert_eq!(thorough.required_matches(10), 5);
+    }
 }
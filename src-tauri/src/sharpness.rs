@@ -0,0 +1,70 @@
+//! Sharpness estimation for spotting blurry photos
+
+use crate::decode::open_image;
+use image::GrayImage;
+use std::path::Path;
+
+/// Larger images are scaled down to this longest side before measuring,
+/// so scores stay comparable across resolutions
+const ANALYSIS_SIZE: u32 = 1024;
+
+/// Laplacian variance below which a photo counts as blurry by default
+pub const DEFAULT_BLUR_THRESHOLD: f64 = 100.0;
+
+/// Sharpness score of an image file (variance of the Laplacian; higher is sharper)
+pub fn sharpness(image_path: &Path) -> Result<f64, String> {
+    let (img, _) = open_image(image_path)?;
+
+    let img = if img.width().max(img.height()) > ANALYSIS_SIZE {
+        img.resize(ANALYSIS_SIZE, ANALYSIS_SIZE, image::imageops::FilterType::Triangle)
+    } else {
+        img
+    };
+
+    Ok(laplacian_variance(&img.to_luma8()))
+}
+
+/// Variance of the 4-neighbour Laplacian over the image interior.
+/// Blur removes fine edges, which flattens the Laplacian and lowers its variance.
+pub fn laplacian_variance(gray: &GrayImage) -> f64 {
+    let (width, height) = gray.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let px = |x: u32, y: u32| gray.get_pixel(x, y)[0] as f64;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let laplacian = px(x - 1, y) + px(x + 1, y) + px(x, y - 1) + px(x, y + 1) - 4.0 * px(x, y);
+            sum += laplacian;
+            sum_sq += laplacian * laplacian;
+        }
+    }
+
+    let count = ((width - 2) * (height - 2)) as f64;
+    let mean = sum / count;
+    sum_sq / count - mean * mean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blur_lowers_laplacian_variance() {
+        let sharp = GrayImage::from_fn(64, 64, |x, y| {
+            image::Luma([if (x / 4 + y / 4) % 2 == 0 { 255 } else { 0 }])
+        });
+        let blurred = image::imageops::blur(&sharp, 3.0);
+
+        let sharp_score = laplacian_variance(&sharp);
+        let blurred_score = laplacian_variance(&blurred);
+        assert!(sharp_score > blurred_score * 10.0, "{} vs {}", sharp_score, blurred_score);
+
+        let flat = GrayImage::from_pixel(16, 16, image::Luma([128]));
+        assert_eq!(laplacian_variance(&flat), 0.0);
+    }
+}
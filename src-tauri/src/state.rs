@@ -1,10 +1,17 @@
 //! Application state management
 
-use crate::config::Config;
+use crate::config::{write_atomic, Config, DestinationOverride, RatingConfig};
+use crate::hashing::HashAlgorithm;
+use crate::metadata::ExifSummary;
+use crate::ranking::{DEFAULT_MU, DEFAULT_SIGMA, DEFAULT_VOLATILITY};
+use crate::watcher::SourceWatcher;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 /// Supported image extensions
 /// Includes common formats, RAW formats from major camera manufacturers, and modern formats
@@ -35,12 +42,25 @@ pub const SUPPORTED_EXTENSIONS: &[&str] = &[
     "ari",                      // Arri
 ];
 
+/// RAW formats the `image` crate can't decode; hashing uses their embedded JPEG preview
+pub const RAW_EXTENSIONS: &[&str] = &[
+    "raw", "cr2", "cr3", "crw", "nef", "nrw", "arw", "srf", "sr2", "orf", "rw2", "raf",
+    "pef", "ptx", "srw", "x3f", "dng", "3fr", "fff", "iiq", "rwl", "dcr", "kdc", "erf",
+    "mrw", "bay", "ari",
+];
+
+/// HEIF-container formats, decoded with libheif when the `heic` feature is enabled
+pub const HEIF_EXTENSIONS: &[&str] = &["heic", "heif"];
+
 /// Represents a single image to be triaged
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageRecord {
     pub id: String,
     pub source_folder: String,
     pub relative_path: String,
+    pub timestamp: Option<f64>, // capture time, only read when ordering by date
+    #[serde(default)]
+    pub size: u64, // file size in bytes when scanned (0 if it couldn't be read)
 }
 
 impl ImageRecord {
@@ -63,62 +83,373 @@ impl ImageRecord {
     }
 }
 
+/// Triage decisions kept in history (and in the redo stack) for undo
+pub const MAX_HISTORY: usize = 100;
+
+/// Ranking comparisons kept in history for undo; older ones are folded into the
+/// win/loss counters
+pub const MAX_COMPARISON_HISTORY: usize = 100;
+
+/// How often the background saver writes state marked with `AppState::save_later`
+pub const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Number of recent swipe timestamps kept for the triage ETA estimate
+pub const DECISION_TIME_WINDOW: usize = 50;
+
 /// Persistent state that gets saved to disk
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PersistentState {
     pub current_index: usize,
-    pub decisions: HashMap<String, String>, // image_id -> "accepted"|"rejected"|"skipped"
+    pub decisions: HashMap<String, String>, // image_id -> "accepted"|"rejected"|"skipped"|"deferred"
     pub history: Vec<(String, String, String)>, // (image_id, old_decision, new_decision)
-    pub moved_files: HashMap<String, String>, // image_id -> destination_path
+    #[serde(default)]
+    pub redo_stack: Vec<(String, String, String)>, // undone history entries, most recent last
+    #[serde(default)]
+    pub redo_reasons: HashMap<String, String>, // image_id -> reason of its latest undone decision
+    pub moved_files: HashMap<String, String>, // image_id -> destination_path (or trash-restore token)
     pub original_paths: HashMap<String, String>, // image_id -> original_path (for undo)
+    #[serde(default)]
+    pub copied_files: HashSet<String>, // image_ids decided in copy mode (source left in place)
+    #[serde(default)]
+    pub extra_copies: HashMap<String, Vec<String>>, // image_id -> copies in extra_accept_destinations
+    #[serde(default)]
+    pub moved_sizes: HashMap<String, u64>, // image_id -> file size when moved (for stats once it leaves the scan)
     pub mode: String, // "triage" or "ranking"
-    pub ranking: RankingState,
+    pub ranking: RankingState, // active ranking session
+    #[serde(default)]
+    pub parked_rankings: HashMap<String, RankingState>, // inactive ranking sessions, keyed by target
+    #[serde(default)]
+    pub decision_reasons: HashMap<String, String>, // image_id -> reason code ("blurry", "duplicate", ...)
+    #[serde(default)]
+    pub recent_decision_times: Vec<f64>, // rolling buffer of swipe timestamps (for ETA)
+    #[serde(default)]
+    pub decided_at: HashMap<String, f64>, // image_id -> unix time of its current decision
+    #[serde(default)]
+    pub skip_counts: HashMap<String, usize>, // image_id -> times skipped (undone skips don't count)
+    #[serde(default)]
+    pub content_ids: bool, // ids above are content-based (see Config::content_ids)
+    #[serde(default)]
+    pub planned_moves: HashMap<String, String>, // image_id -> would-be destination, decided in a dry run
+    #[serde(default)]
+    pub ranked_removals: HashMap<String, RankedRemoval>, // image_id -> ranking entry decide_cluster took out
+    #[serde(default)]
+    pub last_removed_folder: Option<RemovedFolder>, // most recent remove_source_folder, for undo
+    #[serde(skip)]
+    pub recovery: Option<StateRecovery>, // set when state.json couldn't be read; blocks saving
+}
+
+/// A removed source folder with the bookkeeping cleared along with it, so
+/// `restore_last_removed_folder` can put both back
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RemovedFolder {
+    pub path: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool, // false if it was a disabled folder when removed
+    pub destination_override: Option<DestinationOverride>,
+    pub decisions: HashMap<String, String>,
+    pub decided_at: HashMap<String, f64>,
+    pub moved_files: HashMap<String, String>,
+    pub original_paths: HashMap<String, String>,
+    pub copied_files: HashSet<String>,
+    pub extra_copies: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub moved_sizes: HashMap<String, u64>,
+}
+
+/// What happened when state.json couldn't be read at load. Saving is refused until
+/// the user acknowledges it, so the unreadable file isn't silently replaced.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateRecovery {
+    pub error: String,
+    pub backup_path: Option<String>, // copy of the unreadable file
+    pub recovered: Vec<String>, // top-level fields salvaged from it, e.g. "decisions"
 }
 
 impl PersistentState {
     /// Load state from file
     pub fn load() -> Self {
         let path = Config::state_path();
-        if path.exists() {
-            if let Ok(contents) = fs::read_to_string(&path) {
-                if let Ok(state) = serde_json::from_str(&contents) {
-                    return state;
-                }
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::fresh(),
+            Err(e) => {
+                eprintln!("Warning: Could not read {}: {}", path.display(), e);
+                return Self::unreadable(&path, None, e.to_string());
+            }
+        };
+
+        match serde_json::from_str(&contents) {
+            Ok(state) => state,
+            Err(e) => {
+                eprintln!("Warning: Could not parse {}: {}", path.display(), e);
+                Self::unreadable(&path, Some(&contents), e.to_string())
             }
         }
+    }
+
+    fn fresh() -> Self {
         Self {
             mode: "triage".to_string(),
             ..Default::default()
         }
     }
 
+    /// State for a file that failed to load: back it up as `state.json.corrupt.<timestamp>`,
+    /// salvage what still parses, and hold off saving until the user has been told
+    fn unreadable(path: &Path, contents: Option<&str>, error: String) -> Self {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(format!(".corrupt.{}", timestamp));
+        let backup = PathBuf::from(backup);
+
+        let backup_path = match fs::copy(path, &backup) {
+            Ok(_) => Some(backup.to_string_lossy().to_string()),
+            Err(e) => {
+                eprintln!("Warning: Could not back up {}: {}", path.display(), e);
+                None
+            }
+        };
+
+        let (mut state, recovered) = contents.map(Self::salvage)
+            .unwrap_or_else(|| (Self::fresh(), Vec::new()));
+        state.recovery = Some(StateRecovery { error, backup_path, recovered });
+        state
+    }
+
+    /// Rebuild a state from the top-level fields of `contents` that still deserialize
+    /// on their own, so e.g. malformed ranking data doesn't cost the triage decisions.
+    /// Returns the state and the names of the fields kept.
+    fn salvage(contents: &str) -> (Self, Vec<String>) {
+        let fresh = Self::fresh();
+        let (Ok(serde_json::Value::Object(fields)), Ok(serde_json::Value::Object(mut merged))) =
+            (serde_json::from_str(contents), serde_json::to_value(&fresh))
+        else {
+            return (fresh, Vec::new());
+        };
+
+        let mut recovered = Vec::new();
+        for (key, value) in fields {
+            let Some(previous) = merged.insert(key.clone(), value) else {
+                merged.remove(&key); // not a state field
+                continue;
+            };
+            if serde_json::from_value::<Self>(serde_json::Value::Object(merged.clone())).is_ok() {
+                recovered.push(key);
+            } else {
+                merged.insert(key, previous);
+            }
+        }
+
+        match serde_json::from_value(serde_json::Value::Object(merged)) {
+            Ok(state) => (state, recovered),
+            Err(_) => (fresh, Vec::new()),
+        }
+    }
+
     /// Save state to file
     pub fn save(&self) -> Result<(), String> {
         let path = Config::state_path();
 
+        if let Some(recovery) = &self.recovery {
+            return Err(format!(
+                "Not saving over {} until the failed load is acknowledged ({})",
+                path.display(), recovery.error
+            ));
+        }
+
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).map_err(|e| e.to_string())?;
         }
 
         let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
-        fs::write(&path, json).map_err(|e| e.to_string())?;
+        write_atomic(&path, json.as_bytes()).map_err(|e| e.to_string())?;
 
         Ok(())
     }
+
+    /// Record a triage decision (after any file move) with its optional reason code,
+    /// making it the most recent undoable entry
+    pub fn record_decision(&mut self, image_id: &str, decision: &str, reason: Option<String>, timestamp: f64) {
+        self.push_decision(image_id, decision, reason, timestamp);
+
+        // A fresh decision invalidates anything that could be redone
+        self.redo_stack.clear();
+        self.redo_reasons.clear();
+    }
+
+    /// Record a decision taken from the redo stack: like `record_decision`, but the
+    /// entries still left to redo stay
+    pub fn redo_decision(&mut self, image_id: &str, decision: &str, reason: Option<String>, timestamp: f64) {
+        self.push_decision(image_id, decision, reason, timestamp);
+    }
+
+    fn push_decision(&mut self, image_id: &str, decision: &str, reason: Option<String>, timestamp: f64) {
+        let old_decision = self.decisions.get(image_id).cloned().unwrap_or("pending".to_string());
+
+        self.decisions.insert(image_id.to_string(), decision.to_string());
+        match reason.filter(|r| !r.trim().is_empty()) {
+            Some(reason) => self.decision_reasons.insert(image_id.to_string(), reason.trim().to_string()),
+            None => self.decision_reasons.remove(image_id),
+        };
+        self.history.push((image_id.to_string(), old_decision, decision.to_string()));
+        self.decided_at.insert(image_id.to_string(), timestamp);
+        self.record_decision_time(timestamp);
+
+        // Trim history
+        if self.history.len() > MAX_HISTORY {
+            let keep = self.history.len() - MAX_HISTORY;
+            self.history = self.history.split_off(keep);
+        }
+    }
+
+    /// Count a skip of `image_id` and return the decision to record for it:
+    /// "deferred" once it has been skipped `max_skips` times, otherwise "skipped"
+    pub fn count_skip(&mut self, image_id: &str, max_skips: Option<usize>) -> &'static str {
+        let count = self.skip_counts.entry(image_id.to_string()).or_insert(0);
+        *count += 1;
+
+        if max_skips.is_some_and(|max| *count >= max) {
+            "deferred"
+        } else {
+            "skipped"
+        }
+    }
+
+    /// Take back one skip of `image_id` (its skip or deferral was undone)
+    pub fn uncount_skip(&mut self, image_id: &str) {
+        if let Some(count) = self.skip_counts.get_mut(image_id) {
+            *count -= 1;
+            if *count == 0 {
+                self.skip_counts.remove(image_id);
+            }
+        }
+    }
+
+    /// Record the time of a triage decision, keeping only the most recent ones
+    pub fn record_decision_time(&mut self, timestamp: f64) {
+        self.recent_decision_times.push(timestamp);
+        if self.recent_decision_times.len() > DECISION_TIME_WINDOW {
+            let keep = self.recent_decision_times.len() - DECISION_TIME_WINDOW;
+            self.recent_decision_times = self.recent_decision_times.split_off(keep);
+        }
+    }
+
+    /// Ranking session for a target folder ("accepted" | "rejected"), active or parked
+    pub fn ranking_for(&self, target: &str) -> Option<&RankingState> {
+        if self.ranking.target == target {
+            Some(&self.ranking)
+        } else {
+            self.parked_rankings.get(target)
+        }
+    }
+
+    fn ranking_for_mut(&mut self, target: &str) -> Option<&mut RankingState> {
+        if self.ranking.target == target {
+            Some(&mut self.ranking)
+        } else {
+            self.parked_rankings.get_mut(target)
+        }
+    }
+
+    /// Put a photo taken out of its ranking session by a cluster decision back, with
+    /// its rating and cluster, once that decision is undone. Does nothing for photos
+    /// that weren't removed that way.
+    pub fn restore_ranked_removal(&mut self, image_id: &str) {
+        let Some(removal) = self.ranked_removals.remove(image_id) else {
+            return;
+        };
+        let Some(ranking) = self.ranking_for_mut(&removal.target) else {
+            return;
+        };
+
+        ranking.ratings.insert(image_id.to_string(), removal.rating);
+        if let Some(cluster_id) = removal.cluster_id {
+            let cluster = ranking.clusters.entry(cluster_id.clone()).or_insert_with(|| Cluster {
+                id: cluster_id.clone(),
+                photo_ids: Vec::new(),
+                representative_id: None,
+                internal_ranking_complete: true,
+            });
+            if !cluster.photo_ids.iter().any(|id| id == image_id) {
+                cluster.photo_ids.push(image_id.to_string());
+            }
+            if removal.representative && cluster.representative_id.is_none() {
+                cluster.representative_id = Some(image_id.to_string());
+            }
+            ranking.photo_to_cluster.insert(image_id.to_string(), cluster_id);
+        }
+        ranking.cluster_count = ranking.clusters.len();
+        ranking.photo_count = ranking.ratings.len();
+        ranking.pair_queue.clear();
+    }
 }
 
 /// Ranking mode state
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RankingState {
+    #[serde(default = "default_ranking_target")]
+    pub target: String, // folder being ranked: "accepted" or "rejected"
     pub initialized: bool,
     pub ratings: HashMap<String, PhotoRating>,
     pub clusters: HashMap<String, Cluster>,
     pub photo_to_cluster: HashMap<String, String>,
     pub comparison_history: Vec<ComparisonRecord>,
     pub total_comparisons: usize,
-    pub phase: String, // "intra_cluster" or "global"
+    pub phase: String, // "intra_cluster", "global" or "tournament"
     pub photo_count: usize,
     pub cluster_count: usize,
+    #[serde(default)]
+    pub pair_queue: Vec<(String, String)>, // pre-selected upcoming pairs
+    #[serde(default)]
+    pub current_pair: Option<(String, String)>, // pair on screen, awaiting a decision
+    #[serde(default)]
+    pub recent_pairs: Vec<(String, String)>, // last compared pairs, oldest first (see ranking::RECENT_PAIRS_LEN)
+    #[serde(default)]
+    pub bracket: Vec<Vec<String>>, // tournament rounds; the last entry collects the current round's winners
+    #[serde(default)]
+    pub winner_id: Option<String>, // tournament champion, once decided
+    #[serde(default)]
+    pub head_to_head: HashMap<String, PairRecord>, // ranking::pair_key -> record trimmed from history
+}
+
+/// Two photos' record against each other from comparisons no longer in
+/// `comparison_history`; "first" is the id that sorts first (see ranking::pair_key)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PairRecord {
+    pub first_wins: usize,
+    pub second_wins: usize,
+    pub ties: usize,
+}
+
+impl Default for RankingState {
+    fn default() -> Self {
+        Self {
+            target: default_ranking_target(),
+            initialized: false,
+            ratings: HashMap::new(),
+            clusters: HashMap::new(),
+            photo_to_cluster: HashMap::new(),
+            comparison_history: Vec::new(),
+            total_comparisons: 0,
+            phase: String::new(),
+            photo_count: 0,
+            cluster_count: 0,
+            pair_queue: Vec::new(),
+            current_pair: None,
+            recent_pairs: Vec::new(),
+            bracket: Vec::new(),
+            winner_id: None,
+            head_to_head: HashMap::new(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_ranking_target() -> String {
+    "accepted".to_string()
 }
 
 /// Rating for a single photo
@@ -126,19 +457,56 @@ pub struct RankingState {
 pub struct PhotoRating {
     pub mu: f64,
     pub sigma: f64,
+    #[serde(default = "default_volatility")]
+    pub volatility: f64, // Glicko-2 volatility: expected fluctuation in mu
     pub matches_played: usize,
+    // Outcomes of this photo's comparisons that were trimmed from comparison_history
+    #[serde(default)]
+    pub wins: usize,
+    #[serde(default)]
+    pub losses: usize,
+    #[serde(default)]
+    pub ties: usize,
 }
 
 impl Default for PhotoRating {
     fn default() -> Self {
         Self {
-            mu: 1500.0,
-            sigma: 350.0,
+            mu: DEFAULT_MU,
+            sigma: DEFAULT_SIGMA,
+            volatility: DEFAULT_VOLATILITY,
             matches_played: 0,
+            wins: 0,
+            losses: 0,
+            ties: 0,
         }
     }
 }
 
+impl PhotoRating {
+    /// Starting rating for a newly initialized photo
+    pub fn initial(rating_config: &RatingConfig) -> Self {
+        Self {
+            mu: rating_config.default_mu,
+            sigma: rating_config.default_sigma,
+            ..Self::default()
+        }
+    }
+}
+
+fn default_volatility() -> f64 {
+    DEFAULT_VOLATILITY
+}
+
+/// A photo's place in a ranking session before a cluster decision moved it out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankedRemoval {
+    pub target: String, // ranking session it was in
+    pub rating: PhotoRating,
+    pub cluster_id: Option<String>,
+    pub representative: bool, // it was its cluster's representative
+}
+
 /// Cluster of similar photos
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Cluster {
@@ -158,6 +526,14 @@ pub struct ComparisonRecord {
     pub left_sigma_before: f64,
     pub right_mu_before: f64,
     pub right_sigma_before: f64,
+    #[serde(default = "default_volatility")]
+    pub left_volatility_before: f64,
+    #[serde(default = "default_volatility")]
+    pub right_volatility_before: f64,
+    #[serde(default)]
+    pub bracket_before: Option<Vec<Vec<String>>>, // tournament bracket before this match
+    #[serde(default)]
+    pub batch_with_previous: bool, // made in the same action as the record before it; undone together
     pub timestamp: f64,
 }
 
@@ -167,7 +543,30 @@ pub struct AppState {
     pub persistent: Mutex<PersistentState>,
     pub image_records: Mutex<Vec<ImageRecord>>,
     pub pending_indices: Mutex<Vec<usize>>,
-    pub photo_hashes: Mutex<HashMap<String, String>>,
+    pub photo_hashes: Mutex<HashMap<String, PhotoHash>>,
+    pub triage_hashes: Mutex<HashMap<String, PhotoHash>>, // source image_id -> hash (triage_hashes.json)
+    pub exif_cache: Mutex<HashMap<String, ExifSummary>>, // image_id -> parsed EXIF
+    pub watcher: Mutex<Option<SourceWatcher>>, // started by initialize_app
+    pub ranking_init_running: AtomicBool, // a background init_ranking pass is hashing
+    pub triage_hashing_running: AtomicBool, // a background precompute_triage_hashes pass is hashing
+    pub folder_scans: Mutex<HashMap<String, FolderScan>>, // destination folder -> last scan
+    pub dimension_cache: Mutex<HashMap<String, CachedDimensions>>, // file path -> size shown in the browser
+    pub state_dirty: AtomicBool, // persistent changed since the last save (see save_later)
+}
+
+/// Photos found in a destination folder (id -> path), reused until the folder changes
+#[derive(Debug, Clone)]
+pub struct FolderScan {
+    pub extensions: Vec<String>,
+    pub mtime: SystemTime, // folder mtime when scanned
+    pub photos: Arc<HashMap<String, PathBuf>>,
+}
+
+/// An image's width and height (None if unreadable), valid while its mtime is unchanged
+#[derive(Debug, Clone)]
+pub struct CachedDimensions {
+    pub mtime: SystemTime,
+    pub dimensions: Option<(u32, u32)>,
 }
 
 impl AppState {
@@ -175,6 +574,7 @@ impl AppState {
         let config = Config::load();
         let persistent = PersistentState::load();
         let photo_hashes = load_photo_hashes();
+        let triage_hashes = load_triage_hashes();
 
         Self {
             config: Mutex::new(config),
@@ -182,33 +582,224 @@ impl AppState {
             image_records: Mutex::new(Vec::new()),
             pending_indices: Mutex::new(Vec::new()),
             photo_hashes: Mutex::new(photo_hashes),
+            triage_hashes: Mutex::new(triage_hashes),
+            exif_cache: Mutex::new(HashMap::new()),
+            watcher: Mutex::new(None),
+            ranking_init_running: AtomicBool::new(false),
+            triage_hashing_running: AtomicBool::new(false),
+            folder_scans: Mutex::new(HashMap::new()),
+            dimension_cache: Mutex::new(HashMap::new()),
+            state_dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Mark the persistent state changed without writing it. The background saver
+    /// writes it within SAVE_DEBOUNCE, so a burst of swipes costs one write.
+    ///
+    /// Used by the frequent per-click actions: swipes, undo/redo, comparisons and
+    /// their undo, and cluster merge/split/decide. Their file moves are already in
+    /// moves.log, so a crash in the window loses at most a few clicks. Everything
+    /// else still saves synchronously: rare or bulk changes that can't be replayed
+    /// (folder add/remove/restore, reset_triage, commit_dry_run, move repairs,
+    /// ranking init/reset/import, tournaments) and anything that switches which
+    /// state is live (set_mode, switch_profile, state recovery).
+    pub fn save_later(&self) {
+        self.state_dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Write a deferred save now, if one is pending. Takes the persistent lock, so
+    /// never call it while holding that.
+    pub fn flush_state(&self) -> Result<(), String> {
+        if !self.state_dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let persistent = self.persistent.lock().unwrap();
+        persistent.save().inspect_err(|_| self.save_later())
+    }
+}
+
+/// Cached perceptual hash plus the file fingerprint it was computed from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhotoHash {
+    pub hash: String,
+    /// Algorithm that produced `hash` (None = older entry, inferred from the hash length)
+    #[serde(default)]
+    pub algorithm: Option<HashAlgorithm>,
+    /// File modification time in milliseconds since epoch (None = unknown, recompute)
+    pub mtime: Option<u64>,
+    /// File size in bytes (None = unknown, recompute)
+    pub size: Option<u64>,
+}
+
+impl PhotoHash {
+    /// Record a freshly computed hash with the file's current mtime/size
+    pub fn new(hash: String, algorithm: HashAlgorithm, path: &Path) -> Self {
+        let (mtime, size) = file_fingerprint(path);
+        Self { hash, algorithm: Some(algorithm), mtime, size }
+    }
+
+    /// Take the file's current mtime/size after a rewrite that leaves its pixels, and
+    /// so the hash, unchanged (such as setting an EXIF rating)
+    pub fn refresh_fingerprint(&mut self, path: &Path) {
+        (self.mtime, self.size) = file_fingerprint(path);
+    }
+
+    /// Whether this hash was made by `algorithm` from the file as it is now on disk
+    pub fn is_current(&self, path: &Path, algorithm: HashAlgorithm) -> bool {
+        // Entries from before aHash were dHash or pHash, told apart by length
+        let same_algorithm = match self.algorithm {
+            Some(stored) => stored == algorithm,
+            None => algorithm != HashAlgorithm::Ahash && self.hash.len() == algorithm.hex_len(),
+        };
+        if !same_algorithm || self.mtime.is_none() || self.size.is_none() {
+            return false;
         }
+        file_fingerprint(path) == (self.mtime, self.size)
     }
 }
 
+/// (mtime in ms, size in bytes) of a file, None where unavailable
+fn file_fingerprint(path: &Path) -> (Option<u64>, Option<u64>) {
+    match fs::metadata(path) {
+        Ok(meta) => {
+            let mtime = meta.modified().ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64);
+            (mtime, Some(meta.len()))
+        }
+        Err(_) => (None, None),
+    }
+}
+
+/// On-disk hash cache entry: current struct format, or the old flat hex string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredPhotoHash {
+    Entry(PhotoHash),
+    Legacy(String),
+}
+
 /// Load cached photo hashes from file
-pub fn load_photo_hashes() -> HashMap<String, String> {
-    let path = Config::hashes_path();
+/// Entries from the old `id -> hex` format load with unknown mtime/size so they get recomputed
+pub fn load_photo_hashes() -> HashMap<String, PhotoHash> {
+    load_hash_file(&Config::hashes_path())
+}
+
+/// Save photo hashes to file
+pub fn save_photo_hashes(hashes: &HashMap<String, PhotoHash>) -> Result<(), String> {
+    save_hash_file(&Config::hashes_path(), hashes)
+}
+
+/// Load cached hashes of source folder photos, keyed by image_id
+pub fn load_triage_hashes() -> HashMap<String, PhotoHash> {
+    load_hash_file(&Config::triage_hashes_path())
+}
+
+/// Save hashes of source folder photos to file
+pub fn save_triage_hashes(hashes: &HashMap<String, PhotoHash>) -> Result<(), String> {
+    save_hash_file(&Config::triage_hashes_path(), hashes)
+}
+
+fn load_hash_file(path: &Path) -> HashMap<String, PhotoHash> {
     if path.exists() {
-        if let Ok(contents) = fs::read_to_string(&path) {
-            if let Ok(hashes) = serde_json::from_str(&contents) {
-                return hashes;
+        if let Ok(contents) = fs::read_to_string(path) {
+            if let Ok(hashes) = serde_json::from_str::<HashMap<String, StoredPhotoHash>>(&contents) {
+                return hashes.into_iter()
+                    .map(|(id, stored)| {
+                        let entry = match stored {
+                            StoredPhotoHash::Entry(entry) => entry,
+                            StoredPhotoHash::Legacy(hash) => PhotoHash { hash, algorithm: None, mtime: None, size: None },
+                        };
+                        (id, entry)
+                    })
+                    .collect();
             }
         }
     }
     HashMap::new()
 }
 
-/// Save photo hashes to file
-pub fn save_photo_hashes(hashes: &HashMap<String, String>) -> Result<(), String> {
-    let path = Config::hashes_path();
-
+fn save_hash_file(path: &Path, hashes: &HashMap<String, PhotoHash>) -> Result<(), String> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
 
     let json = serde_json::to_string_pretty(hashes).map_err(|e| e.to_string())?;
-    fs::write(&path, json).map_err(|e| e.to_string())?;
+    write_atomic(path, json.as_bytes()).map_err(|e| e.to_string())?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_salvage_keeps_decisions_when_ranking_is_malformed() {
+        let mut state = PersistentState::fresh();
+        state.decisions.insert("a".to_string(), "accepted".to_string());
+        let mut json = serde_json::to_value(&state).unwrap();
+        json["ranking"] = serde_json::json!({ "ratings": "not a map" });
+
+        let (salvaged, recovered) = PersistentState::salvage(&json.to_string());
+        assert_eq!(salvaged.decisions.get("a").map(String::as_str), Some("accepted"));
+        assert!(recovered.contains(&"decisions".to_string()));
+        assert!(!recovered.contains(&"ranking".to_string()));
+
+        let (salvaged, recovered) = PersistentState::salvage("{\"decisions\": {\"a\": ");
+        assert!(salvaged.decisions.is_empty() && recovered.is_empty());
+    }
+
+    #[test]
+    fn test_redo_decision_keeps_remaining_redo_entries() {
+        let mut state = PersistentState {
+            redo_stack: vec![
+                ("b".to_string(), "pending".to_string(), "accepted".to_string()),
+                ("a".to_string(), "pending".to_string(), "rejected".to_string()),
+            ],
+            redo_reasons: HashMap::from([("a".to_string(), "blurry".to_string())]),
+            ..PersistentState::default()
+        };
+
+        // Redoing "a" is recorded like a swipe, reason and ETA sample included
+        state.redo_stack.pop();
+        let reason = state.redo_reasons.remove("a");
+        state.redo_decision("a", "rejected", reason, 10.0);
+        assert_eq!(state.decision_reasons.get("a").map(String::as_str), Some("blurry"));
+        assert_eq!(state.history.last().unwrap().2, "rejected");
+        assert_eq!(state.recent_decision_times, vec![10.0]);
+        assert_eq!(state.redo_stack.len(), 1);
+
+        // A fresh decision drops what is left to redo
+        state.record_decision("c", "accepted", None, 11.0);
+        assert!(state.redo_stack.is_empty());
+    }
+
+    #[test]
+    fn test_restore_ranked_removal_brings_back_rating_and_cluster() {
+        let rating = PhotoRating { mu: 1700.0, matches_played: 4, ..PhotoRating::default() };
+        let mut state = PersistentState::default();
+        state.ranked_removals.insert("a".to_string(), RankedRemoval {
+            target: "accepted".to_string(),
+            rating: rating.clone(),
+            cluster_id: Some("c1".to_string()),
+            representative: true,
+        });
+
+        // Its cluster was emptied and dropped by the decision
+        state.restore_ranked_removal("a");
+        let ranking = &state.ranking;
+        assert_eq!(ranking.ratings["a"].mu, rating.mu);
+        assert_eq!(ranking.ratings["a"].matches_played, 4);
+        assert_eq!(ranking.photo_to_cluster.get("a").map(String::as_str), Some("c1"));
+        assert_eq!(ranking.clusters["c1"].photo_ids, vec!["a".to_string()]);
+        assert_eq!(ranking.clusters["c1"].representative_id.as_deref(), Some("a"));
+        assert_eq!((ranking.photo_count, ranking.cluster_count), (1, 1));
+        assert!(state.ranked_removals.is_empty());
+
+        // Only once
+        state.ranking.ratings.clear();
+        state.restore_ranked_removal("a");
+        assert!(state.ranking.ratings.is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+//! Thumbnail generation - downscaled previews for export and browsing
+
+use crate::config::Config;
+use crate::decode::open_image;
+use image::ImageFormat;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Bumped when thumbnails start rendering differently, so stale cache entries aren't reused
+const THUMBNAIL_CACHE_VERSION: u32 = 2; // 2: EXIF orientation applied
+
+/// Decode an image (RAW and HEIC through their own decoders), turned upright, and
+/// downscale it to fit within `max_dim`, encoded as JPEG bytes
+pub fn render_thumbnail(image_path: &Path, max_dim: u32) -> Result<Vec<u8>, String> {
+    let (img, _) = open_image(image_path)?;
+
+    // JPEG has no alpha channel, so flatten to RGB before encoding
+    let thumb = img.thumbnail(max_dim, max_dim).to_rgb8();
+
+    let mut bytes = Vec::new();
+    thumb.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg)
+        .map_err(|e| format!("Could not encode thumbnail: {}", e))?;
+
+    Ok(bytes)
+}
+
+/// Return the path of a cached JPEG thumbnail, generating it on a cache miss.
+/// Cache entries are keyed by source path, modification time, and size, so an
+/// edited file gets a fresh thumbnail.
+pub fn cached_thumbnail(image_path: &Path, max_dim: u32) -> Result<PathBuf, String> {
+    let metadata = fs::metadata(image_path)
+        .map_err(|e| format!("Image not found: {} ({})", image_path.display(), e))?;
+    let mtime = metadata.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+
+    let key = md5::compute(format!(
+        "{}|{}|{}|{}", image_path.to_string_lossy(), mtime, max_dim, THUMBNAIL_CACHE_VERSION
+    ));
+    let cache_path = Config::thumbnails_dir().join(format!("{:x}.jpg", key));
+
+    if cache_path.exists() {
+        return Ok(cache_path);
+    }
+
+    let bytes = render_thumbnail(image_path, max_dim)?;
+    fs::create_dir_all(Config::thumbnails_dir()).map_err(|e| e.to_string())?;
+    fs::write(&cache_path, bytes).map_err(|e| format!("Could not write thumbnail: {}", e))?;
+
+    Ok(cache_path)
+}
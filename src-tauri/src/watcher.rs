@@ -0,0 +1,137 @@
+//! Watching source folders for photos that arrive during a session
+
+use crate::image_manager::{
+    build_pending_indices, has_supported_extension, record_id, ExcludeFilter,
+};
+use crate::metadata::capture_timestamp;
+use crate::state::{AppState, ImageRecord};
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Quiet period before a burst of file events is handled as one batch
+const DEBOUNCE_DELAY: Duration = Duration::from_secs(1);
+
+/// Watches the source folders; dropping it stops all watches
+pub struct SourceWatcher {
+    debouncer: Debouncer<RecommendedWatcher>,
+    folders: Vec<String>, // folders currently being watched
+}
+
+impl SourceWatcher {
+    /// Start watching `folders`, adding new images to the triage queue as they appear
+    pub fn start(app: AppHandle, folders: &[String]) -> Result<Self, String> {
+        let debouncer = new_debouncer(DEBOUNCE_DELAY, move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                let paths: Vec<PathBuf> = events.into_iter().map(|event| event.path).collect();
+                add_new_images(&app, &paths);
+            }
+            Err(e) => eprintln!("Warning: Folder watcher error: {:?}", e),
+        })
+        .map_err(|e| format!("Failed to start folder watcher: {}", e))?;
+
+        let mut watcher = Self {
+            debouncer,
+            folders: Vec::new(),
+        };
+        watcher.set_folders(folders);
+        Ok(watcher)
+    }
+
+    /// Watch exactly `folders`: removed folders are unwatched, new ones watched
+    pub fn set_folders(&mut self, folders: &[String]) {
+        let mut watched = Vec::new();
+
+        for folder in &self.folders {
+            if folders.contains(folder) {
+                watched.push(folder.clone());
+            } else if let Err(e) = self.debouncer.watcher().unwatch(Path::new(folder)) {
+                eprintln!("Warning: Could not stop watching {}: {}", folder, e);
+            }
+        }
+
+        for folder in folders {
+            if watched.contains(folder) {
+                continue;
+            }
+            match self.debouncer.watcher().watch(Path::new(folder), RecursiveMode::Recursive) {
+                Ok(()) => watched.push(folder.clone()),
+                Err(e) => eprintln!("Warning: Could not watch {}: {}", folder, e),
+            }
+        }
+
+        self.folders = watched;
+    }
+}
+
+/// Append records for new supported files under the source folders, rebuild the
+/// pending queue, and tell the frontend how many were added
+fn add_new_images(app: &AppHandle, paths: &[PathBuf]) {
+    let state = app.state::<AppState>();
+
+    let added = {
+        let config = state.config.lock().unwrap();
+        let persistent = state.persistent.lock().unwrap();
+        let mut image_records = state.image_records.lock().unwrap();
+
+        let extensions = config.scan_extensions();
+        let by_date = config.order == "date_asc" || config.order == "date_desc";
+        let exclude = ExcludeFilter::new(&config.exclude_patterns);
+        let mut known: HashSet<String> = image_records.iter().map(|r| r.id.clone()).collect();
+        let mut added = 0;
+
+        for path in paths {
+            if !path.is_file() || !has_supported_extension(path, &extensions) {
+                continue;
+            }
+
+            // Longest matching source folder, in case folders are nested
+            let Some(folder) = config.source_folders.iter()
+                .filter(|folder| path.starts_with(folder.as_str()))
+                .max_by_key(|folder| folder.len())
+            else {
+                continue;
+            };
+            if config.disabled_folders.contains(folder) {
+                continue;
+            }
+            let Ok(rel_path) = path.strip_prefix(folder.as_str()) else {
+                continue;
+            };
+            if config.scan_depth.is_some_and(|depth| rel_path.components().count() > depth)
+                || exclude.excludes_path(rel_path)
+            {
+                continue;
+            }
+
+            let id = record_id(path, config.content_ids);
+            if !known.insert(id.clone()) {
+                continue;
+            }
+
+            image_records.push(ImageRecord {
+                id,
+                source_folder: folder.clone(),
+                relative_path: rel_path.to_string_lossy().to_string(),
+                timestamp: if by_date { capture_timestamp(path) } else { None },
+                size: std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0),
+            });
+            added += 1;
+        }
+
+        if added > 0 {
+            let mut pending_indices = state.pending_indices.lock().unwrap();
+            *pending_indices = build_pending_indices(&image_records, &persistent.decisions);
+        }
+        added
+    };
+
+    if added > 0 {
+        if let Err(e) = app.emit("images-added", added) {
+            eprintln!("Warning: Could not emit images-added: {}", e);
+        }
+    }
+}
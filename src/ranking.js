@@ -98,6 +98,12 @@ function initRanking() {
     // Keyboard shortcuts for ranking
     document.addEventListener('keydown', onRankingKeyDown);
 
+    // The last comparison left no pairs to compare
+    window.__TAURI__.event.listen('ranking-complete', (event) => {
+        updateRankingStats(event.payload);
+        showRankingDone();
+    });
+
     // Check initial mode
     checkMode();
     } catch (e) {
@@ -152,6 +158,36 @@ async function switchMode(mode) {
     }
 }
 
+/**
+ * Run init_ranking and, if it started a background pass, wait for it to finish
+ * while showing hashing progress. Resolves with the ranking stats.
+ */
+async function runRankingInit() {
+    let resolveInit, rejectInit;
+    const finished = new Promise((resolve, reject) => {
+        resolveInit = resolve;
+        rejectInit = reject;
+    });
+
+    const listen = window.__TAURI__.event.listen;
+    const unlisteners = await Promise.all([
+        listen('ranking-init-progress', (event) => {
+            const { done, total } = event.payload;
+            document.getElementById('initStatus').textContent =
+                `Computing photo hashes... ${done} / ${total}`;
+        }),
+        listen('ranking-init-complete', (event) => resolveInit(event.payload)),
+        listen('ranking-init-error', (event) => rejectInit(event.payload)),
+    ]);
+
+    try {
+        const result = await invoke('init_ranking');
+        return result.status === 'ready' ? result.stats : await finished;
+    } finally {
+        unlisteners.forEach(unlisten => unlisten());
+    }
+}
+
 /**
  * Initialize ranking mode (first time)
  */
@@ -160,7 +196,7 @@ async function initializeRanking() {
     document.getElementById('initStatus').textContent = 'Scanning accepted photos and computing hashes...';
 
     try {
-        const result = await invoke('init_ranking');
+        const result = await runRankingInit();
         rankingInitialized = true;
         rankingInitOverlay.style.display = 'none';
 
@@ -179,8 +215,13 @@ async function initializeRanking() {
  * Update ranking stats display
  */
 function updateRankingStats(stats) {
-    comparisonsCount.textContent = `${stats.total_comparisons} comparisons`;
-    photosRanked.textContent = `${stats.total_photos} photos`;
+    const remaining = stats.estimated_remaining_comparisons;
+    comparisonsCount.textContent = remaining != null
+        ? `${stats.total_comparisons} comparisons (about ${remaining} left)`
+        : `${stats.total_comparisons} comparisons`;
+    photosRanked.textContent = stats.uncompared > 0
+        ? `${stats.total_photos} photos (${stats.uncompared} not yet compared)`
+        : `${stats.total_photos} photos`;
     rankingPhase.textContent = `Phase: ${stats.phase}`;
 }
 
@@ -266,7 +307,7 @@ async function rescanPhotos() {
 
     try {
         // Re-initialize ranking (will add new photos)
-        const result = await invoke('init_ranking');
+        const result = await runRankingInit();
         rankingInitOverlay.style.display = 'none';
         updateRankingStats(result);
         await loadNextPair();
@@ -88,6 +88,19 @@ async function init() {
     setupScreen = document.getElementById('setupScreen');
     mainApp = document.getElementById('mainApp');
 
+    // A state file that failed to load was backed up; nothing is saved until the user knows
+    const recovery = await invoke('get_state_recovery');
+    if (recovery) {
+        const recovered = recovery.recovered.length > 0
+            ? `Recovered: ${recovery.recovered.join(', ')}.`
+            : 'Nothing could be recovered; starting fresh.';
+        const backup = recovery.backup_path
+            ? `The original file was backed up to ${recovery.backup_path}.`
+            : 'The original file could not be backed up.';
+        alert(`Your saved progress could not be read (${recovery.error}).\n\n${recovered}\n${backup}`);
+        await invoke('acknowledge_state_recovery');
+    }
+
     // Check if config is valid
     const isValid = await invoke('is_config_valid');
 
@@ -104,6 +117,18 @@ async function init() {
     bindEvents();
     initSettings();
     initSetup();
+
+    // New photos dropped into a source folder while the app is open
+    window.__TAURI__.event.listen('images-added', async () => {
+        if (doneMessage.style.display === 'flex') {
+            await loadCurrentImage();
+        }
+    });
+
+    // The last pending photo was just decided
+    window.__TAURI__.event.listen('triage-complete', (event) => {
+        showDoneMessage({ stats: event.payload });
+    });
 }
 
 /**
@@ -129,6 +154,7 @@ async function initSetup() {
     // Load existing config if any
     const config = await invoke('get_config');
     setupConfig = {
+        ...config,
         source_folders: config.source_folders || [],
         accepted_folder: config.accepted_folder || '',
         rejected_folder: config.rejected_folder || ''
@@ -264,7 +290,9 @@ async function loadCurrentImage() {
 function updateStats(stats) {
     acceptedCount.textContent = stats.accepted;
     rejectedCount.textContent = stats.rejected;
-    skippedCount.textContent = stats.skipped;
+    skippedCount.textContent = stats.deferred
+        ? `${stats.skipped} (+${stats.deferred} deferred)`
+        : stats.skipped;
 }
 
 /**
@@ -770,6 +798,7 @@ function initSettings() {
         btn.addEventListener('click', () => openBrowser(btn.dataset.target));
     });
     document.getElementById('addSourceFolderBtn').addEventListener('click', () => openBrowser('source'));
+    document.getElementById('restoreFolderBtn').addEventListener('click', restoreRemovedFolder);
 
     document.getElementById('closeBrowser').addEventListener('click', closeBrowser);
     document.getElementById('browserCancelBtn').addEventListener('click', closeBrowser);
@@ -819,11 +848,20 @@ async function loadFolders() {
 
         data.folders.forEach(folder => {
             const item = document.createElement('div');
-            item.className = 'folder-item' + (folder.exists ? '' : ' missing');
+            const unreachable = folder.status === 'unreachable';
+            item.className = 'folder-item' + (folder.exists ? '' : unreachable ? ' unreachable' : ' missing')
+                + (folder.enabled ? '' : ' disabled');
+            const stats = !folder.enabled
+                ? 'Disabled - progress is kept until it is enabled'
+                : unreachable
+                    ? 'Unreachable - progress is kept until it is back'
+                    : `${folder.decided_count}/${folder.photo_count} triaged`;
             item.innerHTML = `
+                <input type="checkbox" class="folder-enabled-toggle" data-path="${folder.path}"
+                    title="Include in triage" ${folder.enabled ? 'checked' : ''}>
                 <div class="folder-info">
                     <span class="folder-path" title="${folder.path}">${folder.path}</span>
-                    <span class="folder-stats">${folder.decided_count}/${folder.photo_count} triaged</span>
+                    <span class="folder-stats">${stats}</span>
                 </div>
                 <button class="remove-folder-btn" data-path="${folder.path}">&times;</button>
             `;
@@ -833,6 +871,14 @@ async function loadFolders() {
         document.querySelectorAll('.remove-folder-btn').forEach(btn => {
             btn.addEventListener('click', () => confirmDeleteFolder(btn.dataset.path));
         });
+        document.querySelectorAll('.folder-enabled-toggle').forEach(toggle => {
+            toggle.addEventListener('change', () => setFolderEnabled(toggle.dataset.path, toggle.checked));
+        });
+
+        const restoreBtn = document.getElementById('restoreFolderBtn');
+        restoreBtn.style.display = data.last_removed_folder ? 'block' : 'none';
+        restoreBtn.textContent = `Undo removal of ${data.last_removed_folder}`;
+        restoreBtn.title = data.last_removed_folder || '';
     } catch (e) {
         console.error('Error loading folders:', e);
     }
@@ -983,6 +1029,28 @@ async function deleteFolder(clearDecisions) {
     }
 }
 
+async function setFolderEnabled(path, enabled) {
+    try {
+        await invoke('set_folder_enabled', { path, enabled });
+        await loadFolders();
+        await loadCurrentImage();
+    } catch (e) {
+        console.error('Error toggling folder:', e);
+        alert('Failed to update folder: ' + e);
+    }
+}
+
+async function restoreRemovedFolder() {
+    try {
+        await invoke('restore_last_removed_folder');
+        await loadFolders();
+        await loadCurrentImage();
+    } catch (e) {
+        console.error('Error restoring folder:', e);
+        alert('Failed to restore folder: ' + e);
+    }
+}
+
 // ==================== PHOTO BROWSER ====================
 
 let photoBrowserModal, photoBrowserGrid, browserTitle;
@@ -1077,7 +1145,8 @@ async function loadBrowserPhotos() {
             status: browserStatus,
             sort: browserSort,
             page: browserPage,
-            perPage: browserPerPage
+            perPage: browserPerPage,
+            includeThumbnails: true
         });
 
         browserTotalPages = data.total_pages;
@@ -1119,7 +1188,7 @@ async function loadBrowserPhotos() {
 
             item.innerHTML = `
                 <span class="rank">#${rank}</span>
-                <img src="${convertFileSrc(photo.file_path)}" alt="${photo.filename}" loading="lazy">
+                <img src="${convertFileSrc(photo.thumbnail_path || photo.file_path)}" alt="${photo.filename}" loading="lazy">
                 ${scoreHtml}
                 <div class="filename" title="${photo.filename}">${photo.filename}</div>
             `;